@@ -0,0 +1,68 @@
+use std::time::Instant;
+
+use sql_rs::data::Database;
+use sql_rs::parse::parse_queries;
+
+const ROWS: u32 = 200_000;
+
+const QUERIES: &[&str] = &[
+    "select count(*) from events where amount > 500 and kind = 'b';",
+    "select count(*), sum(amount), min(amount), max(amount), avg(amount) from events;",
+    "select kind, count(*), sum(amount), min(amount), max(amount), avg(amount) from events group by kind;",
+    "select flag, count(amount), sum(amount + id) from events where id > 1000 or flag = true group by flag;",
+    "select * from events where amount >= 990 and flag = false;",
+];
+
+/// Run analytic queries over a large table both row by row and vectorized, checking they agree.
+fn main() {
+    let mut seed =
+        String::from("create table events (id number, kind text, amount number, flag boolean);\n");
+    for id in 0..ROWS {
+        let kind = ["a", "b", "c", "d"][(id % 4) as usize];
+        let flag = id % 3 == 0;
+        // every tenth event has no amount
+        if id % 10 == 0 {
+            seed += &format!(
+                "insert into events (id, kind, flag) values ({}, '{}', {});\n",
+                id, kind, flag
+            );
+        } else {
+            seed += &format!(
+                "insert into events (id, kind, amount, flag) values ({}, '{}', {}, {});\n",
+                id,
+                kind,
+                id * 7919 % 1000,
+                flag
+            );
+        }
+    }
+
+    let mut database = Database::new();
+    for query in parse_queries(&seed).unwrap().0 {
+        database.execute(query).unwrap();
+    }
+
+    for query in QUERIES {
+        println!("{}", query);
+
+        let mut outputs = Vec::new();
+        for vectorized in [false, true] {
+            database.vectorized = vectorized;
+            let query = parse_queries(query).unwrap().0.remove(0);
+
+            let start = Instant::now();
+            let output = database.execute(query).unwrap().to_string();
+            let elapsed = start.elapsed();
+
+            let mode = if vectorized {
+                "vectorized"
+            } else {
+                "row by row"
+            };
+            println!("  {:>10}: {:8.2} ms", mode, elapsed.as_secs_f64() * 1000.0);
+            outputs.push(output);
+        }
+
+        assert_eq!(outputs[0], outputs[1], "outputs differ");
+    }
+}
@@ -14,17 +14,12 @@ fn main() {
         stdout.flush().unwrap();
         reader.read_line(&mut line).unwrap();
 
-        if line == "" {
+        if line.is_empty() {
             println!();
             break;
         }
 
         let queries = parse_queries(&line);
         dbg!(&queries);
-
-        // use result
-        match queries {
-            _ => {}
-        }
     }
 }
@@ -9,17 +9,31 @@ use super::Datatype;
 pub struct Column {
     pub name: String,
     pub datatype: Datatype,
+    /// Left out of an unqualified `*`, though it can still be named. A column of a table in a
+    /// USING (or NATURAL) join is hidden, in favour of the column it's merged into.
+    pub hidden: bool,
 }
 
-impl Display for Column {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        match self.name.find(".") {
-            None => write!(f, "{}", self.name),
-            Some(idx) => {
-                let (_, name) = self.name.split_at(idx + 1);
+impl Column {
+    pub fn new(name: String, datatype: Datatype) -> Self {
+        Column {
+            name,
+            datatype,
+            hidden: false,
+        }
+    }
 
-                write!(f, "{}", name)
-            }
+    /// The name of the column, without the alias of the table it's from.
+    pub fn unqualified_name(&self) -> &str {
+        match self.name.find('.') {
+            None => &self.name,
+            Some(idx) => &self.name[idx + 1..],
         }
     }
 }
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.unqualified_name())
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::iter;
+
+use super::{Index, Row, Table, Value, ValueError};
+use crate::execute::evaluate;
+use crate::parse::parse_expression;
+
+/// A rule every row of a table must satisfy.
+///
+/// Constraints are checked whenever rows are inserted or updated, and a statement which would
+/// leave the table violating one of them fails without changing the table. Columns are referred
+/// to by name so that constraints survive columns being added or dropped around them.
+/// Expressions are stored as SQL text, and parsed again whenever they're needed.
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    /// The listed columns uniquely identify a row, and can't be null. A table has at most one.
+    PrimaryKey(Vec<String>),
+    /// No two rows share the same values for the listed columns, unless one of them is null.
+    Unique(Vec<String>),
+    NotNull(String),
+    /// The value given to a column when a row is inserted without it. Not a rule as such, but
+    /// declared alongside the constraints, and follows its column around in the same way.
+    Default {
+        column: String,
+        expression: String,
+    },
+    /// An expression which mustn't evaluate to false for any row (null is fine).
+    Check(String),
+    /// Every row's values for `columns` (unless one is null) are the values of
+    /// `referenced_columns` for some row of `table`, which are a key of that table.
+    ForeignKey {
+        columns: Vec<String>,
+        table: String,
+        referenced_columns: Vec<String>,
+        on_delete: ReferentialAction,
+    },
+}
+
+/// What happens to the rows referencing a row through a foreign key when that row is deleted.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ReferentialAction {
+    /// The delete fails.
+    #[default]
+    Restrict,
+    /// The referencing rows are deleted too.
+    Cascade,
+    /// The referencing columns of the referencing rows are set to null.
+    SetNull,
+}
+
+impl Constraint {
+    /// The columns this constraint involves.
+    pub fn columns(&self) -> Vec<String> {
+        match self {
+            Constraint::PrimaryKey(columns)
+            | Constraint::Unique(columns)
+            | Constraint::ForeignKey { columns, .. } => columns.clone(),
+            Constraint::NotNull(column) | Constraint::Default { column, .. } => {
+                vec![column.clone()]
+            }
+            Constraint::Check(expression) => {
+                let mut columns: Vec<_> = parse_expression(expression)
+                    .unwrap()
+                    .column_identifiers()
+                    .iter()
+                    .map(|i| i.as_string())
+                    .collect();
+                columns.sort();
+                columns.dedup();
+                columns
+            }
+        }
+    }
+
+    pub fn rename_column(&mut self, from: &str, to: &str) {
+        match self {
+            Constraint::PrimaryKey(columns)
+            | Constraint::Unique(columns)
+            | Constraint::ForeignKey { columns, .. } => rename(columns, from, to),
+            Constraint::NotNull(column) | Constraint::Default { column, .. } => {
+                if column == from {
+                    *column = to.to_owned();
+                }
+            }
+            Constraint::Check(expression) => {
+                let mut renamed = parse_expression(expression).unwrap();
+                renamed.rename_column(from, to);
+                *expression = renamed.to_string();
+            }
+        }
+    }
+
+    /// Follow a column of the table referenced by this constraint being renamed.
+    pub fn rename_referenced_column(&mut self, referenced_table: &str, from: &str, to: &str) {
+        if let Constraint::ForeignKey {
+            table,
+            referenced_columns,
+            ..
+        } = self
+        {
+            if table == referenced_table {
+                rename(referenced_columns, from, to);
+            }
+        }
+    }
+
+    /// Follow the table referenced by this constraint being renamed.
+    pub fn rename_referenced_table(&mut self, from: &str, to: &str) {
+        if let Constraint::ForeignKey { table, .. } = self {
+            if table == from {
+                *table = to.to_owned();
+            }
+        }
+    }
+
+    /// The columns of the referenced table this constraint involves, if it's a foreign key.
+    pub fn referenced_columns(&self) -> &[String] {
+        match self {
+            Constraint::ForeignKey {
+                referenced_columns, ..
+            } => referenced_columns,
+            _ => &[],
+        }
+    }
+
+    /// The table referenced by this constraint, if it's a foreign key.
+    pub fn referenced_table(&self) -> Option<&str> {
+        match self {
+            Constraint::ForeignKey { table, .. } => Some(table),
+            _ => None,
+        }
+    }
+}
+
+fn rename(columns: &mut [String], from: &str, to: &str) {
+    for column in columns {
+        if column == from {
+            *column = to.to_owned();
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Constraint::PrimaryKey(columns) => write!(f, "primary key ({})", columns.join(", ")),
+            Constraint::Unique(columns) => write!(f, "unique ({})", columns.join(", ")),
+            Constraint::NotNull(column) => write!(f, "not null ({})", column),
+            Constraint::Default { column, expression } => {
+                write!(f, "default ({}) {}", column, expression)
+            }
+            Constraint::Check(expression) => write!(f, "check ({})", expression),
+            Constraint::ForeignKey {
+                columns,
+                table,
+                referenced_columns,
+                ..
+            } => write!(
+                f,
+                "foreign key ({}) references {} ({})",
+                columns.join(", "),
+                table,
+                referenced_columns.join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConstraintViolation {
+    #[error("duplicate key ({values}) violates {constraint} constraint")]
+    DuplicateKey { constraint: String, values: String },
+    #[error("null value in column `{column}` violates {constraint} constraint")]
+    NullKey { constraint: String, column: String },
+    #[error("null value in column `{column}` violates not null constraint")]
+    NullValue { column: String },
+    #[error("row violates {constraint} constraint")]
+    CheckFailed { constraint: String },
+    #[error("key ({values}) violates {constraint} constraint, no such row is referenced")]
+    MissingReference { constraint: String, values: String },
+    #[error("key ({values}) is still referenced from table `{table}` by {constraint} constraint")]
+    StillReferenced {
+        constraint: String,
+        table: String,
+        values: String,
+    },
+    #[error(transparent)]
+    Value(#[from] ValueError),
+}
+
+impl Table {
+    /// Check that inserting `row` wouldn't violate any constraint of the table.
+    pub fn check_insert(&self, row: &Row) -> Result<(), ConstraintViolation> {
+        self.check_row_constraints(iter::once(row))?;
+
+        for constraint in self.keys() {
+            let indices = self.constraint_indices(constraint);
+
+            if let Some(key) = key(constraint, &indices, row)? {
+                let duplicate = match self.key_index(constraint) {
+                    Some(index) => !index.get(&key).is_empty(),
+                    None => self
+                        .rows
+                        .iter()
+                        .any(|existing| key_values(&indices, existing) == key),
+                };
+
+                if duplicate {
+                    return Err(duplicate_key(constraint, &key));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that replacing the rows of the table with `rows` wouldn't violate any constraint
+    /// of the table.
+    pub fn check_rows(&self, rows: &[Row]) -> Result<(), ConstraintViolation> {
+        self.check_row_constraints(rows.iter())?;
+
+        for constraint in self.keys() {
+            check_distinct_keys(constraint, &self.constraint_indices(constraint), rows)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that replacing the rows of the table at the positions in `changed` wouldn't
+    /// violate any constraint of the table. Only the changed rows are checked, looking their keys
+    /// up in the table's indexes.
+    pub fn check_update(&self, changed: &BTreeMap<usize, Row>) -> Result<(), ConstraintViolation> {
+        self.check_row_constraints(changed.values())?;
+
+        for constraint in self.keys() {
+            let indices = self.constraint_indices(constraint);
+            let index = match self.key_index(constraint) {
+                Some(index) => index,
+                None => {
+                    check_distinct_keys(constraint, &indices, self.updated_rows(changed))?;
+                    continue;
+                }
+            };
+
+            let mut seen = BTreeSet::new();
+            for row in changed.values() {
+                if let Some(key) = key(constraint, &indices, row)? {
+                    // the key of another changed row, or of a row which isn't changed, and so
+                    // still has the key it's indexed under
+                    let duplicate = seen.contains(&key)
+                        || index.get(&key).iter().any(|p| !changed.contains_key(p));
+                    if duplicate {
+                        return Err(duplicate_key(constraint, &key));
+                    }
+
+                    seen.insert(key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The values of a new row before any are given explicitly: each column's default, or null.
+    pub fn default_values(&self) -> Result<Vec<Value>, ValueError> {
+        self.columns
+            .iter()
+            .map(|column| self.default_value(&column.name))
+            .collect()
+    }
+
+    /// A column's default, converted to its datatype, or null.
+    pub fn default_value(&self, column: &str) -> Result<Value, ValueError> {
+        let expression = self
+            .constraints
+            .iter()
+            .find_map(|constraint| match constraint {
+                Constraint::Default {
+                    column: c,
+                    expression,
+                } if c == column => Some(expression),
+                _ => None,
+            });
+
+        match expression {
+            Some(expression) => {
+                let datatype = self.columns[self.find_column(column).unwrap()].datatype;
+
+                evaluate(&parse_expression(expression).unwrap(), None, None)?.cast(datatype)
+            }
+            None => Ok(Value::Null),
+        }
+    }
+
+    /// Check the constraints which apply to each row on its own.
+    fn check_row_constraints<'a>(
+        &self,
+        rows: impl Iterator<Item = &'a Row> + Clone,
+    ) -> Result<(), ConstraintViolation> {
+        for constraint in &self.constraints {
+            match constraint {
+                Constraint::NotNull(column) => {
+                    let idx = self.find_column(column).unwrap();
+
+                    if rows.clone().any(|row| matches!(row.0[idx], Value::Null)) {
+                        return Err(ConstraintViolation::NullValue {
+                            column: column.clone(),
+                        });
+                    }
+                }
+                Constraint::Check(expression) => {
+                    let expression = parse_expression(expression).unwrap();
+
+                    for row in rows.clone() {
+                        let result = evaluate(&expression, Some((&self.columns, row)), None)?;
+
+                        if result == Value::Boolean(false) {
+                            return Err(ConstraintViolation::CheckFailed {
+                                constraint: constraint.to_string(),
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &Constraint> {
+        self.constraints
+            .iter()
+            .filter(|c| matches!(c, Constraint::PrimaryKey(_) | Constraint::Unique(_)))
+    }
+
+    /// The index over exactly the columns of a key, which every key has unless the table was
+    /// stored before keys were given indexes.
+    fn key_index(&self, constraint: &Constraint) -> Option<&Index> {
+        let columns = constraint.columns();
+
+        self.indexes.iter().find(|index| index.columns == columns)
+    }
+
+    fn constraint_indices(&self, constraint: &Constraint) -> Vec<usize> {
+        constraint
+            .columns()
+            .iter()
+            .map(|c| self.find_column(c).unwrap())
+            .collect()
+    }
+}
+
+/// The values of a row covered by a key. Keys containing a null are never duplicates, so
+/// they're `None`, unless the key is a primary key, which can't be null at all.
+fn key(
+    constraint: &Constraint,
+    indices: &[usize],
+    row: &Row,
+) -> Result<Option<Vec<Value>>, ConstraintViolation> {
+    let key = key_values(indices, row);
+
+    match key.iter().position(|v| matches!(v, Value::Null)) {
+        None => Ok(Some(key)),
+        Some(i) => match constraint {
+            Constraint::PrimaryKey(columns) => Err(ConstraintViolation::NullKey {
+                constraint: constraint.to_string(),
+                column: columns[i].clone(),
+            }),
+            _ => Ok(None),
+        },
+    }
+}
+
+/// Check that no two of `rows` have the same values for a key.
+fn check_distinct_keys<'a>(
+    constraint: &Constraint,
+    indices: &[usize],
+    rows: impl IntoIterator<Item = &'a Row>,
+) -> Result<(), ConstraintViolation> {
+    let mut seen = BTreeSet::new();
+
+    for row in rows {
+        if let Some(key) = key(constraint, indices, row)? {
+            if seen.contains(&key) {
+                return Err(duplicate_key(constraint, &key));
+            }
+
+            seen.insert(key);
+        }
+    }
+
+    Ok(())
+}
+
+fn key_values(indices: &[usize], row: &Row) -> Vec<Value> {
+    indices.iter().map(|&i| row.0[i].clone()).collect()
+}
+
+fn duplicate_key(constraint: &Constraint, key: &[Value]) -> ConstraintViolation {
+    let values: Vec<_> = key.iter().map(|v| v.to_string()).collect();
+
+    ConstraintViolation::DuplicateKey {
+        constraint: constraint.to_string(),
+        values: values.join(", "),
+    }
+}
@@ -1,23 +1,172 @@
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
 
-use super::Table;
+use super::{Table, Transaction};
+use crate::storage::{self, Storage, Wal};
 
+/// A handle on a database, which may be shared by many threads by cloning it.
+///
+/// Each committed version of a table is left as it is while a snapshot holds it, and shared by
+/// every handle reading it. A handle's query (or transaction) reads a snapshot of the latest
+/// versions taken when it begins, so it never sees part of another handle's changes. One handle
+/// at a time may write, changing a table where it is if no snapshot holds it, or a copy of it
+/// otherwise, and its changed versions replace the committed ones when its query (or transaction)
+/// commits. Versions no snapshot holds any longer are freed.
 pub struct Database {
-    pub tables: HashMap<String, Table>,
+    /// The tables this handle is reading: the snapshot taken when its query or transaction began,
+    /// along with the changes it's made.
+    pub tables: HashMap<String, Arc<Table>>,
+    /// Whether select queries evaluate filters and aggregates over batches of column vectors,
+    /// rather than row by row.
+    pub vectorized: bool,
+    /// The transaction begun and not yet committed or rolled back, if there is one. Without one,
+    /// each query's changes are kept as soon as it completes.
+    pub transaction: Option<Transaction>,
+    /// The right to change the database, while this handle holds it.
+    pub(crate) writer: Option<Writer>,
+    pub(crate) shared: Arc<Shared>,
+}
+
+/// What's shared by every handle on a database.
+pub(crate) struct Shared {
+    /// The latest committed version of each table.
+    committed: Mutex<HashMap<String, Arc<Table>>>,
+    /// The right to change the database, unless a handle holds it.
+    writer: Mutex<Option<Writer>>,
+    writer_released: Condvar,
+}
+
+/// What only the handle changing the database may use.
+#[derive(Default)]
+pub(crate) struct Writer {
+    /// The file the database is stored in, which every change is written to. Without one, the
+    /// database only lives in memory.
+    pub storage: Option<Storage>,
+    /// The write-ahead log every statement changing the database is recorded in before its
+    /// changes are stored, if there is one.
+    pub wal: Option<Wal>,
 }
 
 impl Database {
     pub fn new() -> Self {
+        Database::with_tables(HashMap::new(), Writer::default())
+    }
+
+    /// Open the database stored in a file, creating an empty one if the file doesn't exist.
+    pub fn open(path: &Path) -> Result<Self, storage::Error> {
+        let (storage, tables) = Storage::open(path)?;
+        let tables = tables
+            .into_iter()
+            .map(|(name, table)| (name, Arc::new(table)))
+            .collect();
+
+        Ok(Database::with_tables(
+            tables,
+            Writer {
+                storage: Some(storage),
+                wal: None,
+            },
+        ))
+    }
+
+    fn with_tables(tables: HashMap<String, Arc<Table>>, writer: Writer) -> Self {
         Database {
             tables: HashMap::new(),
+            vectorized: false,
+            transaction: None,
+            writer: None,
+            shared: Arc::new(Shared {
+                committed: Mutex::new(tables),
+                writer: Mutex::new(Some(writer)),
+                writer_released: Condvar::new(),
+            }),
         }
     }
 
+    /// A snapshot of the latest committed version of each table.
+    pub fn snapshot(&self) -> HashMap<String, Arc<Table>> {
+        self.shared.snapshot()
+    }
+
     pub fn find_table(&self, table: &str) -> &Table {
         self.tables.get(table).unwrap()
     }
 
+    /// A table to change, copied first if a snapshot holds the same version of it.
     pub fn find_table_mut(&mut self, table: &str) -> &mut Table {
-        self.tables.get_mut(table).unwrap()
+        Arc::make_mut(self.tables.get_mut(table).unwrap())
+    }
+
+    /// Wait for the right to change the database, unless this handle already holds it.
+    pub(crate) fn lock_writer(&mut self) {
+        if self.writer.is_some() {
+            return;
+        }
+
+        let mut writer = lock(&self.shared.writer);
+        while writer.is_none() {
+            writer = self
+                .shared
+                .writer_released
+                .wait(writer)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        self.writer = writer.take();
+    }
+
+    /// Give up the right to change the database, if this handle holds it.
+    pub(crate) fn release_writer(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            *lock(&self.shared.writer) = Some(writer);
+            self.shared.writer_released.notify_one();
+        }
     }
 }
+
+impl Shared {
+    /// The latest committed version of each table, which no other handle can commit to while
+    /// it's held.
+    pub(crate) fn committed(&self) -> MutexGuard<'_, HashMap<String, Arc<Table>>> {
+        lock(&self.committed)
+    }
+
+    /// A snapshot of the latest committed version of each table.
+    pub(crate) fn snapshot(&self) -> HashMap<String, Arc<Table>> {
+        self.committed().clone()
+    }
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Database::new()
+    }
+}
+
+/// Another handle on the same database, which can be sent to another thread. It has no
+/// transaction of its own.
+impl Clone for Database {
+    fn clone(&self) -> Self {
+        Database {
+            tables: HashMap::new(),
+            vectorized: self.vectorized,
+            transaction: None,
+            writer: None,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+/// A handle dropped in the middle of a transaction rolls it back, so that other handles can
+/// write.
+impl Drop for Database {
+    fn drop(&mut self) {
+        self.release_writer();
+    }
+}
+
+/// Lock a mutex, even if a thread panicked while holding it. Queries check they can complete
+/// before they change anything, so a panic doesn't leave the tables half changed.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
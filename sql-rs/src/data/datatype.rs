@@ -1,9 +1,78 @@
+use std::fmt::{Display, Formatter};
+
 /// TODO quick description.
 ///
 /// TODO long description.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Datatype {
-    Number,
+    /// A 64-bit signed integer.
+    Integer,
+    /// A 64-bit floating point number.
+    Real,
+    /// An exact decimal number, with at most a precision (the number of digits in all) and a
+    /// scale (the number of digits after the point), if they're given.
+    Numeric(Option<(u32, u32)>),
     Text,
     Boolean,
+    /// A day, without a time of day.
+    Date,
+    /// A time of day, without a day.
+    Time,
+    /// A day and a time of day, without a time zone.
+    Timestamp,
+    /// A length of time, in months, days and microseconds.
+    Interval,
+    /// A string of bytes, which needn't be text.
+    Blob,
+    /// A json value, checked to be valid when it's stored.
+    Json,
+    /// A universally unique identifier, stored as 16 bytes.
+    Uuid,
+}
+
+impl Datatype {
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Datatype::Integer | Datatype::Real | Datatype::Numeric(_)
+        )
+    }
+
+    /// Whether this is the same datatype as another, regardless of the precision and scale of
+    /// a numeric datatype.
+    pub fn same_kind(&self, other: Datatype) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(&other)
+    }
+
+    /// The datatype arithmetic on numbers of two numeric datatypes results in: real if either is
+    /// real, otherwise numeric if either is numeric, otherwise integer.
+    pub fn promote(self, other: Datatype) -> Datatype {
+        match (self, other) {
+            (Datatype::Real, _) | (_, Datatype::Real) => Datatype::Real,
+            (Datatype::Numeric(_), _) | (_, Datatype::Numeric(_)) => Datatype::Numeric(None),
+            _ => Datatype::Integer,
+        }
+    }
+}
+
+impl Display for Datatype {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Datatype::Integer => write!(f, "integer"),
+            Datatype::Real => write!(f, "real"),
+            Datatype::Numeric(None) => write!(f, "numeric"),
+            Datatype::Numeric(Some((precision, scale))) => {
+                write!(f, "numeric({}, {})", precision, scale)
+            }
+            Datatype::Text => write!(f, "text"),
+            Datatype::Boolean => write!(f, "boolean"),
+            Datatype::Date => write!(f, "date"),
+            Datatype::Time => write!(f, "time"),
+            Datatype::Timestamp => write!(f, "timestamp"),
+            Datatype::Interval => write!(f, "interval"),
+            Datatype::Blob => write!(f, "blob"),
+            Datatype::Json => write!(f, "json"),
+            Datatype::Uuid => write!(f, "uuid"),
+        }
+    }
 }
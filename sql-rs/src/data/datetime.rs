@@ -0,0 +1,710 @@
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Decimal;
+
+pub const MICROS_PER_SECOND: i64 = 1_000_000;
+pub const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+pub const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+pub const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+
+/// How many days a month of an interval counts as, when intervals are compared (or divided).
+pub const DAYS_PER_MONTH: i64 = 30;
+
+/// The first and last days a date can be, 0001-01-01 and 9999-12-31, as days since 1970-01-01.
+const MIN_DAYS: i64 = days_from_civil(1, 1, 1);
+const MAX_DAYS: i64 = days_from_civil(9999, 12, 31);
+
+/// The number of days from 1970-01-01 to a day of the (proleptic) Gregorian calendar.
+const fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year, month and day of the day some number of days from 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// A day, from 0001-01-01 to 9999-12-31, written in ISO 8601 as `YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// Days since 1970-01-01.
+    days: i32,
+}
+
+impl Date {
+    /// The date some number of days from 1970-01-01. `None` if it's out of range.
+    pub fn from_days(days: i64) -> Option<Self> {
+        (MIN_DAYS..=MAX_DAYS)
+            .contains(&days)
+            .then_some(Date { days: days as i32 })
+    }
+
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        Date::from_days(days_from_civil(year, month, day))
+    }
+
+    /// The number of days since 1970-01-01.
+    pub fn days(&self) -> i64 {
+        self.days as i64
+    }
+
+    pub fn ymd(&self) -> (i64, u32, u32) {
+        civil_from_days(self.days())
+    }
+
+    /// The day of the week, from 0 for Sunday to 6 for Saturday.
+    pub fn weekday(&self) -> u32 {
+        // 1970-01-01 was a Thursday
+        (self.days() + 4).rem_euclid(7) as u32
+    }
+
+    /// The day of the year, from 1.
+    pub fn ordinal(&self) -> u32 {
+        let (year, _, _) = self.ymd();
+
+        (self.days() - days_from_civil(year, 1, 1)) as u32 + 1
+    }
+
+    /// The number of the ISO 8601 week this day is in, whose weeks begin on a Monday, and whose
+    /// first week is the one with the year's first Thursday in it.
+    pub fn iso_week(&self) -> u32 {
+        fn weeks_in_year(year: i64) -> u32 {
+            let p = |y: i64| (y + y.div_euclid(4) - y.div_euclid(100) + y.div_euclid(400)) % 7;
+            match p(year) == 4 || p(year - 1) == 3 {
+                true => 53,
+                false => 52,
+            }
+        }
+
+        let (year, _, _) = self.ymd();
+        let iso_weekday = (self.weekday() + 6) % 7 + 1;
+        let week = (self.ordinal() as i64 - iso_weekday as i64 + 10) / 7;
+        if week < 1 {
+            weeks_in_year(year - 1)
+        } else if week > weeks_in_year(year) as i64 {
+            1
+        } else {
+            week as u32
+        }
+    }
+
+    /// This date some number of months later, on the same day of the month, or the last day of
+    /// the month if it's shorter. `None` if that's out of range.
+    pub fn add_months(&self, months: i64) -> Option<Self> {
+        let (year, month, day) = self.ymd();
+        let total = (year * 12 + month as i64 - 1).checked_add(months)?;
+        let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+
+        Date::from_ymd(year, month, day.min(days_in_month(year, month)))
+    }
+
+    /// This date some number of days later. `None` if that's out of range.
+    pub fn add_days(&self, days: i64) -> Option<Self> {
+        Date::from_days(self.days().checked_add(days)?)
+    }
+}
+
+/// Parses `YYYY-MM-DD`.
+impl FromStr for Date {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, '-');
+        let year = digits(parts.next(), 4..=4).ok_or(())?;
+        let month = digits(parts.next(), 2..=2).ok_or(())?;
+        let day = digits(parts.next(), 2..=2).ok_or(())?;
+
+        Date::from_ymd(year, month as u32, day as u32).ok_or(())
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let (year, month, day) = self.ymd();
+
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// A time of day, to the microsecond, written in ISO 8601 as `HH:MM:SS` with an optional
+/// fraction of a second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    /// Microseconds since midnight.
+    micros: i64,
+}
+
+impl Time {
+    /// The time some number of microseconds after midnight. `None` if that's a day or more.
+    pub fn from_micros(micros: i64) -> Option<Self> {
+        (0..MICROS_PER_DAY)
+            .contains(&micros)
+            .then_some(Time { micros })
+    }
+
+    /// The number of microseconds since midnight.
+    pub fn micros(&self) -> i64 {
+        self.micros
+    }
+
+    /// This time a number of microseconds later (or earlier), wrapping around midnight.
+    pub fn add_micros(&self, micros: i64) -> Self {
+        Time {
+            micros: (self.micros + micros % MICROS_PER_DAY).rem_euclid(MICROS_PER_DAY),
+        }
+    }
+}
+
+/// Parses `HH:MM`, `HH:MM:SS`, or `HH:MM:SS.FFFFFF`.
+impl FromStr for Time {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let micros = clock_micros(s, 2..=2).ok_or(())?;
+
+        Time::from_micros(micros).ok_or(())
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", Clock(self.micros as u64))
+    }
+}
+
+/// A date and a time of day (without a time zone), written in ISO 8601 as `YYYY-MM-DD HH:MM:SS`
+/// with an optional fraction of a second.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    /// Microseconds since 1970-01-01 00:00:00.
+    micros: i64,
+}
+
+thread_local! {
+    /// The time the statement executing on this thread began, or the transaction it's part of.
+    static STATEMENT_TIME: Cell<Option<Timestamp>> = const { Cell::new(None) };
+}
+
+impl Timestamp {
+    /// The current time, in UTC.
+    pub fn now() -> Self {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Timestamp {
+            micros: since_epoch.as_micros() as i64,
+        }
+    }
+
+    /// The time the statement executing on this thread began (or its transaction did), which
+    /// every call to `now()` in it gives. The current time, outside of a statement.
+    pub fn statement_time() -> Self {
+        STATEMENT_TIME
+            .with(Cell::get)
+            .unwrap_or_else(Timestamp::now)
+    }
+
+    /// Set the time the statement about to be executed on this thread began.
+    pub fn set_statement_time(time: Timestamp) {
+        STATEMENT_TIME.with(|cell| cell.set(Some(time)));
+    }
+
+    /// The timestamp some number of microseconds since 1970-01-01 00:00:00. `None` if its date
+    /// is out of range.
+    pub fn from_micros(micros: i64) -> Option<Self> {
+        Date::from_days(micros.div_euclid(MICROS_PER_DAY))?;
+
+        Some(Timestamp { micros })
+    }
+
+    pub fn new(date: Date, time: Time) -> Self {
+        Timestamp {
+            micros: date.days() * MICROS_PER_DAY + time.micros,
+        }
+    }
+
+    /// The number of microseconds since 1970-01-01 00:00:00.
+    pub fn micros(&self) -> i64 {
+        self.micros
+    }
+
+    pub fn date(&self) -> Date {
+        Date {
+            days: self.micros.div_euclid(MICROS_PER_DAY) as i32,
+        }
+    }
+
+    pub fn time(&self) -> Time {
+        Time {
+            micros: self.micros.rem_euclid(MICROS_PER_DAY),
+        }
+    }
+
+    /// This timestamp an interval later: its months are added first (keeping the day of the
+    /// month, unless the month is shorter), then its days, then its time. `None` if that's out
+    /// of range.
+    pub fn add_interval(&self, interval: &Interval) -> Option<Self> {
+        let date = self.date().add_months(interval.months as i64)?;
+        let micros = (date.days() as i128 + interval.days as i128) * MICROS_PER_DAY as i128
+            + self.time().micros as i128
+            + interval.micros as i128;
+
+        Timestamp::from_micros(i64::try_from(micros).ok()?)
+    }
+
+    /// The interval from another timestamp to this one, in days and a time of less than a day.
+    pub fn since(&self, other: &Timestamp) -> Option<Interval> {
+        let micros = self.micros.checked_sub(other.micros)?;
+
+        Some(Interval {
+            months: 0,
+            days: (micros / MICROS_PER_DAY) as i32,
+            micros: micros % MICROS_PER_DAY,
+        })
+    }
+}
+
+/// Midnight on a date.
+impl From<Date> for Timestamp {
+    fn from(date: Date) -> Self {
+        Timestamp::new(date, Time { micros: 0 })
+    }
+}
+
+/// Parses a date, optionally followed by a space (or a `T`) and a time.
+impl FromStr for Timestamp {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (date, time) = match s.find([' ', 'T']) {
+            Some(i) => (&s[..i], s[i + 1..].parse()?),
+            None => (s, Time { micros: 0 }),
+        };
+
+        Ok(Timestamp::new(date.parse()?, time))
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{} {}", self.date(), self.time())
+    }
+}
+
+/// A length of time in months, days and microseconds, kept apart since months and days vary in
+/// length. Written as a list of quantities and units, such as `1 year 2 months 3 days 04:05:06`
+/// (which is how it's displayed), or in ISO 8601 as a duration, such as `P1Y2M3DT4H5M6S`.
+///
+/// Intervals are compared as if every month were 30 days, and every day 24 hours, so `1 month`
+/// equals `30 days`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Interval {
+    months: i32,
+    days: i32,
+    micros: i64,
+}
+
+/// What a quantity of a unit of an interval is made up of.
+#[derive(Clone, Copy)]
+enum Unit {
+    Months(i128),
+    Days(i128),
+    Micros(i128),
+}
+
+impl Interval {
+    pub fn new(months: i32, days: i32, micros: i64) -> Self {
+        Interval {
+            months,
+            days,
+            micros,
+        }
+    }
+
+    pub fn months(&self) -> i32 {
+        self.months
+    }
+
+    pub fn days(&self) -> i32 {
+        self.days
+    }
+
+    pub fn micros(&self) -> i64 {
+        self.micros
+    }
+
+    pub fn checked_add(&self, rhs: &Interval) -> Option<Self> {
+        Some(Interval {
+            months: self.months.checked_add(rhs.months)?,
+            days: self.days.checked_add(rhs.days)?,
+            micros: self.micros.checked_add(rhs.micros)?,
+        })
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Interval {
+            months: self.months.checked_neg()?,
+            days: self.days.checked_neg()?,
+            micros: self.micros.checked_neg()?,
+        })
+    }
+
+    /// This interval divided by a count, with what's left over of its months carried into its
+    /// days, and of its days into its microseconds (rounding those to the nearest).
+    pub fn div_count(&self, count: i64) -> Self {
+        let count = count as i128;
+        let months = self.months as i128;
+        let days = self.days as i128 + months % count * DAYS_PER_MONTH as i128;
+        let micros = self.micros as i128 + days % count * MICROS_PER_DAY as i128;
+
+        Interval {
+            months: (months / count) as i32,
+            days: (days / count) as i32,
+            micros: div_round(micros, count) as i64,
+        }
+    }
+
+    /// The length of this interval in microseconds, taking months as 30 days.
+    pub fn total_micros(&self) -> i128 {
+        (self.months as i128 * DAYS_PER_MONTH as i128 + self.days as i128) * MICROS_PER_DAY as i128
+            + self.micros as i128
+    }
+
+    /// Add a quantity of a unit, with any fraction of a month carried into days, and of a day
+    /// into microseconds. `None` if that's out of range.
+    fn add_quantity(&mut self, quantity: &str, unit: Unit) -> Option<()> {
+        let quantity: Decimal = quantity.parse().ok()?;
+        let divisor = 10i128.pow(quantity.scale());
+        let mut rest = quantity.mantissa();
+
+        let mut months = 0;
+        if let Unit::Months(n) = unit {
+            rest = rest.checked_mul(n)?;
+            months = rest / divisor;
+            rest = (rest % divisor).checked_mul(DAYS_PER_MONTH as i128)?;
+        }
+        let mut days = 0;
+        if let Unit::Months(_) | Unit::Days(_) = unit {
+            if let Unit::Days(n) = unit {
+                rest = rest.checked_mul(n)?;
+            }
+            days = rest / divisor;
+            rest = (rest % divisor).checked_mul(MICROS_PER_DAY as i128)?;
+        }
+        if let Unit::Micros(n) = unit {
+            rest = rest.checked_mul(n)?;
+        }
+        let micros = div_round(rest, divisor);
+
+        *self = self.checked_add(&Interval {
+            months: i32::try_from(months).ok()?,
+            days: i32::try_from(days).ok()?,
+            micros: i64::try_from(micros).ok()?,
+        })?;
+
+        Some(())
+    }
+
+    /// Parse an ISO 8601 duration, such as `P1Y2M3DT4H5M6.5S`. Each quantity may have a sign
+    /// and a fraction.
+    fn parse_iso(s: &str) -> Option<Self> {
+        let mut interval = Interval::default();
+        let (date, time) = s.split_once('T').unwrap_or((s, ""));
+        if date.is_empty() && time.is_empty() || s.ends_with('T') {
+            return None;
+        }
+
+        for (is_time, part, units) in [(false, date, "YMWD"), (true, time, "HMS")] {
+            let mut rest = part;
+            let mut units = units;
+            while !rest.is_empty() {
+                let end = rest.find(|c: char| c.is_ascii_alphabetic())?;
+                let (quantity, designator) = (&rest[..end], &rest[end..end + 1]);
+                // each designator may appear once, in order
+                let position = units.find(designator)?;
+                units = &units[position + 1..];
+
+                let unit = match (designator, is_time) {
+                    ("Y", false) => Unit::Months(12),
+                    ("M", false) => Unit::Months(1),
+                    ("W", false) => Unit::Days(7),
+                    ("D", false) => Unit::Days(1),
+                    ("H", true) => Unit::Micros(MICROS_PER_HOUR as i128),
+                    ("M", true) => Unit::Micros(MICROS_PER_MINUTE as i128),
+                    _ => Unit::Micros(MICROS_PER_SECOND as i128),
+                };
+                interval.add_quantity(quantity, unit)?;
+                rest = &rest[end + 1..];
+            }
+        }
+
+        Some(interval)
+    }
+
+    /// Parse a list of quantities and units, such as `1 year -2 mons 3.5 days`, optionally
+    /// followed by a time such as `-04:05:06`.
+    fn parse_verbose(s: &str) -> Option<Self> {
+        let mut interval = Interval::default();
+        let mut words = s.split_whitespace().peekable();
+        words.peek()?;
+
+        while let Some(word) = words.next() {
+            if word.contains(':') {
+                let (negative, clock) = match word.strip_prefix('-') {
+                    Some(clock) => (true, clock),
+                    None => (false, word.strip_prefix('+').unwrap_or(word)),
+                };
+                let micros = clock_micros(clock, 1..=9)?;
+                let micros = if negative { -micros } else { micros };
+                interval.micros = interval.micros.checked_add(micros)?;
+                continue;
+            }
+
+            let unit = match words.next()?.to_ascii_lowercase().as_str() {
+                "year" | "years" => Unit::Months(12),
+                "month" | "months" | "mon" | "mons" => Unit::Months(1),
+                "week" | "weeks" => Unit::Days(7),
+                "day" | "days" => Unit::Days(1),
+                "hour" | "hours" => Unit::Micros(MICROS_PER_HOUR as i128),
+                "minute" | "minutes" | "min" | "mins" => Unit::Micros(MICROS_PER_MINUTE as i128),
+                "second" | "seconds" | "sec" | "secs" => Unit::Micros(MICROS_PER_SECOND as i128),
+                "millisecond" | "milliseconds" => Unit::Micros(1000),
+                "microsecond" | "microseconds" => Unit::Micros(1),
+                _ => return None,
+            };
+            interval.add_quantity(word, unit)?;
+        }
+
+        Some(interval)
+    }
+}
+
+impl FromStr for Interval {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('P') {
+            Some(duration) => Interval::parse_iso(duration),
+            None => Interval::parse_verbose(s),
+        }
+        .ok_or(())
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let (years, months) = (self.months / 12, self.months % 12);
+        let mut parts = Vec::new();
+        for (n, unit) in [(years, "year"), (months, "mon"), (self.days, "day")] {
+            match n {
+                0 => {}
+                1 => parts.push(format!("1 {}", unit)),
+                n => parts.push(format!("{} {}s", n, unit)),
+            }
+        }
+        if self.micros != 0 || parts.is_empty() {
+            let sign = if self.micros < 0 { "-" } else { "" };
+            parts.push(format!("{}{}", sign, Clock(self.micros.unsigned_abs())));
+        }
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Intervals are equal if they're the same length, taking months as 30 days.
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Interval {}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.total_micros().cmp(&other.total_micros())
+    }
+}
+
+impl Hash for Interval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.total_micros().hash(state);
+    }
+}
+
+/// A part of a date or time, which can be extracted from one, or which one can be truncated to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateField {
+    Year,
+    Quarter,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    /// The day of the week, from 0 for Sunday.
+    Dow,
+    /// The day of the year, from 1.
+    Doy,
+    /// The number of seconds since 1970-01-01 00:00:00 (or in an interval).
+    Epoch,
+}
+
+impl FromStr for DateField {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "year" => DateField::Year,
+            "quarter" => DateField::Quarter,
+            "month" => DateField::Month,
+            "week" => DateField::Week,
+            "day" => DateField::Day,
+            "hour" => DateField::Hour,
+            "minute" => DateField::Minute,
+            "second" => DateField::Second,
+            "dow" => DateField::Dow,
+            "doy" => DateField::Doy,
+            "epoch" => DateField::Epoch,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl Display for DateField {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let name = match self {
+            DateField::Year => "year",
+            DateField::Quarter => "quarter",
+            DateField::Month => "month",
+            DateField::Week => "week",
+            DateField::Day => "day",
+            DateField::Hour => "hour",
+            DateField::Minute => "minute",
+            DateField::Second => "second",
+            DateField::Dow => "dow",
+            DateField::Doy => "doy",
+            DateField::Epoch => "epoch",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A quotient rounded half away from zero, dividing by a positive number.
+fn div_round(dividend: i128, divisor: i128) -> i128 {
+    let (quotient, remainder) = (dividend / divisor, dividend % divisor);
+    match remainder.abs() >= divisor - remainder.abs() {
+        true => quotient + dividend.signum(),
+        false => quotient,
+    }
+}
+
+/// A number made of only ASCII digits, with a number of digits in `len`.
+fn digits(s: Option<&str>, len: std::ops::RangeInclusive<usize>) -> Option<i64> {
+    let s = s?;
+    if !len.contains(&s.len()) || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    s.parse().ok()
+}
+
+/// The microseconds in a time written as `H:MM`, `H:MM:SS` or `H:MM:SS.FFFFFF`, with an hour of
+/// a number of digits in `hour_len`, which is less than 24 if it's two digits (as in a time of
+/// day) and any number otherwise (as in an interval).
+fn clock_micros(s: &str, hour_len: std::ops::RangeInclusive<usize>) -> Option<i64> {
+    let mut parts = s.splitn(3, ':');
+    let hours = digits(parts.next(), hour_len.clone())?;
+    let minutes = digits(parts.next(), 2..=2)?;
+    let (seconds, fraction) = match parts.next() {
+        Some(seconds) => seconds.split_once('.').unwrap_or((seconds, "0")),
+        None => ("0", "0"),
+    };
+    let seconds = digits(Some(seconds), 1..=2)?;
+    let fraction = digits(Some(fraction), 1..=6)? * 10i64.pow(6 - fraction.len() as u32);
+
+    if *hour_len.end() == 2 && hours >= 24 || minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+
+    hours
+        .checked_mul(MICROS_PER_HOUR)?
+        .checked_add(minutes * MICROS_PER_MINUTE + seconds * MICROS_PER_SECOND + fraction)
+}
+
+/// A number of microseconds displayed as `HH:MM:SS`, with a fraction of a second only if there
+/// is one (and without trailing zeros).
+struct Clock(u64);
+
+impl Display for Clock {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let seconds = self.0 / MICROS_PER_SECOND as u64;
+        write!(
+            f,
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )?;
+
+        match self.0 % MICROS_PER_SECOND as u64 {
+            0 => Ok(()),
+            fraction => write!(f, ".{}", format!("{:06}", fraction).trim_end_matches('0')),
+        }
+    }
+}
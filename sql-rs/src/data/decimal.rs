@@ -0,0 +1,232 @@
+use std::cmp::Ordering;
+use std::convert::TryInto;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// The most digits a decimal can have, before and after the point together.
+pub const MAX_PRECISION: u32 = 38;
+
+/// An exact decimal number: an integer of at most [`MAX_PRECISION`] digits, and how many of
+/// them come after the point.
+///
+/// Decimals which differ only in trailing zeros after the point (`1.5` and `1.50`) are equal,
+/// but each keeps its own scale for display.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// A decimal of `mantissa` with `scale` digits after the point. `None` if it has too many
+    /// digits.
+    pub fn new(mantissa: i128, scale: u32) -> Option<Self> {
+        let decimal = Decimal { mantissa, scale };
+
+        (scale <= MAX_PRECISION && decimal.precision() <= MAX_PRECISION).then_some(decimal)
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// The number of digits before the point.
+    pub fn integer_digits(&self) -> u32 {
+        let integer = self.mantissa.unsigned_abs() / 10u128.pow(self.scale);
+
+        match integer {
+            0 => 0,
+            n => n.ilog10() + 1,
+        }
+    }
+
+    /// The number of digits, before and after the point.
+    fn precision(&self) -> u32 {
+        self.integer_digits() + self.scale
+    }
+
+    /// This decimal with `scale` digits after the point, rounding half away from zero if
+    /// that's fewer. `None` if that's too many digits.
+    pub fn rescale(&self, scale: u32) -> Option<Self> {
+        if scale >= self.scale {
+            let mantissa = self
+                .mantissa
+                .checked_mul(10i128.checked_pow(scale - self.scale)?)?;
+            return Decimal::new(mantissa, scale);
+        }
+
+        let divisor = 10i128.pow(self.scale - scale);
+        let (quotient, remainder) = (self.mantissa / divisor, self.mantissa % divisor);
+        let rounded = if remainder.abs() * 2 >= divisor {
+            quotient + self.mantissa.signum()
+        } else {
+            quotient
+        };
+
+        Decimal::new(rounded, scale)
+    }
+
+    pub fn checked_add(&self, rhs: &Decimal) -> Option<Self> {
+        let scale = self.scale.max(rhs.scale);
+
+        Decimal::new(
+            self.rescale(scale)?
+                .mantissa
+                .checked_add(rhs.rescale(scale)?.mantissa)?,
+            scale,
+        )
+    }
+
+    pub fn checked_sub(&self, rhs: &Decimal) -> Option<Self> {
+        self.checked_add(&Decimal {
+            mantissa: -rhs.mantissa,
+            scale: rhs.scale,
+        })
+    }
+
+    /// This decimal divided by a count, keeping 16 digits after the point (or as many as fit,
+    /// and never fewer than it has).
+    pub fn div_count(&self, count: i64) -> Self {
+        let scale = (self.scale..=self.scale.max(16))
+            .rev()
+            .find(|&scale| self.rescale(scale).is_some())
+            .unwrap_or(self.scale);
+        let dividend = self.rescale(scale).unwrap_or(*self).mantissa;
+
+        let divisor = count as i128;
+        let (quotient, remainder) = (dividend / divisor, dividend % divisor);
+        let mantissa = if remainder.abs() * 2 >= divisor {
+            quotient + dividend.signum()
+        } else {
+            quotient
+        };
+
+        Decimal { mantissa, scale }
+    }
+
+    /// The nearest integer, rounding half away from zero. `None` if it's out of range.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.rescale(0)?.mantissa.try_into().ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    /// The integer and fraction parts, with the fraction scaled up to `scale` digits (which
+    /// always fits, since a fraction has at most [`MAX_PRECISION`] digits).
+    fn parts(&self, scale: u32) -> (i128, i128) {
+        let divisor = 10i128.pow(self.scale);
+        let fraction = self.mantissa.rem_euclid(divisor) * 10i128.pow(scale - self.scale);
+
+        (self.mantissa.div_euclid(divisor), fraction)
+    }
+
+    /// The mantissa and scale without trailing zeros after the point, which are the same for
+    /// every equal decimal.
+    fn normalized(&self) -> (i128, u32) {
+        let (mut mantissa, mut scale) = (self.mantissa, self.scale);
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+
+        (mantissa, scale)
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(n: i64) -> Self {
+        Decimal {
+            mantissa: n as i128,
+            scale: 0,
+        }
+    }
+}
+
+/// Parses digits with an optional sign and point, such as `-12.50`. Fails if there are too
+/// many digits.
+impl FromStr for Decimal {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer.is_empty() && fraction.is_empty()
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(());
+        }
+
+        // leading zeros don't count towards the precision
+        let integer = integer.trim_start_matches('0');
+        if integer.len() + fraction.len() > MAX_PRECISION as usize {
+            return Err(());
+        }
+
+        let mantissa: i128 = format!("0{}{}", integer, fraction)
+            .parse()
+            .map_err(|_| ())?;
+        let mantissa = if negative { -mantissa } else { mantissa };
+
+        Decimal::new(mantissa, fraction.len() as u32).ok_or(())
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let digits = format!(
+            "{:0width$}",
+            self.mantissa.unsigned_abs(),
+            width = self.scale as usize + 1
+        );
+        let (integer, fraction) = digits.split_at(digits.len() - self.scale as usize);
+
+        if self.mantissa < 0 {
+            write!(f, "-")?;
+        }
+        match fraction {
+            "" => write!(f, "{}", integer),
+            _ => write!(f, "{}.{}", integer, fraction),
+        }
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+
+        self.parts(scale).cmp(&other.parts(scale))
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
+    }
+}
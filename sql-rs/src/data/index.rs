@@ -0,0 +1,147 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+
+use super::Value;
+
+/// How an index organizes its keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexMethod {
+    /// Keys are kept in order, so the index finds the rows in a range of values as well as the
+    /// rows equal to a value.
+    #[default]
+    BTree,
+    /// Keys are hashed, so the index only finds the rows equal to a value.
+    Hash,
+}
+
+/// A secondary index over some columns of a table, mapping the values of those columns (the
+/// key) to the positions of the rows holding them.
+///
+/// Rows with a null anywhere in their key are left out, since a null is never equal to, or in a
+/// range of, any value. An index is kept up to date by the table it belongs to.
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub name: String,
+    pub columns: Vec<String>,
+    entries: Entries,
+}
+
+#[derive(Debug, Clone)]
+enum Entries {
+    BTree(BTreeMap<Vec<Value>, Vec<usize>>),
+    Hash(HashMap<Vec<Value>, Vec<usize>>),
+}
+
+impl Index {
+    /// An empty index.
+    pub fn new(name: String, columns: Vec<String>, method: IndexMethod) -> Self {
+        let entries = match method {
+            IndexMethod::BTree => Entries::BTree(BTreeMap::new()),
+            IndexMethod::Hash => Entries::Hash(HashMap::new()),
+        };
+
+        Index {
+            name,
+            columns,
+            entries,
+        }
+    }
+
+    pub fn method(&self) -> IndexMethod {
+        match self.entries {
+            Entries::BTree(_) => IndexMethod::BTree,
+            Entries::Hash(_) => IndexMethod::Hash,
+        }
+    }
+
+    /// Record that the row at `position` has the values `key`.
+    pub fn insert(&mut self, key: Vec<Value>, position: usize) {
+        if key.iter().any(|v| matches!(v, Value::Null)) {
+            return;
+        }
+
+        match &mut self.entries {
+            Entries::BTree(map) => map.entry(key).or_default().push(position),
+            Entries::Hash(map) => map.entry(key).or_default().push(position),
+        }
+    }
+
+    /// Forget that the row at `position` has the values `key`.
+    pub fn remove(&mut self, key: &[Value], position: usize) {
+        let positions = match &mut self.entries {
+            Entries::BTree(map) => map.get_mut(key),
+            Entries::Hash(map) => map.get_mut(key),
+        };
+        let empty = match positions {
+            Some(positions) => {
+                positions.retain(|&p| p != position);
+                positions.is_empty()
+            }
+            None => false,
+        };
+
+        if empty {
+            match &mut self.entries {
+                Entries::BTree(map) => map.remove(key),
+                Entries::Hash(map) => map.remove(key),
+            };
+        }
+    }
+
+    /// Move every row to the position `to` gives for the one it's at.
+    pub fn move_positions(&mut self, to: impl Fn(usize) -> usize) {
+        match &mut self.entries {
+            Entries::BTree(map) => map.values_mut().flatten().for_each(|p| *p = to(*p)),
+            Entries::Hash(map) => map.values_mut().flatten().for_each(|p| *p = to(*p)),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match &mut self.entries {
+            Entries::BTree(map) => map.clear(),
+            Entries::Hash(map) => map.clear(),
+        }
+    }
+
+    /// The positions of the rows whose key is `key`, in no particular order.
+    pub fn get(&self, key: &[Value]) -> &[usize] {
+        let positions = match &self.entries {
+            Entries::BTree(map) => map.get(key),
+            Entries::Hash(map) => map.get(key),
+        };
+
+        positions.map_or(&[], Vec::as_slice)
+    }
+
+    /// The positions of the rows whose key is between `lower` and `upper`, in no particular
+    /// order. Only an index on a single column, kept in order, can find a range, so for any
+    /// other index this is `None`.
+    pub fn range(&self, lower: Bound<&Value>, upper: Bound<&Value>) -> Option<Vec<usize>> {
+        let map = match &self.entries {
+            Entries::BTree(map) if self.columns.len() == 1 => map,
+            _ => return None,
+        };
+
+        // a range which is empty (or backwards) would panic if passed to the map
+        let empty = match (lower, upper) {
+            (Bound::Included(l), Bound::Included(u)) => l > u,
+            (Bound::Included(l) | Bound::Excluded(l), Bound::Included(u) | Bound::Excluded(u)) => {
+                l >= u
+            }
+            _ => false,
+        };
+        if empty {
+            return Some(Vec::new());
+        }
+
+        let key = |v: &Value| vec![v.clone()];
+        let range = map.range((lower.map(key), upper.map(key)));
+
+        Some(
+            range
+                .flat_map(|(_, positions)| positions)
+                .copied()
+                .collect(),
+        )
+    }
+}
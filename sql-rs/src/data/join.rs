@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use super::{Row, Value};
+
+/// The columns an equi-join matches rows on: the rows of both tables must be equal in each
+/// pair of columns, `left[i]` of the left table and `right[i]` of the right table.
+#[derive(Debug)]
+pub struct JoinKeys {
+    pub left: Vec<usize>,
+    pub right: Vec<usize>,
+}
+
+/// How a join finds the pairs of rows to test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinMethod {
+    /// Every pair of rows is tested.
+    NestedLoop,
+    /// The inner rows are hashed by key, then looked up by each outer row's key.
+    Hash,
+    /// The inner rows are already sorted by key, so they're walked in step with the outer rows.
+    Merge,
+}
+
+impl Display for JoinMethod {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            JoinMethod::NestedLoop => write!(f, "nested loop join"),
+            JoinMethod::Hash => write!(f, "hash join"),
+            JoinMethod::Merge => write!(f, "merge join"),
+        }
+    }
+}
+
+// A join is executed by pulling rows from the outer table (the right table for a right join, the
+// left table otherwise) one at a time, testing each against the inner table's rows it's paired
+// with, which are held in memory. Whichever way those rows are found, they're tested in order,
+// so every join method produces the same rows in the same order.
+
+/// The rows of the inner table of a join, arranged for finding the rows each outer row may
+/// match.
+#[derive(Debug)]
+pub struct InnerRows {
+    pub rows: Vec<Row>,
+    lookup: Lookup,
+}
+
+#[derive(Debug)]
+enum Lookup {
+    All,
+    Hash {
+        outer_keys: Vec<usize>,
+        positions: HashMap<Vec<Value>, Vec<usize>>,
+    },
+    Merge {
+        outer_keys: Vec<usize>,
+        /// The key and position of each row without a null in its key, in order.
+        keys: Vec<(Vec<Value>, usize)>,
+        /// The start of the run of keys no smaller than the last outer row's.
+        start: usize,
+    },
+}
+
+impl InnerRows {
+    /// Arrange the inner rows of a join which tests every pair of rows.
+    pub fn all(rows: Vec<Row>) -> Self {
+        InnerRows {
+            rows,
+            lookup: Lookup::All,
+        }
+    }
+
+    /// Arrange the inner rows of an equi-join, where `inner_keys` are the columns of the inner
+    /// rows which must equal `outer_keys` of an outer row. If the rows are `sorted` by their
+    /// keys they're merged with the outer rows, otherwise they're hashed.
+    pub fn keyed(
+        rows: Vec<Row>,
+        outer_keys: Vec<usize>,
+        inner_keys: &[usize],
+        sorted: bool,
+    ) -> Self {
+        let keys = rows
+            .iter()
+            .enumerate()
+            .filter_map(|(i, row)| join_key(inner_keys, row).map(|key| (key, i)));
+        let lookup = if sorted {
+            Lookup::Merge {
+                outer_keys,
+                keys: keys.collect(),
+                start: 0,
+            }
+        } else {
+            let mut positions: HashMap<Vec<Value>, Vec<usize>> = HashMap::new();
+            for (key, i) in keys {
+                positions.entry(key).or_default().push(i);
+            }
+
+            Lookup::Hash {
+                outer_keys,
+                positions,
+            }
+        };
+
+        InnerRows { rows, lookup }
+    }
+
+    /// Replace `candidates` with the positions of the rows an outer row may match, in order.
+    pub fn candidates(&mut self, outer_row: &Row, candidates: &mut Vec<usize>) {
+        candidates.clear();
+
+        match &mut self.lookup {
+            Lookup::All => candidates.extend(0..self.rows.len()),
+            Lookup::Hash {
+                outer_keys,
+                positions,
+            } => {
+                if let Some(positions) =
+                    join_key(outer_keys, outer_row).and_then(|key| positions.get(&key))
+                {
+                    candidates.extend_from_slice(positions);
+                }
+            }
+            Lookup::Merge {
+                outer_keys,
+                keys,
+                start,
+            } => {
+                let key = match join_key(outer_keys, outer_row) {
+                    Some(key) => key,
+                    None => return,
+                };
+
+                // outer rows usually come in order too, otherwise search for the run again
+                if *start > 0 && keys[*start - 1].0 >= key {
+                    *start = keys.partition_point(|(k, _)| *k < key);
+                }
+                while *start < keys.len() && keys[*start].0.cmp(&key) == Ordering::Less {
+                    *start += 1;
+                }
+
+                candidates.extend(
+                    keys[*start..]
+                        .iter()
+                        .take_while(|(k, _)| *k == key)
+                        .map(|&(_, i)| i),
+                );
+            }
+        }
+    }
+}
+
+/// Whether rows are in order of the values of `columns`, as a sort-merge join needs the inner
+/// rows to be.
+pub fn sorted_by(rows: &[Row], columns: &[usize]) -> bool {
+    rows.windows(2).all(|pair| {
+        columns
+            .iter()
+            .map(|&i| &pair[0].0[i])
+            .le(columns.iter().map(|&i| &pair[1].0[i]))
+    })
+}
+
+/// The values of `columns` of a row, unless one is null (so the row can't equal any other).
+fn join_key(columns: &[usize], row: &Row) -> Option<Vec<Value>> {
+    let key: Vec<_> = columns.iter().map(|&i| row.0[i].clone()).collect();
+
+    if key.iter().any(|v| matches!(v, Value::Null)) {
+        None
+    } else {
+        Some(key)
+    }
+}
@@ -0,0 +1,391 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::{CharIndices, FromStr};
+
+use super::ValueError;
+
+/// How deeply arrays and objects can be nested in json.
+const MAX_DEPTH: usize = 128;
+
+/// A json value, parsed from text.
+///
+/// Numbers are stored as reals, and an object's fields are kept in order of their keys, with
+/// the last of several fields with the same key, so json which differs only in whitespace, the
+/// order of fields or how numbers are written (`1.0` and `1`) is equal, and is displayed the
+/// same.
+#[derive(Debug, Clone)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    /// The field of an object with a key. `None` if there isn't one, or this isn't an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.get(key),
+            _ => None,
+        }
+    }
+
+    /// The element of an array at an index, from 0, or counting back from the end if it's
+    /// negative. `None` if there isn't one, or this isn't an array.
+    pub fn index(&self, index: i64) -> Option<&Json> {
+        match self {
+            Json::Array(elements) => {
+                let index = match index {
+                    i if i < 0 => elements.len().checked_sub(i.unsigned_abs() as usize)?,
+                    i => i as usize,
+                };
+
+                elements.get(index)
+            }
+            _ => None,
+        }
+    }
+
+    /// The value at a path, which is `$` followed by keys (`.key`) and indexes (`[0]`) to look
+    /// up in turn, such as `$.tags[0]`. `Ok(None)` if there isn't one, failing if the path isn't
+    /// valid.
+    pub fn extract(&self, path: &str) -> Result<Option<&Json>, ValueError> {
+        let invalid = || ValueError::InvalidJsonPath(path.to_owned());
+        let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+        let mut value = Some(self);
+
+        while !rest.is_empty() {
+            if let Some(key) = rest.strip_prefix('.') {
+                let end = key.find(['.', '[']).unwrap_or(key.len());
+                if end == 0 {
+                    return Err(invalid());
+                }
+
+                value = value.and_then(|v| v.get(&key[..end]));
+                rest = &key[end..];
+            } else if let Some(index) = rest.strip_prefix('[') {
+                let (index, after) = index.split_once(']').ok_or_else(invalid)?;
+                let index = index.trim().parse().map_err(|_| invalid())?;
+
+                value = value.and_then(|v| v.index(index));
+                rest = after;
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// This value as text: a string without its quotes, and anything else as json. `None` for
+    /// null.
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            Json::Null => None,
+            Json::String(s) => Some(s.clone()),
+            value => Some(value.to_string()),
+        }
+    }
+
+    /// The position of this value's kind in the order values of different kinds are sorted in.
+    fn rank(&self) -> u8 {
+        match self {
+            Json::Null => 0,
+            Json::String(_) => 1,
+            Json::Number(_) => 2,
+            Json::Boolean(_) => 3,
+            Json::Array(_) => 4,
+            Json::Object(_) => 5,
+        }
+    }
+}
+
+/// Parses json text (RFC 8259), with whitespace around it. Fails on anything else after the
+/// value, numbers out of range of a real, and arrays and objects nested too deeply.
+impl FromStr for Json {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { rest: s };
+        let value = parser.value(0)?;
+        parser.whitespace();
+
+        match parser.rest {
+            "" => Ok(value),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Reads json from the front of some text.
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn whitespace(&mut self) {
+        self.rest = self.rest.trim_start_matches([' ', '\t', '\n', '\r']);
+    }
+
+    /// Whether the text (after whitespace) starts with a token, skipping it if so.
+    fn eat(&mut self, token: &str) -> bool {
+        self.whitespace();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, ()> {
+        if depth > MAX_DEPTH {
+            return Err(());
+        }
+
+        self.whitespace();
+        match self.rest.chars().next().ok_or(())? {
+            '{' => self.object(depth),
+            '[' => self.array(depth),
+            '"' => self.string().map(Json::String),
+            '-' | '0'..='9' => self.number(),
+            _ if self.eat("null") => Ok(Json::Null),
+            _ if self.eat("true") => Ok(Json::Boolean(true)),
+            _ if self.eat("false") => Ok(Json::Boolean(false)),
+            _ => Err(()),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json, ()> {
+        self.eat("{");
+        let mut fields = BTreeMap::new();
+        if self.eat("}") {
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            if !self.eat(":") {
+                return Err(());
+            }
+            fields.insert(key, self.value(depth + 1)?);
+
+            match (self.eat(","), self.eat("}")) {
+                (true, false) => continue,
+                (false, true) => return Ok(Json::Object(fields)),
+                _ => return Err(()),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json, ()> {
+        self.eat("[");
+        let mut elements = vec![];
+        if self.eat("]") {
+            return Ok(Json::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value(depth + 1)?);
+
+            match (self.eat(","), self.eat("]")) {
+                (true, false) => continue,
+                (false, true) => return Ok(Json::Array(elements)),
+                _ => return Err(()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, ()> {
+        let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+
+        let mut len = self.rest.starts_with('-') as usize;
+        match digits(&self.rest[len..]) {
+            0 => return Err(()),
+            // no leading zeros
+            n if n > 1 && self.rest[len..].starts_with('0') => return Err(()),
+            n => len += n,
+        }
+        if self.rest[len..].starts_with('.') {
+            match digits(&self.rest[len + 1..]) {
+                0 => return Err(()),
+                n => len += 1 + n,
+            }
+        }
+        if self.rest[len..].starts_with(['e', 'E']) {
+            len += 1;
+            if self.rest[len..].starts_with(['+', '-']) {
+                len += 1;
+            }
+            match digits(&self.rest[len..]) {
+                0 => return Err(()),
+                n => len += n,
+            }
+        }
+
+        let (number, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        match number.parse::<f64>() {
+            Ok(x) if x.is_finite() => Ok(Json::Number(x)),
+            _ => Err(()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ()> {
+        let mut chars = self.rest.strip_prefix('"').ok_or(())?.char_indices();
+        let mut s = String::new();
+
+        loop {
+            let (i, c) = chars.next().ok_or(())?;
+            match c {
+                '"' => {
+                    self.rest = &self.rest[1 + i + 1..];
+                    return Ok(s);
+                }
+                '\\' => s.push(match chars.next().ok_or(())?.1 {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => {
+                        // a character outside the basic plane is a pair of surrogates
+                        let code = match hex_escape(&mut chars)? {
+                            high @ 0xd800..=0xdbff => {
+                                if chars.next().map(|(_, c)| c) != Some('\\')
+                                    || chars.next().map(|(_, c)| c) != Some('u')
+                                {
+                                    return Err(());
+                                }
+                                match hex_escape(&mut chars)? {
+                                    low @ 0xdc00..=0xdfff => {
+                                        0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                                    }
+                                    _ => return Err(()),
+                                }
+                            }
+                            code => code,
+                        };
+
+                        char::from_u32(code).ok_or(())?
+                    }
+                    _ => return Err(()),
+                }),
+                c if c < ' ' => return Err(()),
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+/// The four hex digits of a `\u` escape in a string.
+fn hex_escape(chars: &mut CharIndices) -> Result<u32, ()> {
+    let digits: String = chars.take(4).map(|(_, c)| c).collect();
+    if digits.len() != 4 {
+        return Err(());
+    }
+
+    u32::from_str_radix(&digits, 16).map_err(|_| ())
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Boolean(b) => write!(f, "{}", b),
+            Json::Number(x) => write!(f, "{}", x),
+            Json::String(s) => write_string(f, s),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ": {}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// A json string, in quotes, escaping quotes, backslashes and control characters.
+fn write_string(f: &mut Formatter, s: &str) -> Result<(), std::fmt::Error> {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl PartialEq for Json {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Json {}
+
+impl PartialOrd for Json {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Values of the same kind are in order (arrays and objects by their elements and fields in
+/// turn), and otherwise null comes first, then strings, numbers, booleans, arrays and objects.
+impl Ord for Json {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Json::Boolean(l), Json::Boolean(r)) => l.cmp(r),
+            // with zero equal to negative zero
+            (Json::Number(l), Json::Number(r)) => (l + 0.0).total_cmp(&(r + 0.0)),
+            (Json::String(l), Json::String(r)) => l.cmp(r),
+            (Json::Array(l), Json::Array(r)) => l.cmp(r),
+            (Json::Object(l), Json::Object(r)) => l.cmp(r),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl Hash for Json {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Json::Null => {}
+            Json::Boolean(b) => b.hash(state),
+            Json::Number(x) => (x + 0.0).to_bits().hash(state),
+            Json::String(s) => s.hash(state),
+            Json::Array(elements) => elements.hash(state),
+            Json::Object(fields) => fields.hash(state),
+        }
+    }
+}
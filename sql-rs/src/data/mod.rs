@@ -4,6 +4,9 @@
 mod database;
 pub use database::*;
 
+mod transaction;
+pub use transaction::*;
+
 mod value;
 pub use value::*;
 
@@ -19,5 +22,29 @@ pub use datatype::*;
 mod table;
 pub use table::*;
 
+mod join;
+pub use join::*;
+
+mod vector;
+pub use vector::*;
+
 mod value_list;
 pub use value_list::*;
+
+mod decimal;
+pub use decimal::*;
+
+mod datetime;
+pub use datetime::*;
+
+mod json;
+pub use json::*;
+
+mod uuid;
+pub use uuid::*;
+
+mod constraint;
+pub use constraint::*;
+
+mod index;
+pub use index::*;
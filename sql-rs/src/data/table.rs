@@ -1,10 +1,10 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt::{Display, Formatter};
 use std::mem;
 
-use super::{Column, Row, Value};
+use super::{Column, Constraint, Index, IndexMethod, Row, Value};
 use crate::execute::RowEvaluationContext;
-use crate::parse::ast::{ColumnIdentifier, JoinKind};
+use crate::parse::ast::ColumnIdentifier;
 
 /// TODO short description.
 ///
@@ -13,6 +13,8 @@ use crate::parse::ast::{ColumnIdentifier, JoinKind};
 pub struct Table {
     pub columns: Vec<Column>,
     pub rows: Vec<Row>,
+    pub constraints: Vec<Constraint>,
+    pub indexes: Vec<Index>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -24,41 +26,30 @@ pub enum Error {
 // fundamental table operations
 
 impl Table {
-    pub fn get_column_idx(columns: &Vec<Column>, column_identifier: &ColumnIdentifier) -> usize {
+    /// The index of the column a column identifier refers to, if there is one.
+    pub fn find_column_idx(
+        columns: &[Column],
+        column_identifier: &ColumnIdentifier,
+    ) -> Option<usize> {
         // TODO make sure that the column identifier uniquely specifies a column within the table
-        let (column_idx, _) = columns
-            .iter()
-            .enumerate()
-            .find(|(_, column)| {
-                let name = {
-                    let mut parts = column.name.split(".");
-
-                    let first = parts.next().unwrap();
-                    let second = parts.next();
-
-                    match second {
-                        Some(name) => name,
-                        None => first,
-                    }
-                };
-
-                match &column_identifier.alias {
-                    None => name == column_identifier.as_string(),
-                    Some(_) => column.name == column_identifier.as_string(),
-                }
-            })
-            .unwrap();
+        columns.iter().position(|column| {
+            let name = {
+                let mut parts = column.name.split(".");
 
-        column_idx
-    }
+                let first = parts.next().unwrap();
+                let second = parts.next();
 
-    pub fn filter<F: Fn(RowEvaluationContext) -> bool>(&mut self, predicate: F) {
-        let mut columns = Vec::new();
-        mem::swap(&mut columns, &mut self.columns);
-
-        self.rows.retain(|row| predicate((&columns, row)));
+                match second {
+                    Some(name) => name,
+                    None => first,
+                }
+            };
 
-        mem::swap(&mut columns, &mut self.columns);
+            match &column_identifier.alias {
+                None => name == column_identifier.as_string(),
+                Some(_) => column.name == column_identifier.as_string(),
+            }
+        })
     }
 
     pub fn filter_mut<F: Fn(RowEvaluationContext) -> bool>(
@@ -76,147 +67,182 @@ impl Table {
         rows
     }
 
-    pub fn limit(&mut self, limit: usize) {
-        self.rows.truncate(limit);
+    // resulting table contains all rows of both tables
+    pub fn union(&mut self, _other: &mut Table) -> Result<(), Error> {
+        todo!()
     }
+}
 
-    pub fn sort<K: Ord, F: Fn(RowEvaluationContext) -> K>(&mut self, key_fn: F) {
-        let mut columns = Vec::new();
-        mem::swap(&mut columns, &mut self.columns);
-
-        self.rows
-            .sort_unstable_by_key(|row| key_fn((&columns, row)));
+// utilities
 
-        mem::swap(&mut columns, &mut self.columns);
+impl Table {
+    pub fn prefix_column_names(&mut self, prefix: &str) {
+        for column in &mut self.columns {
+            column.name.insert_str(0, prefix);
+        }
     }
 
-    // resulting table contains all rows of both tables
-    pub fn union(&mut self, _other: &mut Table) -> Result<(), Error> {
-        todo!()
+    pub fn find_column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name == name)
     }
+}
 
-    // TODO re-write. make cleaner.
-    // Nested loop join.
-    pub fn join<F: Fn(RowEvaluationContext) -> bool>(
-        &mut self,
-        mut rhs: Table,
-        predicate: F,
-        join_kind: JoinKind,
-    ) {
-        let lhs_column_count = self.columns.len();
-        let rhs_column_count = rhs.columns.len();
+// changing rows, keeping indexes up to date
 
-        self.columns.append(&mut rhs.columns);
+impl Table {
+    pub fn push_row(&mut self, row: Row) {
+        self.rows.push(row);
 
-        let mut columns = Vec::new();
-        mem::swap(&mut columns, &mut self.columns);
+        let position = self.rows.len() - 1;
+        for i in 0..self.indexes.len() {
+            let key = self.index_key(i, &self.rows[position]);
+            self.indexes[i].insert(key, position);
+        }
+    }
+
+    /// The rows of the table once the rows at the positions in `changed` are replaced.
+    pub fn updated_rows<'a>(
+        &'a self,
+        changed: &'a BTreeMap<usize, Row>,
+    ) -> impl Iterator<Item = &'a Row> + Clone {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(move |(position, row)| changed.get(&position).unwrap_or(row))
+    }
 
-        let outer_iter = || match join_kind {
-            JoinKind::Right => rhs.rows.iter(),
-            _ => self.rows.iter(),
-        };
-        let inner_iter = || match join_kind {
-            JoinKind::Right => self.rows.iter(),
-            _ => rhs.rows.iter(),
-        };
-
-        let mut rows = Vec::new();
-        for i in outer_iter() {
-            let mut did_add_row = false;
-
-            for j in inner_iter() {
-                let mut row = i.0.clone();
-                row.append(&mut j.0.clone());
-                let row = Row(row);
-                if predicate((&columns, &row)) {
-                    rows.push(row);
-                    did_add_row = true;
+    /// Replace the rows at the positions in `changed`, moving each to its new key in every index
+    /// whose key it changes.
+    pub fn update_rows(&mut self, changed: BTreeMap<usize, Row>) {
+        for (position, row) in changed {
+            for i in 0..self.indexes.len() {
+                let old = self.index_key(i, &self.rows[position]);
+                let new = self.index_key(i, &row);
+
+                if old != new {
+                    self.indexes[i].remove(&old, position);
+                    self.indexes[i].insert(new, position);
                 }
             }
 
-            if !did_add_row {
-                let row = match join_kind {
-                    JoinKind::Left => {
-                        let mut row = i.0.clone();
-                        let mut nulls = {
-                            let mut nulls = Vec::with_capacity(rhs_column_count);
-                            for _i in 0..rhs_column_count {
-                                nulls.push(Value::Null);
-                            }
-                            nulls
-                        };
-                        row.append(&mut nulls);
-                        Some(Row(row))
-                    }
-                    JoinKind::Right => {
-                        let mut row = i.0.clone();
-                        let mut nulls = {
-                            let mut nulls = Vec::with_capacity(lhs_column_count);
-                            for _i in 0..lhs_column_count {
-                                nulls.push(Value::Null);
-                            }
-                            nulls
-                        };
-                        nulls.append(&mut row);
-                        Some(Row(nulls))
-                    }
-                    _ => None,
-                };
-
-                if row.is_some() {
-                    rows.push(row.unwrap());
-                }
+            self.rows[position] = row;
+        }
+    }
+
+    /// Remove the rows at the positions in `deleted`, along with their keys, moving the rows
+    /// after each one back.
+    pub fn delete_rows(&mut self, deleted: &BTreeSet<usize>) {
+        for &position in deleted {
+            for i in 0..self.indexes.len() {
+                let key = self.index_key(i, &self.rows[position]);
+                self.indexes[i].remove(&key, position);
             }
         }
 
-        mem::swap(&mut self.rows, &mut rows);
-        mem::swap(&mut columns, &mut self.columns);
+        let deleted: Vec<_> = deleted.iter().copied().collect();
+        for index in &mut self.indexes {
+            index.move_positions(|position| position - deleted.partition_point(|&d| d < position));
+        }
+
+        let mut position = 0;
+        self.rows.retain(|_| {
+            let keep = deleted.binary_search(&position).is_err();
+            position += 1;
+            keep
+        });
+    }
+
+    /// Add an index over `columns`, which must exist, covering the rows already in the table.
+    pub fn create_index(&mut self, name: String, columns: Vec<String>, method: IndexMethod) {
+        self.indexes.push(Index::new(name, columns, method));
+        self.rebuild_index(self.indexes.len() - 1);
+    }
+
+    fn rebuild_index(&mut self, i: usize) {
+        let keys: Vec<_> = self.rows.iter().map(|row| self.index_key(i, row)).collect();
+
+        let index = &mut self.indexes[i];
+        index.clear();
+        for (position, key) in keys.into_iter().enumerate() {
+            index.insert(key, position);
+        }
+    }
+
+    /// The values of `row` covered by the index at `i`.
+    fn index_key(&self, i: usize, row: &Row) -> Vec<Value> {
+        self.indexes[i]
+            .columns
+            .iter()
+            .map(|c| row.0[self.find_column(c).unwrap()].clone())
+            .collect()
     }
 }
 
-// utilities
+// schema changes, rewriting every row to match the new columns
 
 impl Table {
-    pub fn prefix_column_names(&mut self, prefix: &str) {
-        for column in &mut self.columns {
-            column.name.insert_str(0, prefix);
+    /// Add a column, which is null for every existing row.
+    pub fn add_column(&mut self, column: Column) {
+        self.columns.push(column);
+
+        for row in &mut self.rows {
+            row.0.push(Value::Null);
+        }
+    }
+
+    /// Drop a column, along with any constraint or index involving it.
+    pub fn drop_column(&mut self, column_index: usize) {
+        let column = self.columns.remove(column_index);
+        self.constraints
+            .retain(|constraint| !constraint.columns().contains(&column.name));
+        self.indexes
+            .retain(|index| !index.columns.contains(&column.name));
+
+        for row in &mut self.rows {
+            row.0.remove(column_index);
         }
     }
+
+    pub fn rename_column(&mut self, column_index: usize, name: &str) {
+        let column = &mut self.columns[column_index];
+
+        for constraint in &mut self.constraints {
+            constraint.rename_column(&column.name, name);
+        }
+        for index in &mut self.indexes {
+            for c in &mut index.columns {
+                if *c == column.name {
+                    *c = name.to_owned();
+                }
+            }
+        }
+
+        column.name = name.to_owned();
+    }
 }
 
 impl Table {
+    /// Map the columns named by an insert query to their indices in the table. Every column
+    /// must exist, and be named at most once, but columns may be left out (to take their
+    /// defaults).
     pub fn validate_insert_query_columns(
         &self,
         insert_query_columns: &[&str],
     ) -> Option<Vec<usize>> {
-        // first check that all columns are provided
+        // first check that no column is provided twice
         {
-            let self_columns: HashSet<&str> =
-                self.columns.iter().map(|c| c.name.as_str()).collect();
-            let insert_query_columns: HashSet<&str> =
-                insert_query_columns.iter().map(|c| *c).collect();
+            let distinct_columns: HashSet<&str> = insert_query_columns.iter().copied().collect();
 
-            if self_columns != insert_query_columns {
+            if distinct_columns.len() != insert_query_columns.len() {
                 return None;
             }
         }
 
         // then map the insert_query_column to the index in the table specificiation
-        let self_columns: Vec<(usize, &str)> = self
-            .columns
-            .iter()
-            .map(|c| c.name.as_str())
-            .enumerate()
-            .collect();
-        let indices = insert_query_columns
+        insert_query_columns
             .iter()
-            .map(|c1| self_columns.iter().find(|(_, c2)| c1 == c2).unwrap().0)
-            .collect();
-        Some(indices)
-    }
-
-    pub fn new_values_vec(&self) -> Vec<Value> {
-        vec![Value::Null; self.columns.len()]
+            .map(|c| self.find_column(c))
+            .collect()
     }
 
     pub fn compatible_type(&self, column_index: usize, value: &Value) -> bool {
@@ -224,19 +250,9 @@ impl Table {
         value.assignable_to(column.datatype)
     }
 
-    pub fn get_update_indices(&self, cols: &[&str]) -> Vec<usize> {
-        let self_columns: Vec<(usize, &str)> = self
-            .columns
-            .iter()
-            .map(|c| c.name.as_str())
-            .enumerate()
-            .collect();
-        let indices = cols
-            .iter()
-            .map(|c1| self_columns.iter().find(|(_, c2)| c1 == c2).unwrap().0)
-            .collect();
-
-        indices
+    /// The index of each column an update assigns to, or `None` if one isn't in the table.
+    pub fn get_update_indices(&self, cols: &[&str]) -> Option<Vec<usize>> {
+        cols.iter().map(|c| self.find_column(c)).collect()
     }
 }
 
@@ -248,7 +264,7 @@ impl Display for Table {
         writeln!(f, "{}", columns.join(","))?;
 
         // write rows
-        if self.rows.len() > 0 {
+        if !self.rows.is_empty() {
             let num_rows = self.rows.len();
             for row in &self.rows[..num_rows - 1] {
                 let values: Vec<_> = row.0.iter().map(|v| v.to_string()).collect();
@@ -0,0 +1,143 @@
+use std::collections::{BTreeSet, HashMap};
+use std::mem;
+use std::sync::Arc;
+
+use super::{Table, Timestamp};
+
+/// The changes made to a database since a transaction began.
+///
+/// The first time a query in the transaction is about to change a table, the version of the table
+/// it changes is kept, so that rolling back to a savepoint only has to put back the tables which
+/// were changed since. Each savepoint
+/// keeps the tables changed after it separately, so that the transaction can be rolled back to
+/// it instead.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    /// The tables changed by the transaction before its first savepoint as they were in its
+    /// snapshot, or `None` for those which didn't exist.
+    pub originals: HashMap<String, Option<Arc<Table>>>,
+    /// The savepoints of the transaction, oldest first.
+    pub savepoints: Vec<Savepoint>,
+    /// The queries which changed the database, recorded in the write-ahead log together once the
+    /// transaction commits.
+    pub statements: Vec<String>,
+    /// The tables which have to be written out again in full once the transaction commits.
+    pub rewritten: BTreeSet<String>,
+    /// Whether a query in the transaction failed, so that it can only be rolled back, or rolled
+    /// back to a savepoint.
+    pub failed: bool,
+    /// The time the transaction began, which is the time every query in it calls the current
+    /// time.
+    pub started: Timestamp,
+    /// The seed of the uuids generated by the queries in the transaction which change the
+    /// database, logged along with them.
+    pub seed: u64,
+}
+
+/// A point in a transaction which it can be rolled back to.
+#[derive(Debug)]
+pub struct Savepoint {
+    pub name: String,
+    /// The tables changed since the savepoint, and not since an earlier one, as they were at the
+    /// savepoint.
+    pub originals: HashMap<String, Option<Arc<Table>>>,
+    /// The number of the transaction's queries which changed the database before the savepoint.
+    pub statements: usize,
+}
+
+impl Transaction {
+    /// Keep the tables as they are before a query changes them, unless they've already been kept
+    /// since the latest savepoint.
+    pub fn keep(&mut self, tables: &HashMap<String, Arc<Table>>, names: &[String]) {
+        let originals = match self.savepoints.last_mut() {
+            Some(savepoint) => &mut savepoint.originals,
+            None => &mut self.originals,
+        };
+
+        for name in names {
+            if !originals.contains_key(name) {
+                originals.insert(name.clone(), tables.get(name).cloned());
+            }
+        }
+    }
+
+    pub fn savepoint(&mut self, name: &str) {
+        self.savepoints.push(Savepoint {
+            name: name.to_owned(),
+            originals: HashMap::new(),
+            statements: self.statements.len(),
+        });
+    }
+
+    /// Undo the changes made since the latest savepoint with a name, which is kept, and forget
+    /// the savepoints after it. Returns `false` if there's no such savepoint.
+    pub fn rollback_to(&mut self, name: &str, tables: &mut HashMap<String, Arc<Table>>) -> bool {
+        let position = match self.find(name) {
+            Some(position) => position,
+            None => return false,
+        };
+
+        // put back the newest originals first, so a table changed after several savepoints ends
+        // up as it was at the oldest
+        for savepoint in self.savepoints.drain(position + 1..).rev() {
+            restore(savepoint.originals, tables);
+        }
+        let savepoint = &mut self.savepoints[position];
+        restore(mem::take(&mut savepoint.originals), tables);
+        self.statements.truncate(savepoint.statements);
+        self.failed = false;
+
+        true
+    }
+
+    /// Forget the latest savepoint with a name, and those after it, keeping the changes made since.
+    /// Returns `false` if there's no such savepoint.
+    pub fn release(&mut self, name: &str) -> bool {
+        let position = match self.find(name) {
+            Some(position) => position,
+            None => return false,
+        };
+
+        let released: Vec<_> = self.savepoints.drain(position..).collect();
+        let originals = match self.savepoints.last_mut() {
+            Some(savepoint) => &mut savepoint.originals,
+            None => &mut self.originals,
+        };
+        // a table already kept before the released savepoints was kept as it was earlier
+        for savepoint in released {
+            for (name, original) in savepoint.originals {
+                originals.entry(name).or_insert(original);
+            }
+        }
+
+        true
+    }
+
+    /// The names of the tables changed by the transaction, which replace the committed versions
+    /// once it commits.
+    pub fn changed(&self) -> impl Iterator<Item = &String> {
+        self.originals.keys().chain(
+            self.savepoints
+                .iter()
+                .flat_map(|savepoint| savepoint.originals.keys()),
+        )
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.savepoints
+            .iter()
+            .rposition(|savepoint| savepoint.name == name)
+    }
+}
+
+fn restore(
+    originals: HashMap<String, Option<Arc<Table>>>,
+    tables: &mut HashMap<String, Arc<Table>>,
+) {
+    for (name, original) in originals {
+        match original {
+            Some(table) => tables.insert(name, table),
+            None => tables.remove(&name),
+        };
+    }
+}
@@ -0,0 +1,133 @@
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::convert::TryInto;
+use std::fmt::{Display, Formatter};
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+
+use super::{decode_hex, Hex};
+
+/// A universally unique identifier: 16 bytes, written as 32 hex digits in groups of 8, 4, 4, 4
+/// and 12 separated by hyphens, such as `a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid([u8; 16]);
+
+thread_local! {
+    /// The state of the generator the seeds of statements executed on this thread are drawn
+    /// from, which is seeded randomly when it's first used, unless it was seeded already.
+    static SEEDS: Cell<Option<u64>> = const { Cell::new(None) };
+    /// The seed of the uuids generated by the statement executing on this thread, or by the
+    /// transaction it's part of.
+    static STATEMENT_SEED: Cell<u64> = const { Cell::new(0) };
+    /// The state of the generator of the statement's uuids.
+    static STATEMENT_UUIDS: Cell<u64> = const { Cell::new(0) };
+}
+
+impl Uuid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Uuid(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Seed the generator of uuids on this thread, so that the same statements executed after
+    /// it generate the same uuids each time.
+    pub fn seed(seed: u64) {
+        SEEDS.with(|cell| cell.set(Some(seed)));
+    }
+
+    /// A new seed for the uuids generated by a statement (or by the statements of a
+    /// transaction), drawn from this thread's generator.
+    pub fn new_seed() -> u64 {
+        SEEDS.with(|cell| {
+            let mut state = cell
+                .get()
+                .unwrap_or_else(|| RandomState::new().build_hasher().finish());
+            let seed = splitmix(&mut state);
+            cell.set(Some(state));
+
+            seed
+        })
+    }
+
+    /// The seed of the uuids generated by the statement executing on this thread.
+    pub fn statement_seed() -> u64 {
+        STATEMENT_SEED.with(Cell::get)
+    }
+
+    /// Set the seed of the uuids generated by the statement about to be executed on this thread,
+    /// the statement at `index` of those generating uuids from the seed (the statements of a
+    /// transaction share its seed).
+    pub fn set_statement_seed(seed: u64, index: usize) {
+        STATEMENT_SEED.with(|cell| cell.set(seed));
+        STATEMENT_UUIDS.with(|cell| cell.set(mix(seed ^ mix(index as u64))));
+    }
+
+    /// A random (version 4) uuid, the next one generated by the statement executing on this
+    /// thread.
+    pub fn generate() -> Self {
+        STATEMENT_UUIDS.with(|cell| {
+            let mut state = cell.get();
+            let mut bytes = [0; 16];
+            bytes[..8].copy_from_slice(&splitmix(&mut state).to_be_bytes());
+            bytes[8..].copy_from_slice(&splitmix(&mut state).to_be_bytes());
+            cell.set(state);
+
+            // the version, and the variant of the layout
+            bytes[6] = bytes[6] & 0x0f | 0x40;
+            bytes[8] = bytes[8] & 0x3f | 0x80;
+
+            Uuid(bytes)
+        })
+    }
+}
+
+/// The next number of a SplitMix64 generator.
+fn splitmix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    mix(*state)
+}
+
+/// Scrambles the bits of a number, so that close numbers give unrelated ones.
+fn mix(z: u64) -> u64 {
+    let z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    z ^ (z >> 31)
+}
+
+/// Parses a uuid written with hyphens between its groups of hex digits, in either case.
+impl FromStr for Uuid {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let groups: Vec<&str> = s.split('-').collect();
+        let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+        if lengths != [8, 4, 4, 4, 12] {
+            return Err(());
+        }
+
+        let bytes = decode_hex(&groups.concat()).ok_or(())?;
+
+        Ok(Uuid(bytes.try_into().unwrap()))
+    }
+}
+
+impl Display for Uuid {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let b = &self.0;
+
+        write!(
+            f,
+            "{}-{}-{}-{}-{}",
+            Hex(&b[..4]),
+            Hex(&b[4..6]),
+            Hex(&b[6..8]),
+            Hex(&b[8..10]),
+            Hex(&b[10..])
+        )
+    }
+}
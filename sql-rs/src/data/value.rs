@@ -1,78 +1,518 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
 
-use crate::data::{Datatype, ValueList};
+use crate::data::{
+    Datatype, Date, Decimal, Interval, Json, Time, Timestamp, Uuid, ValueList, MICROS_PER_DAY,
+};
 use crate::parse::ast::ExpressionOp;
 
 /// TODO short description.
 ///
 /// TODO long description.
-// TODO remove PartialEq and Eq
 // TODO this and Datatype are very similar
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone)]
 pub enum Value {
     Null,
-    Number(u32),
+    Integer(i64),
+    /// A real number, which is always finite.
+    Real(f64),
+    Numeric(Decimal),
     Text(String),
     Boolean(bool),
+    Date(Date),
+    Time(Time),
+    Timestamp(Timestamp),
+    Interval(Interval),
+    Blob(Vec<u8>),
+    Json(Json),
+    Uuid(Uuid),
     List(ValueList),
 }
 
+/// A value which can't be computed, or can't be stored as a datatype.
+#[derive(Debug, thiserror::Error)]
+pub enum ValueError {
+    #[error("{0} out of range")]
+    OutOfRange(Datatype),
+    #[error(
+        "numeric field overflow: a numeric({precision}, {scale}) value must round to an absolute \
+         value less than 10^{}",
+        .precision - .scale
+    )]
+    NumericFieldOverflow { precision: u32, scale: u32 },
+    #[error("invalid input syntax for type {datatype}: \"{input}\"")]
+    InvalidSyntax { datatype: Datatype, input: String },
+    #[error("unit \"{unit}\" not supported for type {datatype}")]
+    UnsupportedUnit { unit: String, datatype: Datatype },
+    #[error("function {0} does not exist")]
+    UndefinedFunction(String),
+    #[error("negative substring length not allowed")]
+    NegativeSubstringLength,
+    #[error("operator does not exist: {0}")]
+    UndefinedOperator(String),
+    #[error("invalid json path: \"{0}\"")]
+    InvalidJsonPath(String),
+    #[error("column \"{0}\" does not exist")]
+    UndefinedColumn(String),
+    #[error("a condition must be of type boolean, not {0}")]
+    NotBoolean(Datatype),
+    #[error("a subquery can only be used as the list of values on the right of IN")]
+    ScalarSubquery,
+    #[error("subquery has too many columns")]
+    SubqueryColumns,
+    #[error(transparent)]
+    Subquery(Box<dyn std::error::Error + Send + Sync>),
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
         match self {
-            Value::Number(n) => write!(f, "{}", n),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Real(x) => write_real(f, *x),
+            Value::Numeric(d) => write!(f, "{}", d),
             Value::Text(s) => write!(f, "{}", s),
             Value::Boolean(b) => write!(f, "{}", b),
+            Value::Date(d) => write!(f, "{}", d),
+            Value::Time(t) => write!(f, "{}", t),
+            Value::Timestamp(t) => write!(f, "{}", t),
+            Value::Interval(i) => write!(f, "{}", i),
+            Value::Blob(b) => write!(f, "\\x{}", Hex(b)),
+            Value::Json(j) => write!(f, "{}", j),
+            Value::Uuid(u) => write!(f, "{}", u),
             Value::Null => write!(f, "null"),
             Value::List(l) => write!(f, "{}", l),
         }
     }
 }
 
+/// Write a real with the fewest digits which read back as it, the way PostgreSQL does: with an
+/// exponent once it's below 1e-4 or at least 1e15 (so `1e+300`, rather than 301 digits).
+fn write_real(f: &mut Formatter, x: f64) -> Result<(), std::fmt::Error> {
+    let scientific = format!("{:e}", x);
+    let (mantissa, exponent) = match scientific.split_once('e') {
+        Some((mantissa, exponent)) if x != 0.0 => (mantissa, exponent.parse::<i32>().unwrap()),
+        // zero, infinity or not a number
+        _ => return write!(f, "{}", x),
+    };
+
+    if (-4..15).contains(&exponent) {
+        write!(f, "{}", x)
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        write!(f, "{}e{}{:02}", mantissa, sign, exponent.abs())
+    }
+}
+
+/// Two numbers, promoted to the same datatype.
+enum Numbers {
+    Integer(i64, i64),
+    Real(f64, f64),
+    Numeric(Decimal, Decimal),
+}
+
 impl Value {
-    pub fn op(self, op: ExpressionOp, rhs: Value) -> Value {
-        match op {
-            ExpressionOp::GreaterEqual => Value::Boolean(self.greater_equal(&rhs)),
-            ExpressionOp::LessEqual => Value::Boolean(self.less_equal(&rhs)),
-            ExpressionOp::Greater => Value::Boolean(self.greater(&rhs)),
-            ExpressionOp::Less => Value::Boolean(self.less(&rhs)),
-            ExpressionOp::And => Value::Boolean(self.is_true() && rhs.is_true()),
-            ExpressionOp::Or => Value::Boolean(self.is_true() || rhs.is_true()),
-            ExpressionOp::In => {
-                assert!(!matches!(self, Value::List(_)));
-                assert!(matches!(rhs, Value::List(_)));
-
-                match rhs {
-                    Value::List(l) => Value::Boolean(l.contains(self)),
-                    _ => unreachable!(),
+    pub fn op(self, op: ExpressionOp, rhs: Value) -> Result<Value, ValueError> {
+        Ok(match op {
+            // comparisons and arithmetic with an unknown value are unknown
+            ExpressionOp::GreaterEqual
+            | ExpressionOp::LessEqual
+            | ExpressionOp::Greater
+            | ExpressionOp::Less
+            | ExpressionOp::Equal
+            | ExpressionOp::Plus
+            | ExpressionOp::Minus
+            | ExpressionOp::JsonGet
+            | ExpressionOp::JsonGetText
+                if matches!(self, Value::Null) || matches!(rhs, Value::Null) =>
+            {
+                Value::Null
+            }
+            ExpressionOp::GreaterEqual => Value::Boolean(self.compare(op, &rhs)?.is_ge()),
+            ExpressionOp::LessEqual => Value::Boolean(self.compare(op, &rhs)?.is_le()),
+            ExpressionOp::Greater => Value::Boolean(self.compare(op, &rhs)?.is_gt()),
+            ExpressionOp::Less => Value::Boolean(self.compare(op, &rhs)?.is_lt()),
+            // three-valued logic: false and unknown is false, true or unknown is true
+            ExpressionOp::And => match (self, rhs) {
+                (Value::Boolean(false), _) | (_, Value::Boolean(false)) => Value::Boolean(false),
+                (Value::Null, _) | (_, Value::Null) => Value::Null,
+                (lhs, rhs) => Value::Boolean(lhs.is_true()? && rhs.is_true()?),
+            },
+            ExpressionOp::Or => match (self, rhs) {
+                (Value::Boolean(true), _) | (_, Value::Boolean(true)) => Value::Boolean(true),
+                (Value::Null, _) | (_, Value::Null) => Value::Null,
+                (lhs, rhs) => Value::Boolean(lhs.is_true()? || rhs.is_true()?),
+            },
+            ExpressionOp::In => match rhs {
+                Value::List(l) => Value::Boolean(l.contains(self)),
+                _ => return Err(self.undefined_operator(op, &rhs)),
+            },
+            ExpressionOp::Equal => {
+                let (lhs, rhs) = Value::coerce(&self, &rhs)?;
+                Value::Boolean(lhs.equals(&rhs))
+            }
+            ExpressionOp::Plus | ExpressionOp::Minus => self.arithmetic(op, &rhs)?,
+            ExpressionOp::JsonGet | ExpressionOp::JsonGetText => self.json_get(op, &rhs)?,
+        })
+    }
+
+    /// The field of a json object with a key, or the element of a json array at an index (see
+    /// [`Json::index`]), as json for `->`, or as text for `->>` (see [`Json::as_text`]). Unknown
+    /// (null) if there isn't one.
+    fn json_get(&self, op: ExpressionOp, rhs: &Value) -> Result<Value, ValueError> {
+        let value = match (self, rhs) {
+            (Value::Json(j), Value::Text(key)) => j.get(key),
+            (Value::Json(j), Value::Integer(index)) => j.index(*index),
+            _ => return Err(self.undefined_operator(op, rhs)),
+        };
+
+        Ok(match (value, op) {
+            (None, _) => Value::Null,
+            (Some(value), ExpressionOp::JsonGet) => Value::Json(value.clone()),
+            (Some(value), _) => value.as_text().map_or(Value::Null, Value::Text),
+        })
+    }
+
+    /// Whether this value equals another. Numbers of different datatypes are equal if they're
+    /// the same number, a date equals a timestamp of midnight on it, and text equals a value of
+    /// a datatype it's parsed as (see [`Value::coerce`]) if it's parsed as the same value, but
+    /// other values of different datatypes never are.
+    pub fn equals(&self, rhs: &Value) -> bool {
+        let (lhs, rhs) = match Value::coerce(self, rhs) {
+            Ok(operands) => operands,
+            Err(_) => return false,
+        };
+
+        let compared = || {
+            lhs.compare(ExpressionOp::Equal, &rhs)
+                .is_ok_and(Ordering::is_eq)
+        };
+        match (&*lhs, &*rhs) {
+            (Value::Date(_), Value::Timestamp(_)) | (Value::Timestamp(_), Value::Date(_)) => {
+                compared()
+            }
+            (l, r) => match Value::promote(l, r) {
+                Some(_) => compared(),
+                None => l == r,
+            },
+        }
+    }
+
+    /// The operands of a comparison, with text compared with a value of a datatype text is
+    /// parsed as when it's stored (a date, time, timestamp, interval, blob, json, or uuid) parsed
+    /// as one, so that `day > '2024-01-15'` compares dates. Fails if the text doesn't parse.
+    fn coerce<'a>(
+        lhs: &'a Value,
+        rhs: &'a Value,
+    ) -> Result<(Cow<'a, Value>, Cow<'a, Value>), ValueError> {
+        Ok(match (lhs, rhs) {
+            (Value::Text(s), other) if other.parsed_from_text() => (
+                Cow::Owned(Value::parse(s, other.datatype())?),
+                Cow::Borrowed(rhs),
+            ),
+            (other, Value::Text(s)) if other.parsed_from_text() => (
+                Cow::Borrowed(lhs),
+                Cow::Owned(Value::parse(s, other.datatype())?),
+            ),
+            _ => (Cow::Borrowed(lhs), Cow::Borrowed(rhs)),
+        })
+    }
+
+    /// Whether text stored in a column of this value's datatype is parsed as a value of it.
+    fn parsed_from_text(&self) -> bool {
+        matches!(
+            self,
+            Value::Date(_)
+                | Value::Time(_)
+                | Value::Timestamp(_)
+                | Value::Interval(_)
+                | Value::Blob(_)
+                | Value::Json(_)
+                | Value::Uuid(_)
+        )
+    }
+
+    /// How this value compares with another, for the comparison `op`. Numbers of different
+    /// datatypes are promoted first, a date compared with a timestamp is midnight on it, and text
+    /// is parsed as the other value's datatype (see [`Value::coerce`]). Fails for values of other
+    /// datatypes, which can't be compared.
+    fn compare(&self, op: ExpressionOp, rhs: &Value) -> Result<Ordering, ValueError> {
+        let (lhs, rhs) = Value::coerce(self, rhs)?;
+
+        Ok(match (&*lhs, &*rhs) {
+            (Value::Text(l), Value::Text(r)) => l.cmp(r),
+            (Value::Boolean(l), Value::Boolean(r)) => l.cmp(r),
+            (Value::Date(l), Value::Date(r)) => l.cmp(r),
+            (Value::Time(l), Value::Time(r)) => l.cmp(r),
+            (Value::Timestamp(l), Value::Timestamp(r)) => l.cmp(r),
+            (Value::Interval(l), Value::Interval(r)) => l.cmp(r),
+            (Value::Blob(l), Value::Blob(r)) => l.cmp(r),
+            (Value::Json(l), Value::Json(r)) => l.cmp(r),
+            (Value::Uuid(l), Value::Uuid(r)) => l.cmp(r),
+            (Value::Date(l), Value::Timestamp(r)) => Timestamp::from(*l).cmp(r),
+            (Value::Timestamp(l), Value::Date(r)) => l.cmp(&Timestamp::from(*r)),
+            _ => match Value::promote(&lhs, &rhs) {
+                Some(Numbers::Integer(l, r)) => l.cmp(&r),
+                Some(Numbers::Real(l, r)) => compare_reals(l, r),
+                Some(Numbers::Numeric(l, r)) => l.cmp(&r),
+                None => return Err(lhs.undefined_operator(op, &rhs)),
+            },
+        })
+    }
+
+    /// The sum or difference of two numbers, failing if it's out of range of their datatype.
+    fn arithmetic(&self, op: ExpressionOp, rhs: &Value) -> Result<Value, ValueError> {
+        let minus = matches!(op, ExpressionOp::Minus);
+
+        match Value::promote(self, rhs) {
+            Some(Numbers::Integer(l, r)) => match minus {
+                true => l.checked_sub(r),
+                false => l.checked_add(r),
+            }
+            .map(Value::Integer)
+            .ok_or(ValueError::OutOfRange(Datatype::Integer)),
+            Some(Numbers::Real(l, r)) => {
+                let result = if minus { l - r } else { l + r };
+                match result.is_finite() {
+                    true => Ok(Value::Real(result)),
+                    false => Err(ValueError::OutOfRange(Datatype::Real)),
                 }
             }
-            ExpressionOp::Equal => Value::Boolean(self == rhs),
-            ExpressionOp::Plus => Value::Number(self.as_number() + rhs.as_number()),
-            ExpressionOp::Minus => Value::Number(self.as_number() - rhs.as_number()),
+            Some(Numbers::Numeric(l, r)) => match minus {
+                true => l.checked_sub(&r),
+                false => l.checked_add(&r),
+            }
+            .map(Value::Numeric)
+            .ok_or(ValueError::OutOfRange(Datatype::Numeric(None))),
+            None => self.temporal_arithmetic(op, rhs),
+        }
+    }
+
+    /// The sum or difference of dates, times and intervals (or a date and a number of days),
+    /// failing if it's out of range of its datatype.
+    ///
+    /// Adding an interval to a date gives a timestamp, and so does adding a time to a date.
+    /// Subtracting one date from another gives the number of days between them, and one time or
+    /// timestamp from another an interval. Adding an interval to a time only adds its time,
+    /// wrapping around midnight.
+    fn temporal_arithmetic(&self, op: ExpressionOp, rhs: &Value) -> Result<Value, ValueError> {
+        let minus = matches!(op, ExpressionOp::Minus);
+        let interval = |i: &Interval| match minus {
+            true => i
+                .checked_neg()
+                .ok_or(ValueError::OutOfRange(Datatype::Interval)),
+            false => Ok(*i),
+        };
+        let add_interval = |t: Timestamp, i: &Interval| {
+            t.add_interval(&interval(i)?)
+                .map(Value::Timestamp)
+                .ok_or(ValueError::OutOfRange(Datatype::Timestamp))
+        };
+        let add_days = |d: &Date, n: i64| {
+            let n = if minus { n.checked_neg() } else { Some(n) };
+            n.and_then(|n| d.add_days(n))
+                .map(Value::Date)
+                .ok_or(ValueError::OutOfRange(Datatype::Date))
+        };
+
+        match (self, rhs, minus) {
+            (Value::Date(d), Value::Integer(n), _) | (Value::Integer(n), Value::Date(d), false) => {
+                add_days(d, *n)
+            }
+            (Value::Date(l), Value::Date(r), true) => Ok(Value::Integer(l.days() - r.days())),
+            (Value::Date(d), Value::Time(t), false) | (Value::Time(t), Value::Date(d), false) => {
+                Ok(Value::Timestamp(Timestamp::new(*d, *t)))
+            }
+            (Value::Date(d), Value::Interval(i), _)
+            | (Value::Interval(i), Value::Date(d), false) => add_interval((*d).into(), i),
+            (Value::Timestamp(t), Value::Interval(i), _)
+            | (Value::Interval(i), Value::Timestamp(t), false) => add_interval(*t, i),
+            (Value::Timestamp(l), Value::Timestamp(r), true) => l
+                .since(r)
+                .map(Value::Interval)
+                .ok_or(ValueError::OutOfRange(Datatype::Interval)),
+            (Value::Time(t), Value::Interval(i), _)
+            | (Value::Interval(i), Value::Time(t), false) => {
+                let micros = i.micros() % MICROS_PER_DAY;
+                Ok(Value::Time(t.add_micros(if minus {
+                    -micros
+                } else {
+                    micros
+                })))
+            }
+            (Value::Time(l), Value::Time(r), true) => Ok(Value::Interval(Interval::new(
+                0,
+                0,
+                l.micros() - r.micros(),
+            ))),
+            (Value::Interval(l), Value::Interval(r), _) => l
+                .checked_add(&interval(r)?)
+                .map(Value::Interval)
+                .ok_or(ValueError::OutOfRange(Datatype::Interval)),
+            _ => Err(self.undefined_operator(op, rhs)),
+        }
+    }
+
+    /// The error for an operator applied to values of datatypes it isn't defined for.
+    fn undefined_operator(&self, op: ExpressionOp, rhs: &Value) -> ValueError {
+        ValueError::UndefinedOperator(format!("{} {} {}", self.datatype(), op, rhs.datatype()))
+    }
+
+    /// Two numbers, promoted to the datatype arithmetic on them results in (see
+    /// [`Datatype::promote`]). `None` unless both are numbers.
+    fn promote(lhs: &Value, rhs: &Value) -> Option<Numbers> {
+        if !lhs.is_numeric() || !rhs.is_numeric() {
+            return None;
         }
+
+        Some(match lhs.datatype().promote(rhs.datatype()) {
+            Datatype::Integer => Numbers::Integer(lhs.as_integer(), rhs.as_integer()),
+            Datatype::Real => Numbers::Real(lhs.as_real(), rhs.as_real()),
+            _ => Numbers::Numeric(lhs.as_decimal(), rhs.as_decimal()),
+        })
+    }
+
+    /// The value of text written as a value of `datatype`, such as `2024-01-15` as a date, or
+    /// `\xdeadbeef` (hex digits after `\x`) as a blob, json, or a uuid. Leading and trailing
+    /// whitespace is ignored.
+    pub fn parse(s: &str, datatype: Datatype) -> Result<Value, ValueError> {
+        let invalid = || ValueError::InvalidSyntax {
+            datatype,
+            input: s.to_owned(),
+        };
+        let trimmed = s.trim();
+
+        Ok(match datatype {
+            Datatype::Date => Value::Date(trimmed.parse().map_err(|_| invalid())?),
+            Datatype::Time => Value::Time(trimmed.parse().map_err(|_| invalid())?),
+            Datatype::Timestamp => Value::Timestamp(trimmed.parse().map_err(|_| invalid())?),
+            Datatype::Interval => Value::Interval(trimmed.parse().map_err(|_| invalid())?),
+            Datatype::Blob => Value::Blob(
+                trimmed
+                    .strip_prefix("\\x")
+                    .and_then(decode_hex)
+                    .ok_or_else(invalid)?,
+            ),
+            Datatype::Json => Value::Json(trimmed.parse().map_err(|_| invalid())?),
+            Datatype::Uuid => Value::Uuid(trimmed.parse().map_err(|_| invalid())?),
+            _ => Value::Text(s.to_owned()),
+        })
+    }
+
+    /// This value converted to be stored in a column of `datatype`. A number of another numeric
+    /// datatype is converted (rounding to the nearest integer, for an integer), and a numeric
+    /// value is rounded to the column's scale. Text is parsed as a date, time, blob, json or uuid, a date is
+    /// converted to a timestamp at midnight, and a timestamp to its date. Other values are left
+    /// as they are.
+    pub fn cast(self, datatype: Datatype) -> Result<Value, ValueError> {
+        let out_of_range = ValueError::OutOfRange(datatype);
+
+        Ok(match (self, datatype) {
+            (Value::Text(s), _) => return Value::parse(&s, datatype),
+            (Value::Date(d), Datatype::Timestamp) => Value::Timestamp(d.into()),
+            (Value::Timestamp(t), Datatype::Date) => Value::Date(t.date()),
+            (Value::Integer(n), Datatype::Real) => Value::Real(n as f64),
+            (Value::Integer(n), Datatype::Numeric(_)) => {
+                return Value::Numeric(n.into()).cast(datatype)
+            }
+            (Value::Real(x), Datatype::Integer) => {
+                let rounded = x.round();
+                if rounded < i64::MIN as f64 || rounded >= i64::MAX as f64 {
+                    return Err(out_of_range);
+                }
+
+                Value::Integer(rounded as i64)
+            }
+            (Value::Real(x), Datatype::Numeric(_)) => {
+                // a real is displayed with exactly as many digits as it needs
+                let decimal = x
+                    .to_string()
+                    .parse()
+                    .map_err(|_| ValueError::OutOfRange(Datatype::Numeric(None)))?;
+
+                return Value::Numeric(decimal).cast(datatype);
+            }
+            (Value::Numeric(d), Datatype::Integer) => {
+                Value::Integer(d.to_i64().ok_or(out_of_range)?)
+            }
+            (Value::Numeric(d), Datatype::Real) => Value::Real(d.to_f64()),
+            (Value::Numeric(d), Datatype::Numeric(Some((precision, scale)))) => {
+                let overflow = ValueError::NumericFieldOverflow { precision, scale };
+                let rounded = d.rescale(scale).ok_or(overflow)?;
+                if rounded.integer_digits() > precision - scale {
+                    return Err(ValueError::NumericFieldOverflow { precision, scale });
+                }
+
+                Value::Numeric(rounded)
+            }
+            (value, _) => value,
+        })
     }
 
     pub fn assignable_to(&self, datatype: Datatype) -> bool {
         match self {
             Value::Null => true,
-            Value::Number(_) => datatype == Datatype::Number,
-            Value::Text(_) => datatype == Datatype::Text,
+            Value::Integer(_) | Value::Real(_) | Value::Numeric(_) => datatype.is_numeric(),
+            // text is parsed as a date, time, blob, json or uuid
+            Value::Text(_) => matches!(
+                datatype,
+                Datatype::Text
+                    | Datatype::Date
+                    | Datatype::Time
+                    | Datatype::Timestamp
+                    | Datatype::Interval
+                    | Datatype::Blob
+                    | Datatype::Json
+                    | Datatype::Uuid
+            ),
             Value::Boolean(_) => datatype == Datatype::Boolean,
+            Value::Date(_) | Value::Timestamp(_) => {
+                matches!(datatype, Datatype::Date | Datatype::Timestamp)
+            }
+            Value::Time(_) => datatype == Datatype::Time,
+            Value::Interval(_) => datatype == Datatype::Interval,
+            Value::Blob(_) => datatype == Datatype::Blob,
+            Value::Json(_) => datatype == Datatype::Json,
+            Value::Uuid(_) => datatype == Datatype::Uuid,
             _ => unreachable!(),
         }
     }
 
-    pub fn is_true(&self) -> bool {
+    /// Whether a row satisfies a condition evaluating to this value. Unknown (null) doesn't, and
+    /// a value other than a boolean fails.
+    pub fn is_true(&self) -> Result<bool, ValueError> {
+        match self {
+            Value::Boolean(b) => Ok(*b),
+            Value::Null => Ok(false),
+            _ => Err(ValueError::NotBoolean(self.datatype())),
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Value::Integer(_) | Value::Real(_) | Value::Numeric(_))
+    }
+
+    pub fn as_integer(&self) -> i64 {
         match self {
-            Value::Boolean(b) => *b,
-            _ => panic!("cannot use a non-boolean Value in a boolean context"),
+            Value::Integer(n) => *n,
+            _ => panic!(),
         }
     }
 
-    pub fn as_number(&self) -> u32 {
+    fn as_real(&self) -> f64 {
         match self {
-            Value::Number(n) => *n,
+            Value::Integer(n) => *n as f64,
+            Value::Real(x) => *x,
+            Value::Numeric(d) => d.to_f64(),
+            _ => panic!(),
+        }
+    }
+
+    fn as_decimal(&self) -> Decimal {
+        match self {
+            Value::Integer(n) => Decimal::from(*n),
+            Value::Numeric(d) => *d,
             _ => panic!(),
         }
     }
@@ -88,29 +528,128 @@ impl Value {
         match self {
             // TODO placeholder
             Value::Null => Datatype::Text,
-            Value::Number(_) => Datatype::Number,
+            Value::Integer(_) => Datatype::Integer,
+            Value::Real(_) => Datatype::Real,
+            Value::Numeric(_) => Datatype::Numeric(None),
             Value::Text(_) => Datatype::Text,
             Value::Boolean(_) => Datatype::Boolean,
+            Value::Date(_) => Datatype::Date,
+            Value::Time(_) => Datatype::Time,
+            Value::Timestamp(_) => Datatype::Timestamp,
+            Value::Interval(_) => Datatype::Interval,
+            Value::Blob(_) => Datatype::Blob,
+            Value::Json(_) => Datatype::Json,
+            Value::Uuid(_) => Datatype::Uuid,
             _ => unreachable!(),
         }
     }
+
+    /// The position of this value's datatype in the order values of different datatypes are
+    /// sorted in.
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Integer(_) => 1,
+            Value::Real(_) => 2,
+            Value::Numeric(_) => 3,
+            Value::Text(_) => 4,
+            Value::Boolean(_) => 5,
+            Value::Date(_) => 6,
+            Value::Time(_) => 7,
+            Value::Timestamp(_) => 8,
+            Value::Interval(_) => 9,
+            Value::Blob(_) => 10,
+            Value::Json(_) => 11,
+            Value::Uuid(_) => 12,
+            Value::List(_) => 13,
+        }
+    }
 }
 
-macro_rules! value_op {
-    ($name:ident, $op:tt) => {
-        impl Value {
-            fn $name(&self, rhs: &Value) -> bool {
-                match self {
-                    Value::Text(_) => self.as_str() $op rhs.as_str(),
-                    Value::Number(_) => self.as_number() $op rhs.as_number(),
-                    _ => panic!("operands of $op must be text or number"),
-                }
-            }
+/// Reals compare as numbers, with zero equal to negative zero.
+fn compare_reals(l: f64, r: f64) -> Ordering {
+    (l + 0.0).total_cmp(&(r + 0.0))
+}
+
+/// Bytes written as pairs of hex digits, in either case, such as `DEADBEEF`. `None` if there's
+/// an odd number of digits, or something other than a hex digit.
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Bytes displayed as pairs of lowercase hex digits.
+pub struct Hex<'a>(pub &'a [u8]);
+
+impl Display for Hex<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// Values of the same datatype are equal if they're the same, and values of different datatypes
+/// never are (unlike with [`Value::equals`]). Nulls are equal to each other.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Values of the same datatype are in order, and values of different datatypes in the order of
+/// their datatypes, so that any values can be sorted.
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Integer(l), Value::Integer(r)) => l.cmp(r),
+            (Value::Real(l), Value::Real(r)) => compare_reals(*l, *r),
+            (Value::Numeric(l), Value::Numeric(r)) => l.cmp(r),
+            (Value::Text(l), Value::Text(r)) => l.cmp(r),
+            (Value::Boolean(l), Value::Boolean(r)) => l.cmp(r),
+            (Value::Date(l), Value::Date(r)) => l.cmp(r),
+            (Value::Time(l), Value::Time(r)) => l.cmp(r),
+            (Value::Timestamp(l), Value::Timestamp(r)) => l.cmp(r),
+            (Value::Interval(l), Value::Interval(r)) => l.cmp(r),
+            (Value::Blob(l), Value::Blob(r)) => l.cmp(r),
+            (Value::Json(l), Value::Json(r)) => l.cmp(r),
+            (Value::Uuid(l), Value::Uuid(r)) => l.cmp(r),
+            (Value::List(l), Value::List(r)) => l.cmp(r),
+            _ => self.rank().cmp(&other.rank()),
         }
-    };
+    }
 }
 
-value_op!(greater_equal, >=);
-value_op!(greater, >);
-value_op!(less_equal, <=);
-value_op!(less, <);
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Value::Null => {}
+            Value::Integer(n) => n.hash(state),
+            Value::Real(x) => (x + 0.0).to_bits().hash(state),
+            Value::Numeric(d) => d.hash(state),
+            Value::Text(s) => s.hash(state),
+            Value::Boolean(b) => b.hash(state),
+            Value::Date(d) => d.hash(state),
+            Value::Time(t) => t.hash(state),
+            Value::Timestamp(t) => t.hash(state),
+            Value::Interval(i) => i.hash(state),
+            Value::Blob(b) => b.hash(state),
+            Value::Json(j) => j.hash(state),
+            Value::Uuid(u) => u.hash(state),
+            Value::List(l) => l.hash(state),
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt::{Display, Formatter};
 
 use crate::data::{Table, Value};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ValueList(Vec<Value>);
 
 impl Display for ValueList {
@@ -37,6 +37,6 @@ impl From<Table> for ValueList {
 
 impl ValueList {
     pub fn contains(&self, other: Value) -> bool {
-        self.0.as_slice().contains(&other)
+        self.0.iter().any(|value| value.equals(&other))
     }
 }
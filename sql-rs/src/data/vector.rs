@@ -0,0 +1,180 @@
+use super::{Column, Datatype, Row, Value};
+
+/// The number of rows in a batch.
+pub const BATCH_SIZE: usize = 1024;
+
+/// A bit for each value of a vector.
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Bitmap {
+    /// A bitmap of `len` bits, all unset.
+    pub fn new(len: usize) -> Self {
+        Bitmap {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn set(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    /// The bits set in either bitmap.
+    pub fn union(&self, other: &Bitmap) -> Bitmap {
+        Bitmap {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a | b)
+                .collect(),
+            len: self.len,
+        }
+    }
+}
+
+/// The values of a vector, all of a single datatype. Numeric values, dates and times aren't
+/// stored in vectors.
+///
+/// Text borrows from wherever the values came from (the rows of a table, or a query), rather
+/// than copying them.
+#[derive(Debug, Clone)]
+pub enum Values<'a> {
+    Integer(Vec<i64>),
+    Real(Vec<f64>),
+    Text(Vec<&'a str>),
+    Boolean(Vec<bool>),
+}
+
+/// One column of a batch of rows, stored as a vector of typed values.
+///
+/// Which values are null is kept in a separate bitmap, with a placeholder value in their place
+/// in `values`, so operations can work on every value at once and sort out nulls afterwards.
+#[derive(Debug, Clone)]
+pub struct Vector<'a> {
+    pub values: Values<'a>,
+    pub nulls: Bitmap,
+}
+
+impl<'a> Vector<'a> {
+    /// A vector of values of `datatype`. `None` if a value isn't of that datatype, or the
+    /// datatype is numeric or a date or time.
+    pub fn from_values<I>(datatype: Datatype, values: I) -> Option<Self>
+    where
+        I: ExactSizeIterator<Item = &'a Value>,
+    {
+        let mut nulls = Bitmap::new(values.len());
+
+        macro_rules! collect {
+            ($variant:ident, $placeholder:expr, $value:pat => $converted:expr) => {
+                Values::$variant(
+                    values
+                        .enumerate()
+                        .map(|(i, v)| match v {
+                            $value => Some($converted),
+                            Value::Null => {
+                                nulls.set(i);
+                                Some($placeholder)
+                            }
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()?,
+                )
+            };
+        }
+
+        let values = match datatype {
+            Datatype::Integer => collect!(Integer, 0, Value::Integer(n) => *n),
+            Datatype::Real => collect!(Real, 0.0, Value::Real(x) => *x),
+            Datatype::Text => collect!(Text, "", Value::Text(s) => s.as_str()),
+            Datatype::Boolean => collect!(Boolean, false, Value::Boolean(b) => *b),
+            _ => return None,
+        };
+
+        Some(Vector { values, nulls })
+    }
+
+    /// A vector of `len` copies of a value. `None` for null, which has no datatype, and for a
+    /// numeric value or a date or time.
+    pub fn repeat(value: &'a Value, len: usize) -> Option<Self> {
+        let values = match value {
+            Value::Integer(n) => Values::Integer(vec![*n; len]),
+            Value::Real(x) => Values::Real(vec![*x; len]),
+            Value::Text(s) => Values::Text(vec![s.as_str(); len]),
+            Value::Boolean(b) => Values::Boolean(vec![*b; len]),
+            _ => return None,
+        };
+
+        Some(Vector {
+            values,
+            nulls: Bitmap::new(len),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nulls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nulls.is_empty()
+    }
+
+    /// The value at `i`, as a row would hold it.
+    pub fn get(&self, i: usize) -> Value {
+        if self.nulls.get(i) {
+            return Value::Null;
+        }
+
+        match &self.values {
+            Values::Integer(values) => Value::Integer(values[i]),
+            Values::Real(values) => Value::Real(values[i]),
+            Values::Text(values) => Value::Text(values[i].to_owned()),
+            Values::Boolean(values) => Value::Boolean(values[i]),
+        }
+    }
+}
+
+/// A batch of rows, stored as a vector for each column.
+///
+/// Only the columns something is evaluated over are stored, so a batch only costs as much as
+/// the columns it's used for.
+#[derive(Debug)]
+pub struct Batch<'a> {
+    pub len: usize,
+    pub vectors: Vec<Option<Vector<'a>>>,
+}
+
+impl<'a> Batch<'a> {
+    /// A batch of the columns `needed` of some rows. `None` if a value isn't of its column's
+    /// datatype.
+    pub fn new(columns: &[Column], rows: &[&'a Row], needed: &[usize]) -> Option<Self> {
+        let mut vectors = vec![None; columns.len()];
+        for &i in needed {
+            if vectors[i].is_none() {
+                let values = rows.iter().map(|row| &row.0[i]);
+                vectors[i] = Some(Vector::from_values(columns[i].datatype, values)?);
+            }
+        }
+
+        Some(Batch {
+            len: rows.len(),
+            vectors,
+        })
+    }
+}
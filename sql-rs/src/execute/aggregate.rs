@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use super::{evaluate, evaluate_column};
+use crate::data::{Column, Database, Datatype, Row, Value, ValueError};
+use crate::parse::ast::{self, Aggregate, AggregateFunction, Expression};
+
+/// The rows of a table which share the same values for every GROUP BY expression.
+pub type Group = Vec<Row>;
+
+impl Database {
+    /// Split rows into groups by the values of `group_by`.
+    ///
+    /// Groups are returned in the order their first row appears. With no GROUP BY expressions
+    /// every row belongs to a single group, which exists even if there are no rows at all (so
+    /// that `select count(*)` over an empty table still produces a row).
+    pub fn group_rows(
+        &self,
+        columns: &[Column],
+        rows: Vec<Row>,
+        group_by: &[Expression<'_>],
+    ) -> Result<Vec<Group>, ValueError> {
+        if group_by.is_empty() {
+            return Ok(vec![rows]);
+        }
+
+        let mut groups: Vec<Group> = Vec::new();
+        let mut group_indices: BTreeMap<Vec<Value>, usize> = BTreeMap::new();
+
+        for row in rows {
+            let key: Vec<_> = group_by
+                .iter()
+                .map(|expr| evaluate(expr, Some((columns, &row)), Some(self)))
+                .collect::<Result<_, _>>()?;
+
+            match group_indices.get(&key) {
+                Some(&idx) => groups[idx].push(row),
+                None => {
+                    group_indices.insert(key, groups.len());
+                    groups.push(vec![row]);
+                }
+            }
+        }
+
+        Ok(groups)
+    }
+
+    /// A row for each group of rows with equal values of `group_by`: the group's first row (or
+    /// nulls, for an empty group), followed by the value of each of `aggregates` over the group,
+    /// in the columns [`aggregate_columns`] adds.
+    ///
+    /// Any column referenced outside of an aggregate is expected to be constant within the group
+    /// (because it is grouped on), so it's taken from the first row.
+    pub fn aggregate_rows(
+        &self,
+        columns: &[Column],
+        rows: Vec<Row>,
+        group_by: &[Expression<'_>],
+        aggregates: &[Aggregate<'_>],
+    ) -> Result<Vec<Row>, ValueError> {
+        let groups = self.group_rows(columns, rows, group_by)?;
+
+        groups
+            .iter()
+            .map(|group| {
+                let mut row = match group.first() {
+                    Some(row) => row.clone(),
+                    None => Row(vec![Value::Null; columns.len()]),
+                };
+                for aggregate in aggregates {
+                    row.0
+                        .push(self.evaluate_aggregate(aggregate, columns, group)?);
+                }
+
+                Ok(row)
+            })
+            .collect()
+    }
+
+    fn evaluate_aggregate(
+        &self,
+        aggregate: &Aggregate<'_>,
+        columns: &[Column],
+        group: &[Row],
+    ) -> Result<Value, ValueError> {
+        let arg = match &aggregate.arg {
+            // count(*) counts rows, nulls and all
+            None => return Ok(Value::Integer(group.len() as i64)),
+            Some(arg) => arg,
+        };
+
+        // aggregates only consider non-null inputs
+        let mut values = Vec::new();
+        for row in group {
+            match evaluate(arg, Some((columns, row)), Some(self))? {
+                Value::Null => {}
+                value => values.push(value),
+            }
+        }
+
+        if aggregate.distinct {
+            values.sort();
+            values.dedup();
+        }
+
+        Ok(match aggregate.function {
+            AggregateFunction::Count => Value::Integer(values.len() as i64),
+            AggregateFunction::Min => values.into_iter().min().unwrap_or(Value::Null),
+            AggregateFunction::Max => values.into_iter().max().unwrap_or(Value::Null),
+            AggregateFunction::Sum => total(aggregate.function, values)?
+                .sum()?
+                .unwrap_or(Value::Null),
+            AggregateFunction::Avg => {
+                let count = values.len() as i64;
+
+                total(aggregate.function, values)?
+                    .average(count)?
+                    .unwrap_or(Value::Null)
+            }
+        })
+    }
+}
+
+/// The columns of aggregated rows: the columns of the rows aggregated, followed by a hidden column
+/// for each aggregate, named after the aggregate function call. Evaluating an aggregate function
+/// call over a group finds its value there. Fails if an aggregate's argument refers to a column
+/// which isn't one of the columns aggregated.
+pub fn aggregate_columns(
+    columns: &[Column],
+    aggregates: &[Aggregate<'_>],
+) -> Result<Vec<Column>, ValueError> {
+    let aggregate_columns = aggregates.iter().map(|a| {
+        let expr = Expression::Aggregate(a.clone());
+        Ok(Column {
+            name: a.to_string(),
+            datatype: evaluate_column(&expr, columns)?[0].datatype,
+            hidden: true,
+        })
+    });
+
+    columns
+        .iter()
+        .cloned()
+        .map(Ok)
+        .chain(aggregate_columns)
+        .collect()
+}
+
+fn total(function: AggregateFunction, values: Vec<Value>) -> Result<Total, ValueError> {
+    if let Some(value) = values.iter().find(|value| !summable(value)) {
+        return Err(undefined_function(function, value.datatype()));
+    }
+
+    let mut total = Total::default();
+    for value in values {
+        total.add(value)?;
+    }
+
+    Ok(total)
+}
+
+/// A running total of values which can be summed. Integers are added up as 128-bit integers, so
+/// that integers can be averaged even when their sum doesn't fit an integer.
+#[derive(Debug, Default)]
+pub(super) struct Total {
+    pub integers: Option<i128>,
+    /// The total of any other values.
+    pub others: Option<Value>,
+}
+
+impl Total {
+    pub fn add(&mut self, value: Value) -> Result<(), ValueError> {
+        match value {
+            Value::Integer(n) => self.add_integers(n as i128),
+            value => {
+                self.others = Some(match self.others.take() {
+                    Some(total) => total.op(ast::ExpressionOp::Plus, value)?,
+                    None => value,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the total of some integers.
+    pub fn add_integers(&mut self, total: i128) {
+        *self.integers.get_or_insert(0) += total;
+    }
+
+    /// The sum of the values added, if any were. Fails if a sum of integers doesn't fit an
+    /// integer.
+    pub fn sum(self) -> Result<Option<Value>, ValueError> {
+        let integers = self
+            .integers
+            .map(|n| i64::try_from(n).map_err(|_| ValueError::OutOfRange(Datatype::Integer)))
+            .transpose()?
+            .map(Value::Integer);
+
+        Ok(match (integers, self.others) {
+            (Some(integers), Some(others)) => Some(integers.op(ast::ExpressionOp::Plus, others)?),
+            (integers, others) => integers.or(others),
+        })
+    }
+
+    /// The average of the `count` values added, if any were: real for integers and reals,
+    /// numeric for numeric values, and an interval for intervals.
+    pub fn average(self, count: i64) -> Result<Option<Value>, ValueError> {
+        if let (Some(n), None) = (self.integers, &self.others) {
+            return Ok(Some(Value::Real(n as f64 / count as f64)));
+        }
+
+        Ok(self.sum()?.map(|total| match total {
+            Value::Integer(n) => Value::Real(n as f64 / count as f64),
+            Value::Real(x) => Value::Real(x / count as f64),
+            Value::Numeric(d) => Value::Numeric(d.div_count(count)),
+            Value::Interval(i) => Value::Interval(i.div_count(count)),
+            _ => panic!("operands of arithmetic must be numbers"),
+        }))
+    }
+}
+
+/// Whether a value can be summed (and averaged): numbers and intervals can.
+pub(super) fn summable(value: &Value) -> bool {
+    value.is_numeric() || matches!(value, Value::Interval(_))
+}
+
+/// The error for summing (or averaging) values of a datatype which can't be.
+pub(super) fn undefined_function(function: AggregateFunction, datatype: Datatype) -> ValueError {
+    ValueError::UndefinedFunction(format!("{}({})", function, datatype))
+}
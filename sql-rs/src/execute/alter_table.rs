@@ -0,0 +1,139 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use super::create_table::{self, column_constraints, validate_constraints};
+use crate::data::{Column, ConstraintViolation, Database, ValueError};
+use crate::parse::ast::{AlterTableAction, AlterTableQuery};
+
+#[derive(Debug)]
+pub struct Success();
+
+impl Display for Success {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "ALTERED TABLE")
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Table with that name does not exist")]
+    TableNotFound,
+    #[error("Table with that name already exists")]
+    TableExists,
+    #[error("column `{0}` does not exist")]
+    ColumnNotFound(String),
+    #[error("column `{0}` already exists")]
+    ColumnExists(String),
+    #[error("column `{0}` is the only column of the table, so can't be dropped")]
+    OnlyColumn(String),
+    #[error("column `{column}` is referenced from table `{table}` by {constraint} constraint")]
+    ColumnReferenced {
+        column: String,
+        table: String,
+        constraint: String,
+    },
+    #[error(transparent)]
+    InvalidConstraint(#[from] create_table::Error),
+    #[error(transparent)]
+    ConstraintViolation(#[from] ConstraintViolation),
+    #[error(transparent)]
+    Value(#[from] ValueError),
+}
+
+type QueryResult = Result<Success, Error>;
+
+impl Database {
+    pub fn execute_alter_table(&mut self, query: AlterTableQuery<'_>) -> QueryResult {
+        let table_name = query.table_name.0;
+        if !self.tables.contains_key(table_name) {
+            return Err(Error::TableNotFound);
+        }
+
+        if let AlterTableAction::RenameTable(new_name) = query.action {
+            if self.tables.contains_key(new_name.0) {
+                return Err(Error::TableExists);
+            }
+
+            let table = self.tables.remove(table_name).unwrap();
+            self.tables.insert(new_name.0.to_owned(), table);
+            for referencing in self.referencing_tables(table_name) {
+                for constraint in &mut self.find_table_mut(&referencing).constraints {
+                    constraint.rename_referenced_table(table_name, new_name.0);
+                }
+            }
+
+            return Ok(Success());
+        }
+
+        let table = self.find_table(table_name);
+        match query.action {
+            AlterTableAction::AddColumn(column) => {
+                let name = column.name.0;
+                if table.find_column(name).is_some() {
+                    return Err(Error::ColumnExists(name.to_owned()));
+                }
+
+                // make the change to a copy, leaving the table as it was if the existing rows
+                // don't satisfy the new column's constraints
+                let mut altered = table.clone();
+                altered.add_column(Column::new(name.to_owned(), column.datatype));
+                altered.constraints.append(&mut column_constraints(&column));
+                validate_constraints(&altered)?;
+                self.resolve_foreign_keys(table_name, &mut altered)?;
+                self.create_key_indexes(table_name, &mut altered);
+
+                let idx = altered.columns.len() - 1;
+                let default = altered.default_value(name)?;
+                for row in &mut altered.rows {
+                    row.0[idx] = default.clone();
+                }
+                altered.check_rows(&altered.rows)?;
+                self.check_references(table_name, &altered, &altered.rows, altered.rows.iter())?;
+
+                self.tables.insert(table_name.to_owned(), Arc::new(altered));
+            }
+            AlterTableAction::DropColumn(name) => {
+                let idx = table
+                    .find_column(name.0)
+                    .ok_or_else(|| Error::ColumnNotFound(name.0.to_owned()))?;
+                // a table without columns has nothing to select
+                if table.columns.len() == 1 {
+                    return Err(Error::OnlyColumn(name.0.to_owned()));
+                }
+
+                // a foreign key of the table itself is dropped along with its columns
+                let referencing = self.references_to(table_name).find(|(child, _, c)| {
+                    c.referenced_columns().iter().any(|c| c == name.0)
+                        && !(*child == table_name && c.columns().iter().any(|c| c == name.0))
+                });
+                if let Some((child, _, constraint)) = referencing {
+                    return Err(Error::ColumnReferenced {
+                        column: name.0.to_owned(),
+                        table: child.to_owned(),
+                        constraint: constraint.to_string(),
+                    });
+                }
+
+                self.find_table_mut(table_name).drop_column(idx);
+            }
+            AlterTableAction::RenameColumn { from, to } => {
+                let idx = table
+                    .find_column(from.0)
+                    .ok_or_else(|| Error::ColumnNotFound(from.0.to_owned()))?;
+                if table.find_column(to.0).is_some() {
+                    return Err(Error::ColumnExists(to.0.to_owned()));
+                }
+
+                self.find_table_mut(table_name).rename_column(idx, to.0);
+                for referencing in self.referencing_tables(table_name) {
+                    for constraint in &mut self.find_table_mut(&referencing).constraints {
+                        constraint.rename_referenced_column(table_name, from.0, to.0);
+                    }
+                }
+            }
+            AlterTableAction::RenameTable(_) => unreachable!(),
+        }
+
+        Ok(Success())
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::iter;
+
+use crate::data::{Constraint, Database, IndexMethod, Table};
+use crate::parse::ast::CreateIndexQuery;
+
+#[derive(Debug)]
+pub struct Success();
+
+impl Display for Success {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "CREATED INDEX")
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Table with that name does not exist")]
+    TableNotFound,
+    #[error("Index with that name already exists")]
+    IndexExists,
+    #[error("column `{0}` does not exist")]
+    ColumnNotFound(String),
+    #[error("column `{0}` is listed more than once")]
+    DuplicateColumn(String),
+}
+
+type QueryResult = Result<Success, Error>;
+
+impl Database {
+    pub fn execute_create_index(&mut self, query: CreateIndexQuery<'_>) -> QueryResult {
+        // index names are unique across every table
+        let name = query.index_name.0;
+        if self
+            .tables
+            .values()
+            .any(|table| table.indexes.iter().any(|index| index.name == name))
+        {
+            return Err(Error::IndexExists);
+        }
+
+        let table = self
+            .tables
+            .get(query.table_name.0)
+            .ok_or(Error::TableNotFound)?;
+
+        let mut seen = HashSet::new();
+        for column in &query.columns {
+            if table.find_column(column.0).is_none() {
+                return Err(Error::ColumnNotFound(column.0.to_owned()));
+            }
+            if !seen.insert(column.0) {
+                return Err(Error::DuplicateColumn(column.0.to_owned()));
+            }
+        }
+
+        self.find_table_mut(query.table_name.0).create_index(
+            name.to_owned(),
+            query.columns.iter().map(|c| c.0.to_owned()).collect(),
+            query.method,
+        );
+
+        Ok(Success())
+    }
+
+    /// Give each PRIMARY KEY and UNIQUE constraint of `table` which doesn't have one an index
+    /// over its columns, which finds rows by their key in WHERE filters and when the constraint
+    /// is checked. The index is named after the table like in Postgres (`users_pkey`,
+    /// `users_email_key`), numbered if another index has that name already.
+    pub(super) fn create_key_indexes(&self, table_name: &str, table: &mut Table) {
+        let keys: Vec<_> = table
+            .constraints
+            .iter()
+            .filter_map(|constraint| match constraint {
+                Constraint::PrimaryKey(columns) => {
+                    Some((format!("{}_pkey", table_name), columns.clone()))
+                }
+                Constraint::Unique(columns) => Some((
+                    format!("{}_{}_key", table_name, columns.join("_")),
+                    columns.clone(),
+                )),
+                _ => None,
+            })
+            .collect();
+
+        for (base, columns) in keys {
+            if table.indexes.iter().any(|index| index.columns == columns) {
+                continue;
+            }
+
+            let taken = |name: &str| {
+                self.tables
+                    .values()
+                    .map(|table| &**table)
+                    .chain(iter::once(&*table))
+                    .any(|table| table.indexes.iter().any(|index| index.name == name))
+            };
+            let name = match taken(&base) {
+                false => base,
+                true => (1..)
+                    .map(|i| format!("{}{}", base, i))
+                    .find(|name| !taken(name))
+                    .unwrap(),
+            };
+
+            table.create_index(name, columns, IndexMethod::BTree);
+        }
+    }
+}
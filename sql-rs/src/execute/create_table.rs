@@ -1,7 +1,12 @@
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
-use crate::data::{Column, Database, Table};
-use crate::parse::ast::CreateTableQuery;
+use crate::data::{Column, Constraint, Database, Datatype, Table, ValueError, MAX_PRECISION};
+use crate::execute::evaluate;
+use crate::parse::ast::{
+    ColumnConstraint, CreateTableColumn, CreateTableQuery, Identifier, References, TableConstraint,
+};
+use crate::parse::parse_expression;
 
 #[derive(Debug)]
 pub struct Success();
@@ -16,6 +21,30 @@ impl Display for Success {
 pub enum Error {
     #[error("Table with that name already exists")]
     TableExists,
+    #[error("constraint refers to column `{0}`, which does not exist")]
+    ColumnNotFound(String),
+    #[error("a table can have only one primary key")]
+    MultiplePrimaryKeys,
+    #[error("{0} constraint cannot contain aggregate functions or subqueries")]
+    UnsupportedExpression(String),
+    #[error("default value of column `{0}` cannot refer to columns")]
+    DefaultRefersToColumn(String),
+    #[error("default value of column `{0}` is not compatible with the column's datatype")]
+    IncompatibleDefault(String),
+    #[error("referenced table `{0}` does not exist")]
+    ReferencedTableNotFound(String),
+    #[error("referenced table `{0}` has no primary key")]
+    NoPrimaryKey(String),
+    #[error("{0} constraint must reference the columns of a primary key or unique constraint")]
+    ReferencedColumnsNotKey(String),
+    #[error("{0} constraint must have matching numbers and datatypes of columns")]
+    ForeignKeyMismatch(String),
+    #[error("NUMERIC precision {0} must be between 1 and {}", MAX_PRECISION)]
+    InvalidPrecision(u32),
+    #[error("NUMERIC scale {scale} must be between 0 and precision {precision}")]
+    InvalidScale { precision: u32, scale: u32 },
+    #[error(transparent)]
+    Value(#[from] ValueError),
 }
 
 type QueryResult = Result<Success, Error>;
@@ -27,19 +56,136 @@ impl Database {
         }
 
         let name = query.table_name.0.to_owned();
-        let table = Table {
+        let mut table = Table {
             columns: query
                 .columns
                 .iter()
-                .map(|c| Column {
-                    name: c.name.0.to_owned(),
-                    datatype: c.datatype,
-                })
+                .map(|c| Column::new(c.name.0.to_owned(), c.datatype))
                 .collect(),
             rows: Vec::new(),
+            constraints: Vec::new(),
+            indexes: Vec::new(),
         };
-        self.tables.insert(name, table);
+
+        for column in &query.columns {
+            table.constraints.append(&mut column_constraints(column));
+        }
+        for constraint in &query.constraints {
+            table.constraints.push(match constraint {
+                TableConstraint::PrimaryKey(columns) => {
+                    Constraint::PrimaryKey(columns.iter().map(|c| c.0.to_owned()).collect())
+                }
+                TableConstraint::Unique(columns) => {
+                    Constraint::Unique(columns.iter().map(|c| c.0.to_owned()).collect())
+                }
+                TableConstraint::Check(expression) => Constraint::Check(expression.to_string()),
+                TableConstraint::ForeignKey {
+                    columns,
+                    references,
+                } => foreign_key(columns, references),
+            });
+        }
+        validate_constraints(&table)?;
+        self.resolve_foreign_keys(&name, &mut table)?;
+        self.create_key_indexes(&name, &mut table);
+
+        self.tables.insert(name, Arc::new(table));
 
         Ok(Success())
     }
 }
+
+/// The constraints declared as part of a single column's definition.
+pub(super) fn column_constraints(column: &CreateTableColumn<'_>) -> Vec<Constraint> {
+    let name = column.name.0.to_owned();
+
+    column
+        .constraints
+        .iter()
+        .map(|constraint| match constraint {
+            ColumnConstraint::PrimaryKey => Constraint::PrimaryKey(vec![name.clone()]),
+            ColumnConstraint::Unique => Constraint::Unique(vec![name.clone()]),
+            ColumnConstraint::NotNull => Constraint::NotNull(name.clone()),
+            ColumnConstraint::Default(expression) => Constraint::Default {
+                column: name.clone(),
+                expression: expression.to_string(),
+            },
+            ColumnConstraint::Check(expression) => Constraint::Check(expression.to_string()),
+            ColumnConstraint::References(references) => {
+                foreign_key(std::slice::from_ref(&column.name), references)
+            }
+        })
+        .collect()
+}
+
+/// A foreign key from `columns`. If the referenced columns are left out, they're filled in by
+/// `Database::resolve_foreign_keys`.
+fn foreign_key(columns: &[Identifier<'_>], references: &References<'_>) -> Constraint {
+    Constraint::ForeignKey {
+        columns: columns.iter().map(|c| c.0.to_owned()).collect(),
+        table: references.table.0.to_owned(),
+        referenced_columns: references.columns.iter().map(|c| c.0.to_owned()).collect(),
+        on_delete: references.on_delete,
+    }
+}
+
+/// Check that a table's constraints are consistent with its columns, and with each other, and
+/// that its columns' datatypes are valid.
+pub(super) fn validate_constraints(table: &Table) -> Result<(), Error> {
+    for column in &table.columns {
+        if let Datatype::Numeric(Some((precision, scale))) = column.datatype {
+            if !(1..=MAX_PRECISION).contains(&precision) {
+                return Err(Error::InvalidPrecision(precision));
+            }
+            if scale > precision {
+                return Err(Error::InvalidScale { precision, scale });
+            }
+        }
+    }
+
+    for constraint in &table.constraints {
+        for column in constraint.columns() {
+            if table.find_column(&column).is_none() {
+                return Err(Error::ColumnNotFound(column));
+            }
+        }
+    }
+
+    for constraint in &table.constraints {
+        let expression = match constraint {
+            Constraint::Default { expression, .. } | Constraint::Check(expression) => {
+                parse_expression(expression).unwrap()
+            }
+            _ => continue,
+        };
+
+        if expression.contains_aggregate() || expression.contains_subquery() {
+            return Err(Error::UnsupportedExpression(constraint.to_string()));
+        }
+
+        if let Constraint::Default { column, .. } = constraint {
+            if !expression.column_identifiers().is_empty() {
+                return Err(Error::DefaultRefersToColumn(column.clone()));
+            }
+
+            let datatype = table.columns[table.find_column(column).unwrap()].datatype;
+            let default = evaluate(&expression, None, None)?;
+            if !default.assignable_to(datatype) {
+                return Err(Error::IncompatibleDefault(column.clone()));
+            }
+            // such as text which isn't a valid date
+            default.cast(datatype)?;
+        }
+    }
+
+    let num_primary_keys = table
+        .constraints
+        .iter()
+        .filter(|c| matches!(c, Constraint::PrimaryKey(_)))
+        .count();
+    if num_primary_keys > 1 {
+        return Err(Error::MultiplePrimaryKeys);
+    }
+
+    Ok(())
+}
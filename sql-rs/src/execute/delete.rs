@@ -0,0 +1,61 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+use super::index::index_lookup;
+use crate::data::{ConstraintViolation, Database, ValueError};
+use crate::execute::{check_columns, evaluate};
+use crate::parse::ast::DeleteQuery;
+
+#[derive(Debug)]
+pub struct Success {
+    num_deleted: u32,
+}
+
+impl Display for Success {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "DELETE {}", self.num_deleted)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Table with that name does not exist")]
+    TableNotFound,
+    #[error(transparent)]
+    ConstraintViolation(#[from] ConstraintViolation),
+    #[error(transparent)]
+    Value(#[from] ValueError),
+}
+
+type QueryResult = Result<Success, Error>;
+
+impl Database {
+    pub fn execute_delete(&mut self, query: DeleteQuery<'_>) -> QueryResult {
+        let table = self.tables.get(query.table.0).ok_or(Error::TableNotFound)?;
+        let indices = match &query.filter {
+            Some(filter) => {
+                if filter.contains_scalar_subquery() {
+                    return Err(ValueError::ScalarSubquery.into());
+                }
+                check_columns(filter, &table.columns)?;
+                let mut indices = BTreeSet::new();
+                for i in index_lookup(table, None, filter)
+                    .unwrap_or_else(|| (0..table.rows.len()).collect())
+                {
+                    if evaluate(filter, Some((&table.columns, &table.rows[i])), Some(self))?
+                        .is_true()?
+                    {
+                        indices.insert(i);
+                    }
+                }
+
+                indices
+            }
+            None => (0..table.rows.len()).collect(),
+        };
+
+        let num_deleted = self.delete_rows(query.table.0, indices)? as u32;
+
+        Ok(Success { num_deleted })
+    }
+}
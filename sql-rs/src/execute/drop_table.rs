@@ -0,0 +1,47 @@
+use std::fmt::{Display, Formatter};
+
+use crate::data::Database;
+use crate::parse::ast::DropTableQuery;
+
+#[derive(Debug)]
+pub struct Success();
+
+impl Display for Success {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "DROPPED TABLE")
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Table with that name does not exist")]
+    TableNotFound,
+    #[error("table is referenced from table `{table}` by {constraint} constraint")]
+    StillReferenced { table: String, constraint: String },
+}
+
+type QueryResult = Result<Success, Error>;
+
+impl Database {
+    pub fn execute_drop_table(&mut self, query: DropTableQuery<'_>) -> QueryResult {
+        let name = query.table_name.0;
+
+        // a table may reference itself, but mustn't be referenced by any other
+        if let Some((table, constraint)) = self
+            .references_to(name)
+            .find(|(table, _, _)| *table != name)
+            .map(|(table, _, constraint)| (table, constraint))
+        {
+            return Err(Error::StillReferenced {
+                table: table.to_owned(),
+                constraint: constraint.to_string(),
+            });
+        }
+
+        if self.tables.remove(name).is_none() && !query.if_exists {
+            return Err(Error::TableNotFound);
+        }
+
+        Ok(Success())
+    }
+}
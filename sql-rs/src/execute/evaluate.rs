@@ -1,71 +1,202 @@
-use crate::data::{Column, Database, Datatype, Row, Table, Value};
-use crate::parse::ast::{self, Expression, Literal};
+use std::convert::TryFrom;
 
-pub type RowEvaluationContext<'table> = (&'table Vec<Column>, &'table Row);
+use super::function::{call, function_datatype};
+use super::select;
+use crate::data::{Column, Database, Datatype, Row, Table, Value, ValueError};
+use crate::parse::ast::{self, AggregateFunction, Expression, ExpressionOp};
+
+pub type RowEvaluationContext<'table> = (&'table [Column], &'table Row);
 
 /// TODO
 pub fn evaluate(
     expr: &Expression<'_>,
     row_ctx: Option<RowEvaluationContext>,
     database: Option<&Database>,
-) -> Value {
-    match expr {
+) -> Result<Value, ValueError> {
+    Ok(match expr {
         Expression::ColumnIdentifier(i) => {
-            let row_ctx = row_ctx.unwrap();
-            let idx = Table::get_column_idx(row_ctx.0, i);
+            let undefined = || ValueError::UndefinedColumn(i.as_string());
+            let (columns, row) = row_ctx.ok_or_else(undefined)?;
+            let idx = Table::find_column_idx(columns, i).ok_or_else(undefined)?;
+
+            row.0[idx].clone()
+        }
+        Expression::Literal(l) => Value::try_from(l)?,
+        Expression::Constant(v) => v.clone(),
+        Expression::Subquery(sq) => match database.unwrap().execute_select(sq) {
+            Ok(rows) if rows.columns.len() != 1 => return Err(ValueError::SubqueryColumns),
+            Ok(rows) => Value::List(rows.into()),
+            Err(select::Error::Value(e)) => return Err(e),
+            Err(e) => return Err(ValueError::Subquery(Box::new(e))),
+        },
+        Expression::BinaryOp(b) => {
+            let v1 = evaluate(&b.left, row_ctx, database)?;
+            let v2 = evaluate(&b.right, row_ctx, database)?;
 
-            row_ctx.1 .0[idx].clone()
+            v1.op(b.op, v2)?
         }
-        Expression::Literal(l) => l.into(),
-        Expression::Subquery(sq) => {
-            Value::List(database.unwrap().execute_select(sq).unwrap().into())
+        Expression::Function(f) => {
+            let args = f
+                .args
+                .iter()
+                .map(|arg| evaluate(arg, row_ctx, database))
+                .collect::<Result<_, _>>()?;
+
+            call(f.function, args)?
+        }
+        // aggregates are computed for each group beforehand, into columns named after them
+        Expression::Aggregate(a) => {
+            let name = a.to_string();
+            let undefined = || ValueError::UndefinedColumn(name.clone());
+            let (columns, row) = row_ctx.ok_or_else(undefined)?;
+            let idx = columns
+                .iter()
+                .position(|c| c.name == name)
+                .ok_or_else(undefined)?;
+
+            row.0[idx].clone()
         }
+    })
+}
+
+/// The columns an expression gives, out of the columns of the rows it's evaluated for. Fails if
+/// it refers to a column which isn't one of them.
+pub fn evaluate_column(
+    expr: &Expression<'_>,
+    columns: &[Column],
+) -> Result<Vec<Column>, ValueError> {
+    Ok(match expr {
+        Expression::ColumnIdentifier(i) => column_indices(i, columns)?
+            .into_iter()
+            .map(|idx| columns[idx].clone())
+            .collect(),
+        // a subquery is only a list of values on the right of IN, and not a column of its own
+        Expression::Subquery(_) => vec![Column::new(String::from("?column?"), Datatype::Text)],
+        // a number out of range fails when it's evaluated, whatever its datatype
+        Expression::Literal(l) => vec![Column::new(
+            String::from("?column?"),
+            Value::try_from(l).map_or(Datatype::Numeric(None), |v| v.datatype()),
+        )],
+        Expression::Constant(v) => vec![Column::new(String::from("?column?"), v.datatype())],
         Expression::BinaryOp(b) => {
-            let v1 = evaluate(&b.left, row_ctx, database);
-            let v2 = evaluate(&b.right, row_ctx, database);
+            let mut left = evaluate_column(&b.left, columns)?;
+            // arithmetic promotes numbers to the same datatype, and may give a date or time of
+            // another datatype, and a field of json is json or text, otherwise (for now at least)
+            // both sides of a binary op must have the same type
+            match b.op {
+                ExpressionOp::Plus | ExpressionOp::Minus => {
+                    let right = evaluate_column(&b.right, columns)?[0].datatype;
+                    left[0].datatype = arithmetic_datatype(b.op, left[0].datatype, right);
+                }
+                ExpressionOp::JsonGet => left[0].datatype = Datatype::Json,
+                ExpressionOp::JsonGetText => left[0].datatype = Datatype::Text,
+                _ => {}
+            }
+
+            left
+        }
+        Expression::Function(f) => {
+            let args = f
+                .args
+                .iter()
+                .map(|arg| Ok(evaluate_column(arg, columns)?[0].datatype))
+                .collect::<Result<Vec<_>, ValueError>>()?;
+
+            vec![Column::new(
+                f.function.to_string(),
+                function_datatype(f.function, &args),
+            )]
+        }
+        Expression::Aggregate(a) => {
+            let arg = match &a.arg {
+                Some(arg) => Some(evaluate_column(arg, columns)?[0].datatype),
+                None => None,
+            };
 
-            v1.op(b.op, v2)
+            vec![Column::new(
+                a.function.to_string(),
+                aggregate_datatype(a.function, arg),
+            )]
         }
-        _ => unreachable!(),
+    })
+}
+
+/// Check that every column an expression refers to is one of `columns`, so that an expression
+/// naming a column which doesn't exist fails before any rows are read, rather than once it's
+/// evaluated for a row (or not at all, without any rows).
+pub fn check_columns(expr: &Expression<'_>, columns: &[Column]) -> Result<(), ValueError> {
+    for identifier in expr.column_identifiers() {
+        column_indices(identifier, columns)?;
     }
+
+    Ok(())
 }
 
-/// TODO
-pub fn evaluate_column(expr: &Expression<'_>, columns: &Vec<Column>) -> Vec<Column> {
-    match expr {
-        Expression::ColumnIdentifier(i) => match &i.name {
-            ast::Column::Star => columns
-                .iter()
-                .filter(|c| match &i.alias {
-                    None => true,
-                    Some(alias) => {
-                        if c.name.contains(".") {
-                            alias.0 == c.name.rsplit_once(".").unwrap().0
-                        } else {
-                            alias.0 == c.name
-                        }
-                    }
-                })
-                .map(Clone::clone)
-                .collect(),
-            _ => {
-                let idx = Table::get_column_idx(columns, i);
-
-                vec![columns[idx].clone()]
+/// The indices of the columns a column identifier refers to (each column `*` matches). Fails if
+/// it doesn't refer to any.
+pub(super) fn column_indices(
+    i: &ast::ColumnIdentifier<'_>,
+    columns: &[Column],
+) -> Result<Vec<usize>, ValueError> {
+    let indices: Vec<_> = match &i.name {
+        ast::Column::Star => star_columns(i, columns).collect(),
+        _ => Table::find_column_idx(columns, i).into_iter().collect(),
+    };
+    if indices.is_empty() {
+        return Err(ValueError::UndefinedColumn(i.as_string()));
+    }
+
+    Ok(indices)
+}
+
+/// The indices of the columns matched by `alias.*`, or by `*` (which leaves out hidden columns).
+pub fn star_columns<'a>(
+    i: &'a ast::ColumnIdentifier<'_>,
+    columns: &'a [Column],
+) -> impl Iterator<Item = usize> + 'a {
+    columns
+        .iter()
+        .enumerate()
+        .filter(move |(_, c)| match &i.alias {
+            None => !c.hidden,
+            Some(alias) => {
+                if c.name.contains('.') {
+                    alias.0 == c.name.rsplit_once('.').unwrap().0
+                } else {
+                    alias.0 == c.name
+                }
             }
-        },
-        Expression::Subquery(_sq) => todo!(),
-        Expression::Literal(l) => vec![Column {
-            name: String::from("?column?"),
-            datatype: <&Literal<'_> as Into<Value>>::into(l).datatype(),
-        }],
-        Expression::BinaryOp(b) => {
-            // for now at least, both sides of a binary op must have the same type
-            evaluate_column(&b.left, columns)
+        })
+        .map(|(idx, _)| idx)
+}
+
+/// The datatype of the sum or difference of values of two datatypes (see
+/// [`Value::op`]).
+fn arithmetic_datatype(op: ExpressionOp, left: Datatype, right: Datatype) -> Datatype {
+    use Datatype::*;
+
+    match (left, right, op) {
+        (l, r, _) if l.is_numeric() && r.is_numeric() => l.promote(r),
+        (Date, Date, ExpressionOp::Minus) => Integer,
+        (Date, Interval | Time, _) | (Interval | Time, Date, ExpressionOp::Plus) => Timestamp,
+        (Interval, Timestamp | Time, ExpressionOp::Plus) => right,
+        (Timestamp, Timestamp, ExpressionOp::Minus) | (Time, Time, ExpressionOp::Minus) => Interval,
+        (Integer, Date, ExpressionOp::Plus) => Date,
+        _ => left,
+    }
+}
+
+/// The datatype of an aggregate function's value, over an argument of a datatype (or over rows,
+/// for `count(*)`). Sums and averages of numeric values aren't limited to their precision, and
+/// averages of integers are real.
+fn aggregate_datatype(function: AggregateFunction, arg: Option<Datatype>) -> Datatype {
+    match (function, arg) {
+        (AggregateFunction::Count, _) | (_, None) => Datatype::Integer,
+        (AggregateFunction::Min | AggregateFunction::Max, Some(arg)) => arg,
+        (AggregateFunction::Sum | AggregateFunction::Avg, Some(Datatype::Numeric(_))) => {
+            Datatype::Numeric(None)
         }
-        Expression::CountStar => vec![Column {
-            name: String::from("count"),
-            datatype: Datatype::Number,
-        }],
+        (AggregateFunction::Avg, Some(Datatype::Integer)) => Datatype::Real,
+        (AggregateFunction::Sum | AggregateFunction::Avg, Some(arg)) => arg,
     }
 }
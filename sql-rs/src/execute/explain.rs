@@ -0,0 +1,89 @@
+use super::operator::{drain, OperatorStats};
+use super::select::Error;
+use crate::data::{Column, Database, Datatype, Row, Table, Value};
+use crate::parse::ast::ExplainQuery;
+use crate::plan::LogicalPlan;
+
+pub type Success = Table;
+
+impl Database {
+    /// A table describing the plan a select query is executed with, with a row for each
+    /// operator. Each operator comes before the operators it takes its rows from, indented below
+    /// it, and is followed by how it goes about it where there's a choice (such as finding rows
+    /// with an index, or which join algorithm to use). These choices are made as the operators
+    /// are set up, before any rows are pulled, so the query isn't executed to describe them.
+    ///
+    /// When analyzing, the query is executed, and each row also has the number of rows the
+    /// operator produced and how long it took (including the operators below it).
+    pub fn execute_explain(&self, query: &ExplainQuery<'_>) -> Result<Success, Error> {
+        let plan = self.plan_select(&query.query)?;
+
+        let mut lines = Vec::new();
+        describe(&plan, 0, &mut lines);
+
+        let mut stats = Vec::new();
+        let mut operator = self.build_operator(&plan, query.analyze, &mut stats)?;
+
+        if !query.analyze {
+            return Ok(Table {
+                columns: vec![Column::new(String::from("plan"), Datatype::Text)],
+                rows: lines
+                    .into_iter()
+                    .zip(stats)
+                    .map(|(line, stats)| Row(vec![with_method(line, &stats.borrow())]))
+                    .collect(),
+                constraints: Vec::new(),
+                indexes: Vec::new(),
+            });
+        }
+
+        drain(&mut operator)?;
+
+        Ok(Table {
+            columns: vec![
+                Column::new(String::from("plan"), Datatype::Text),
+                Column::new(String::from("rows"), Datatype::Integer),
+                Column::new(String::from("time"), Datatype::Text),
+            ],
+            rows: lines
+                .into_iter()
+                .zip(stats)
+                .map(|(line, stats)| analyzed_row(line, &stats.borrow()))
+                .collect(),
+            constraints: Vec::new(),
+            indexes: Vec::new(),
+        })
+    }
+}
+
+/// Add a line describing each operator of a plan, in the order they're executed in.
+fn describe(plan: &LogicalPlan<'_>, depth: usize, lines: &mut Vec<Value>) {
+    let line = match depth {
+        0 => plan.to_string(),
+        _ => format!("{}-> {}", "  ".repeat(depth - 1), plan),
+    };
+    lines.push(Value::Text(line));
+
+    for child in plan.children() {
+        describe(child, depth + 1, lines);
+    }
+}
+
+/// A line describing an operator, followed by how it goes about it.
+fn with_method(line: Value, stats: &OperatorStats) -> Value {
+    match &stats.method {
+        Some(method) => Value::Text(format!("{} ({})", line, method)),
+        None => line,
+    }
+}
+
+fn analyzed_row(line: Value, stats: &OperatorStats) -> Row {
+    let line = with_method(line, stats);
+    let time = format!("{:.3} ms", stats.time.as_secs_f64() * 1000.0);
+
+    Row(vec![
+        line,
+        Value::Integer(stats.rows as i64),
+        Value::Text(time),
+    ])
+}
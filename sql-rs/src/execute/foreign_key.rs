@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::create_table::Error;
+use crate::data::{
+    Constraint, ConstraintViolation, Database, ReferentialAction, Row, Table, Value,
+};
+
+/// The changes a delete makes, once every foreign key referencing the deleted rows has had its
+/// action applied. Both are keyed by table name.
+#[derive(Default)]
+struct DeletePlan {
+    /// The indices of the rows to delete.
+    deletions: BTreeMap<String, BTreeSet<usize>>,
+    /// The indices of rows, and for each the indices of columns to set to null.
+    nulled: BTreeMap<String, BTreeMap<usize, Vec<usize>>>,
+}
+
+impl Database {
+    /// Fill in the referenced columns of any foreign key which left them out, and check that
+    /// every foreign key references a key of an existing table with matching datatypes.
+    ///
+    /// `table` is about to be stored under `table_name`, and may reference itself.
+    pub(super) fn resolve_foreign_keys(
+        &self,
+        table_name: &str,
+        table: &mut Table,
+    ) -> Result<(), Error> {
+        for i in 0..table.constraints.len() {
+            let (columns, target_name, mut referenced_columns) = match &table.constraints[i] {
+                Constraint::ForeignKey {
+                    columns,
+                    table,
+                    referenced_columns,
+                    ..
+                } => (columns.clone(), table.clone(), referenced_columns.clone()),
+                _ => continue,
+            };
+
+            let target = if target_name == table_name {
+                &*table
+            } else {
+                self.tables
+                    .get(&target_name)
+                    .ok_or_else(|| Error::ReferencedTableNotFound(target_name.clone()))?
+            };
+
+            if referenced_columns.is_empty() {
+                referenced_columns = target
+                    .constraints
+                    .iter()
+                    .find_map(|c| match c {
+                        Constraint::PrimaryKey(columns) => Some(columns.clone()),
+                        _ => None,
+                    })
+                    .ok_or_else(|| Error::NoPrimaryKey(target_name.clone()))?;
+            }
+
+            let mut resolved = table.constraints[i].clone();
+            if let Constraint::ForeignKey {
+                referenced_columns: r,
+                ..
+            } = &mut resolved
+            {
+                *r = referenced_columns.clone();
+            }
+            let constraint = resolved.to_string();
+
+            let referenced_set: BTreeSet<_> = referenced_columns.iter().collect();
+            let is_key = target.constraints.iter().any(|c| match c {
+                Constraint::PrimaryKey(columns) | Constraint::Unique(columns) => {
+                    columns.iter().collect::<BTreeSet<_>>() == referenced_set
+                }
+                _ => false,
+            });
+            if !is_key {
+                return Err(Error::ReferencedColumnsNotKey(constraint));
+            }
+
+            let datatypes_match = columns.len() == referenced_columns.len()
+                && columns.iter().zip(&referenced_columns).all(|(c, r)| {
+                    let column = &table.columns[table.find_column(c).unwrap()];
+                    let referenced = &target.columns[target.find_column(r).unwrap()];
+
+                    column.datatype == referenced.datatype
+                });
+            if !datatypes_match {
+                return Err(Error::ForeignKeyMismatch(constraint));
+            }
+
+            table.constraints[i] = resolved;
+        }
+
+        Ok(())
+    }
+
+    /// Check that every foreign key of `table` is satisfied by `rows`.
+    ///
+    /// `rows_after` are all the rows the table will hold once `rows` are stored, which a foreign
+    /// key referencing the table itself is checked against.
+    pub(super) fn check_references<'a>(
+        &self,
+        table_name: &str,
+        table: &Table,
+        rows: impl IntoIterator<Item = &'a Row> + Clone,
+        rows_after: impl Iterator<Item = &'a Row> + Clone,
+    ) -> Result<(), ConstraintViolation> {
+        for constraint in &table.constraints {
+            let (columns, target_name, referenced_columns) = match constraint {
+                Constraint::ForeignKey {
+                    columns,
+                    table,
+                    referenced_columns,
+                    ..
+                } => (columns, table, referenced_columns),
+                _ => continue,
+            };
+
+            let indices = column_indices(table, columns);
+            let referenced_keys: BTreeSet<_> = if target_name == table_name {
+                let referenced_indices = column_indices(table, referenced_columns);
+
+                rows_after
+                    .clone()
+                    .map(|row| key_values(&referenced_indices, row))
+                    .collect()
+            } else {
+                let target = self.find_table(target_name);
+                let referenced_indices = column_indices(target, referenced_columns);
+
+                target
+                    .rows
+                    .iter()
+                    .map(|row| key_values(&referenced_indices, row))
+                    .collect()
+            };
+
+            for row in rows.clone() {
+                let key = key_values(&indices, row);
+
+                if !has_null(&key) && !referenced_keys.contains(&key) {
+                    return Err(ConstraintViolation::MissingReference {
+                        constraint: constraint.to_string(),
+                        values: display_key(&key),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that replacing the rows of `table` at the positions in `changed` doesn't change any
+    /// key which is still referenced by a foreign key.
+    pub(super) fn check_referenced_keys(
+        &self,
+        table_name: &str,
+        table: &Table,
+        changed: &BTreeMap<usize, Row>,
+    ) -> Result<(), ConstraintViolation> {
+        for (child_name, child, constraint) in self.references_to(table_name) {
+            let (columns, referenced_columns) = match constraint {
+                Constraint::ForeignKey {
+                    columns,
+                    referenced_columns,
+                    ..
+                } => (columns, referenced_columns),
+                _ => unreachable!(),
+            };
+
+            // the keys the changed rows had, unless a row still has them
+            let referenced_indices = column_indices(table, referenced_columns);
+            let mut removed_keys: BTreeSet<_> = changed
+                .keys()
+                .map(|&i| key_values(&referenced_indices, &table.rows[i]))
+                .collect();
+            for row in table.updated_rows(changed) {
+                if removed_keys.is_empty() {
+                    break;
+                }
+                removed_keys.remove(&key_values(&referenced_indices, row));
+            }
+            if removed_keys.is_empty() {
+                continue;
+            }
+
+            let indices = column_indices(child, columns);
+            for (i, row) in child.rows.iter().enumerate() {
+                // a table referencing itself is checked against its updated rows
+                let row = match changed.get(&i) {
+                    Some(row) if child_name == table_name => row,
+                    _ => row,
+                };
+                let key = key_values(&indices, row);
+
+                if removed_keys.contains(&key) {
+                    return Err(ConstraintViolation::StillReferenced {
+                        constraint: constraint.to_string(),
+                        table: child_name.to_owned(),
+                        values: display_key(&key),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete the rows of a table at `indices`, applying the on delete action of every foreign
+    /// key referencing them. Either every change is made, or (if a foreign key restricts the
+    /// delete, or setting a column to null violates a constraint) none are.
+    ///
+    /// Returns the number of rows deleted from the table itself.
+    pub(super) fn delete_rows(
+        &mut self,
+        table_name: &str,
+        indices: BTreeSet<usize>,
+    ) -> Result<usize, ConstraintViolation> {
+        let num_deleted = indices.len();
+
+        let mut plan = DeletePlan::default();
+        self.plan_delete(table_name, indices, &mut plan)?;
+
+        // build the rows set to null on the side first, so that nothing changes if one of them
+        // violates a constraint
+        let mut updates = BTreeMap::new();
+        for (name, nulled) in &plan.nulled {
+            let table = self.find_table(name);
+            let deletions = plan.deletions.get(name);
+
+            let changed: BTreeMap<usize, Row> = nulled
+                .iter()
+                .filter(|(i, _)| !deletions.is_some_and(|d| d.contains(i)))
+                .map(|(&i, columns)| {
+                    let mut row = table.rows[i].clone();
+                    for &column in columns {
+                        row.0[column] = Value::Null;
+                    }
+                    (i, row)
+                })
+                .collect();
+            table.check_update(&changed)?;
+
+            updates.insert(name.clone(), changed);
+        }
+
+        for (name, changed) in updates {
+            self.find_table_mut(&name).update_rows(changed);
+        }
+        for (name, deletions) in &plan.deletions {
+            self.find_table_mut(name).delete_rows(deletions);
+        }
+
+        Ok(num_deleted)
+    }
+
+    fn plan_delete(
+        &self,
+        table_name: &str,
+        indices: BTreeSet<usize>,
+        plan: &mut DeletePlan,
+    ) -> Result<(), ConstraintViolation> {
+        // rows already being deleted have had their references dealt with
+        let deletions = plan.deletions.entry(table_name.to_owned()).or_default();
+        let indices: Vec<_> = indices
+            .into_iter()
+            .filter(|&i| deletions.insert(i))
+            .collect();
+        if indices.is_empty() {
+            return Ok(());
+        }
+
+        let table = self.find_table(table_name);
+        for (child_name, child, constraint) in self.references_to(table_name) {
+            let (columns, referenced_columns, on_delete) = match constraint {
+                Constraint::ForeignKey {
+                    columns,
+                    referenced_columns,
+                    on_delete,
+                    ..
+                } => (columns, referenced_columns, *on_delete),
+                _ => unreachable!(),
+            };
+
+            let referenced_indices = column_indices(table, referenced_columns);
+            let deleted_keys: BTreeSet<_> = indices
+                .iter()
+                .map(|&i| key_values(&referenced_indices, &table.rows[i]))
+                .collect();
+
+            let child_indices = column_indices(child, columns);
+            let child_deletions = plan.deletions.get(child_name);
+            let referencing: BTreeSet<usize> = child
+                .rows
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !child_deletions.is_some_and(|d| d.contains(i)))
+                .filter(|(_, row)| {
+                    let key = key_values(&child_indices, row);
+
+                    !has_null(&key) && deleted_keys.contains(&key)
+                })
+                .map(|(i, _)| i)
+                .collect();
+            if referencing.is_empty() {
+                continue;
+            }
+
+            match on_delete {
+                ReferentialAction::Restrict => {
+                    let row = &child.rows[*referencing.iter().next().unwrap()];
+
+                    return Err(ConstraintViolation::StillReferenced {
+                        constraint: constraint.to_string(),
+                        table: child_name.to_owned(),
+                        values: display_key(&key_values(&child_indices, row)),
+                    });
+                }
+                ReferentialAction::Cascade => self.plan_delete(child_name, referencing, plan)?,
+                ReferentialAction::SetNull => {
+                    let nulled = plan.nulled.entry(child_name.to_owned()).or_default();
+                    for i in referencing {
+                        nulled
+                            .entry(i)
+                            .or_default()
+                            .extend(child_indices.iter().copied());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The names of the tables with a foreign key referencing `table_name`.
+    pub(super) fn referencing_tables(&self, table_name: &str) -> BTreeSet<String> {
+        self.references_to(table_name)
+            .map(|(table, _, _)| table.to_owned())
+            .collect()
+    }
+
+    /// Every foreign key referencing `table_name`, along with the table it belongs to.
+    pub(super) fn references_to<'a>(
+        &'a self,
+        table_name: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a Table, &'a Constraint)> {
+        self.tables.iter().flat_map(move |(name, table)| {
+            table
+                .constraints
+                .iter()
+                .filter(move |c| c.referenced_table() == Some(table_name))
+                .map(move |c| (name.as_str(), &**table, c))
+        })
+    }
+}
+
+fn column_indices(table: &Table, columns: &[String]) -> Vec<usize> {
+    columns
+        .iter()
+        .map(|c| table.find_column(c).unwrap())
+        .collect()
+}
+
+fn key_values(indices: &[usize], row: &Row) -> Vec<Value> {
+    indices.iter().map(|&i| row.0[i].clone()).collect()
+}
+
+fn has_null(key: &[Value]) -> bool {
+    key.iter().any(|v| matches!(v, Value::Null))
+}
+
+fn display_key(key: &[Value]) -> String {
+    let values: Vec<_> = key.iter().map(|v| v.to_string()).collect();
+
+    values.join(", ")
+}
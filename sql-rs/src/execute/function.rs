@@ -0,0 +1,201 @@
+use std::slice;
+
+use crate::data::{
+    Datatype, Date, DateField, Decimal, Interval, Time, Timestamp, Uuid, Value, ValueError,
+    MICROS_PER_HOUR, MICROS_PER_MINUTE, MICROS_PER_SECOND,
+};
+use crate::parse::ast::Function;
+
+/// The value of a function called with some arguments. A function of an unknown (null) argument
+/// is unknown.
+pub(super) fn call(function: Function, args: Vec<Value>) -> Result<Value, ValueError> {
+    if args.iter().any(|arg| matches!(arg, Value::Null)) {
+        return Ok(Value::Null);
+    }
+
+    match (function, args.as_slice()) {
+        (Function::Now, []) => Ok(Value::Timestamp(Timestamp::statement_time())),
+        (Function::GenRandomUuid, []) => Ok(Value::Uuid(Uuid::generate())),
+        (Function::Extract(field), [value]) => extract(field, value),
+        (Function::DateTrunc, [Value::Text(unit), value]) => date_trunc(unit, value),
+        (Function::Length, [Value::Text(s)]) => Ok(Value::Integer(s.chars().count() as i64)),
+        (Function::Length, [Value::Blob(b)]) => Ok(Value::Integer(b.len() as i64)),
+        (
+            Function::Substring,
+            [value @ (Value::Text(_) | Value::Blob(_)), Value::Integer(start)],
+        ) => substring(value, *start, None),
+        (
+            Function::Substring,
+            [value @ (Value::Text(_) | Value::Blob(_)), Value::Integer(start), Value::Integer(count)],
+        ) => substring(value, *start, Some(*count)),
+        (Function::JsonExtract, [Value::Json(json), Value::Text(path)]) => Ok(json
+            .extract(path)?
+            .map_or(Value::Null, |value| Value::Json(value.clone()))),
+        _ => Err(undefined(function, &args)),
+    }
+}
+
+/// The datatype of a function's value, called with arguments of some datatypes. A substring has
+/// the datatype of what it's part of.
+pub(super) fn function_datatype(function: Function, args: &[Datatype]) -> Datatype {
+    match function {
+        Function::Now | Function::DateTrunc => Datatype::Timestamp,
+        Function::Extract(DateField::Second | DateField::Epoch) => Datatype::Numeric(None),
+        Function::Extract(_) | Function::Length => Datatype::Integer,
+        Function::Substring => args[0],
+        Function::JsonExtract => Datatype::Json,
+        Function::GenRandomUuid => Datatype::Uuid,
+    }
+}
+
+/// The error for a function called with arguments of datatypes it doesn't take.
+fn undefined(function: Function, args: &[Value]) -> ValueError {
+    let datatypes: Vec<String> = args.iter().map(|arg| arg.datatype().to_string()).collect();
+
+    ValueError::UndefinedFunction(format!("{}({})", function, datatypes.join(", ")))
+}
+
+/// A part of a date, time, timestamp or interval. The seconds (with their fraction) and the
+/// epoch are numeric, and every other part is an integer.
+fn extract(field: DateField, value: &Value) -> Result<Value, ValueError> {
+    let (date, time) = match value {
+        Value::Date(d) => (Some(*d), None),
+        Value::Time(t) => (None, Some(*t)),
+        Value::Timestamp(t) => (Some(t.date()), Some(t.time())),
+        Value::Interval(i) => return extract_interval(field, i),
+        _ => return Err(undefined(Function::Extract(field), slice::from_ref(value))),
+    };
+    let unsupported = || ValueError::UnsupportedUnit {
+        unit: field.to_string(),
+        datatype: value.datatype(),
+    };
+
+    Ok(match field {
+        DateField::Epoch => Value::Numeric(match value {
+            Value::Date(d) => Decimal::from(d.days() * 86_400),
+            Value::Time(t) => micros_to_seconds(t.micros() as i128),
+            Value::Timestamp(t) => micros_to_seconds(t.micros() as i128),
+            _ => unreachable!(),
+        }),
+        DateField::Hour | DateField::Minute | DateField::Second => {
+            let micros = time.ok_or_else(unsupported)?.micros();
+            match field {
+                DateField::Hour => Value::Integer(micros / MICROS_PER_HOUR),
+                DateField::Minute => Value::Integer(micros / MICROS_PER_MINUTE % 60),
+                _ => Value::Numeric(micros_to_seconds((micros % MICROS_PER_MINUTE) as i128)),
+            }
+        }
+        _ => {
+            let date = date.ok_or_else(unsupported)?;
+            let (year, month, day) = date.ymd();
+            Value::Integer(match field {
+                DateField::Year => year,
+                DateField::Quarter => (month as i64 - 1) / 3 + 1,
+                DateField::Month => month as i64,
+                DateField::Week => date.iso_week() as i64,
+                DateField::Day => day as i64,
+                DateField::Dow => date.weekday() as i64,
+                DateField::Doy => date.ordinal() as i64,
+                _ => unreachable!(),
+            })
+        }
+    })
+}
+
+/// A part of an interval. Its years and months are parts of its months, and its hours, minutes
+/// and seconds parts of its time, and its epoch is its length in seconds, taking months as 30
+/// days.
+fn extract_interval(field: DateField, interval: &Interval) -> Result<Value, ValueError> {
+    let (months, micros) = (interval.months() as i64, interval.micros());
+
+    Ok(match field {
+        DateField::Year => Value::Integer(months / 12),
+        DateField::Quarter => Value::Integer(months % 12 / 3 + 1),
+        DateField::Month => Value::Integer(months % 12),
+        DateField::Day => Value::Integer(interval.days() as i64),
+        DateField::Hour => Value::Integer(micros / MICROS_PER_HOUR),
+        DateField::Minute => Value::Integer(micros / MICROS_PER_MINUTE % 60),
+        DateField::Second => {
+            Value::Numeric(micros_to_seconds((micros % MICROS_PER_MINUTE) as i128))
+        }
+        DateField::Epoch => Value::Numeric(micros_to_seconds(interval.total_micros())),
+        DateField::Week | DateField::Dow | DateField::Doy => {
+            return Err(ValueError::UnsupportedUnit {
+                unit: field.to_string(),
+                datatype: Datatype::Interval,
+            })
+        }
+    })
+}
+
+/// A number of microseconds as seconds, with six digits after the point.
+fn micros_to_seconds(micros: i128) -> Decimal {
+    // microseconds of a timestamp or interval have far fewer digits than a decimal can
+    Decimal::new(micros, 6).unwrap()
+}
+
+/// A timestamp (or date) truncated to a unit, such as `month` (the first of its month) or
+/// `hour`. Weeks begin on a Monday.
+fn date_trunc(unit: &str, value: &Value) -> Result<Value, ValueError> {
+    let timestamp = match value {
+        Value::Date(d) => Timestamp::from(*d),
+        Value::Timestamp(t) => *t,
+        _ => {
+            return Err(undefined(
+                Function::DateTrunc,
+                &[Value::Text(unit.to_owned()), value.clone()],
+            ))
+        }
+    };
+    let unsupported = || ValueError::UnsupportedUnit {
+        unit: unit.to_owned(),
+        datatype: Datatype::Timestamp,
+    };
+    let field: DateField = unit.parse().map_err(|_| unsupported())?;
+
+    let date = timestamp.date();
+    let (year, month, _) = date.ymd();
+    let micros = timestamp.time().micros();
+    let truncate_time =
+        |unit: i64| Timestamp::new(date, Time::from_micros(micros - micros % unit).unwrap());
+
+    let truncated = match field {
+        DateField::Year => Date::from_ymd(year, 1, 1),
+        DateField::Quarter => Date::from_ymd(year, (month - 1) / 3 * 3 + 1, 1),
+        DateField::Month => Date::from_ymd(year, month, 1),
+        DateField::Week => date.add_days(-(((date.weekday() + 6) % 7) as i64)),
+        DateField::Day => Some(date),
+        DateField::Hour => return Ok(Value::Timestamp(truncate_time(MICROS_PER_HOUR))),
+        DateField::Minute => return Ok(Value::Timestamp(truncate_time(MICROS_PER_MINUTE))),
+        DateField::Second => return Ok(Value::Timestamp(truncate_time(MICROS_PER_SECOND))),
+        DateField::Dow | DateField::Doy | DateField::Epoch => return Err(unsupported()),
+    };
+
+    // 0001-01-01 is a Monday, so every date truncates to one in range
+    Ok(Value::Timestamp(truncated.unwrap().into()))
+}
+
+/// Part of text (its characters) or a blob (its bytes), from a position, the first being 1, for a
+/// count, or to the end. Positions before the first are counted but empty, so
+/// `substring('abc', 0, 2)` is `'a'`.
+fn substring(value: &Value, start: i64, count: Option<i64>) -> Result<Value, ValueError> {
+    let end = match count {
+        Some(count) if count < 0 => return Err(ValueError::NegativeSubstringLength),
+        Some(count) => start.saturating_add(count),
+        None => i64::MAX,
+    };
+    // the range of indices from 0, within a length
+    let range = |len: usize| {
+        let index = |position: i64| (position.max(1) - 1).min(len as i64) as usize;
+        index(start)..index(end)
+    };
+
+    Ok(match value {
+        Value::Text(s) => {
+            let range = range(s.chars().count());
+            Value::Text(s.chars().skip(range.start).take(range.len()).collect())
+        }
+        Value::Blob(b) => Value::Blob(b[range(b.len())].to_vec()),
+        _ => unreachable!(),
+    })
+}
@@ -0,0 +1,233 @@
+use std::ops::Bound;
+
+use super::evaluate;
+use crate::data::{Index, IndexMethod, Table, Value};
+use crate::parse::ast::{Column, Expression, ExpressionOp};
+
+/// A condition on a single column of a table, comparing it with a constant.
+struct Comparison {
+    column: usize,
+    op: ExpressionOp,
+    value: Value,
+}
+
+/// How rows which may satisfy a filter are found with one of a table's indexes.
+pub(super) struct IndexLookup<'t> {
+    pub index: &'t Index,
+    key: LookupKey,
+}
+
+enum LookupKey {
+    /// The rows whose key is equal to this one.
+    Equal(Vec<Value>),
+    /// The rows whose key (a single column) is in this range. A range with a null end holds no
+    /// rows.
+    Range(Bound<Value>, Bound<Value>),
+}
+
+impl IndexLookup<'_> {
+    /// The positions of the rows found, in table order.
+    pub fn positions(&self) -> Vec<usize> {
+        let mut positions = match &self.key {
+            LookupKey::Equal(key) => self.index.get(key).to_vec(),
+            LookupKey::Range(Bound::Included(Value::Null) | Bound::Excluded(Value::Null), _)
+            | LookupKey::Range(_, Bound::Included(Value::Null) | Bound::Excluded(Value::Null)) => {
+                Vec::new()
+            }
+            LookupKey::Range(lower, upper) => {
+                self.index.range(lower.as_ref(), upper.as_ref()).unwrap()
+            }
+        };
+        positions.sort_unstable();
+
+        positions
+    }
+}
+
+/// The positions of the rows of `table` which may satisfy `filter`, found with one of the
+/// table's indexes, in table order. `None` if no index helps, and every row has to be scanned.
+///
+/// The rows found are only those satisfying one part of the filter, so the whole filter still
+/// has to be evaluated for each of them. `alias` is the name columns of the table may be
+/// qualified with in the filter.
+pub(super) fn index_lookup(
+    table: &Table,
+    alias: Option<&str>,
+    filter: &Expression<'_>,
+) -> Option<Vec<usize>> {
+    choose_index(table, alias, filter).map(|lookup| lookup.positions())
+}
+
+/// The index `index_lookup` finds the rows which may satisfy `filter` with, and how, without
+/// looking them up yet.
+pub(super) fn choose_index<'t>(
+    table: &'t Table,
+    alias: Option<&str>,
+    filter: &Expression<'_>,
+) -> Option<IndexLookup<'t>> {
+    if table.indexes.is_empty() {
+        return None;
+    }
+
+    let mut conjuncts = Vec::new();
+    split_conjuncts(filter, &mut conjuncts);
+    let comparisons: Vec<_> = conjuncts
+        .into_iter()
+        .filter_map(|expr| comparison(table, alias, expr))
+        .collect();
+
+    equality_lookup(table, &comparisons).or_else(|| range_lookup(table, &comparisons))
+}
+
+/// Look up rows with an index whose every column is compared for equality.
+fn equality_lookup<'t>(table: &'t Table, comparisons: &[Comparison]) -> Option<IndexLookup<'t>> {
+    table.indexes.iter().find_map(|index| {
+        let key: Option<Vec<_>> = index
+            .columns
+            .iter()
+            .map(|name| {
+                let column = table.find_column(name).unwrap();
+
+                comparisons
+                    .iter()
+                    .find(|c| c.column == column && matches!(c.op, ExpressionOp::Equal))
+                    .map(|c| c.value.clone())
+            })
+            .collect();
+
+        key.map(|key| IndexLookup {
+            index,
+            key: LookupKey::Equal(key),
+        })
+    })
+}
+
+/// Look up rows with an ordered index on a single column which is compared with a range.
+fn range_lookup<'t>(table: &'t Table, comparisons: &[Comparison]) -> Option<IndexLookup<'t>> {
+    table
+        .indexes
+        .iter()
+        .filter(|index| index.method() == IndexMethod::BTree && index.columns.len() == 1)
+        .find_map(|index| {
+            let column = table.find_column(&index.columns[0]).unwrap();
+            let mut lower = Bound::Unbounded;
+            let mut upper = Bound::Unbounded;
+
+            for c in comparisons.iter().filter(|c| c.column == column) {
+                // comparisons between different datatypes can't use the index's ordering
+                let datatype = table.columns[column].datatype;
+                if !matches!(c.value, Value::Null) && !c.value.datatype().same_kind(datatype) {
+                    continue;
+                }
+
+                match c.op {
+                    ExpressionOp::Greater => lower = Bound::Excluded(c.value.clone()),
+                    ExpressionOp::GreaterEqual => lower = Bound::Included(c.value.clone()),
+                    ExpressionOp::Less => upper = Bound::Excluded(c.value.clone()),
+                    ExpressionOp::LessEqual => upper = Bound::Included(c.value.clone()),
+                    _ => {}
+                }
+            }
+
+            match (lower, upper) {
+                (Bound::Unbounded, Bound::Unbounded) => None,
+                (lower, upper) => Some(IndexLookup {
+                    index,
+                    key: LookupKey::Range(lower, upper),
+                }),
+            }
+        })
+}
+
+fn split_conjuncts<'a, 'input>(
+    expr: &'a Expression<'input>,
+    conjuncts: &mut Vec<&'a Expression<'input>>,
+) {
+    match expr {
+        Expression::BinaryOp(b) if matches!(b.op, ExpressionOp::And) => {
+            split_conjuncts(&b.left, conjuncts);
+            split_conjuncts(&b.right, conjuncts);
+        }
+        _ => conjuncts.push(expr),
+    }
+}
+
+/// A comparison of a column of `table` with a constant, with the column on the left.
+fn comparison(table: &Table, alias: Option<&str>, expr: &Expression<'_>) -> Option<Comparison> {
+    let b = match expr {
+        Expression::BinaryOp(b) => b,
+        _ => return None,
+    };
+
+    let (column, constant, op) = match (
+        table_column(table, alias, &b.left),
+        table_column(table, alias, &b.right),
+    ) {
+        (Some(column), None) => (column, &*b.right, b.op),
+        (None, Some(column)) => (column, &*b.left, flip(b.op)?),
+        _ => return None,
+    };
+
+    let is_constant = constant.column_identifiers().is_empty()
+        && !constant.contains_subquery()
+        && !constant.contains_aggregate();
+    if !is_constant {
+        return None;
+    }
+
+    if !matches!(
+        op,
+        ExpressionOp::Equal
+            | ExpressionOp::Greater
+            | ExpressionOp::GreaterEqual
+            | ExpressionOp::Less
+            | ExpressionOp::LessEqual
+    ) {
+        return None;
+    }
+
+    // a constant which fails to evaluate fails when the filter is evaluated instead
+    let value = evaluate(constant, None, None).ok()?;
+    // the index holds values of the column's datatype, so a number of another numeric datatype
+    // is looked up as one, if it converts to it exactly, and text is parsed as one. Text which
+    // doesn't parse fails when the filter is evaluated instead.
+    let value = match value.clone().cast(table.columns[column].datatype) {
+        Ok(cast) if cast.equals(&value) => cast,
+        Ok(_) => value,
+        Err(_) if matches!(value, Value::Text(_)) => return None,
+        Err(_) => value,
+    };
+
+    Some(Comparison { column, op, value })
+}
+
+/// The index of the column of `table` an expression names, if it names one.
+fn table_column(table: &Table, alias: Option<&str>, expr: &Expression<'_>) -> Option<usize> {
+    let identifier = match expr {
+        Expression::ColumnIdentifier(i) => i,
+        _ => return None,
+    };
+
+    match (&identifier.alias, alias) {
+        (None, _) => {}
+        (Some(a), Some(alias)) if a.0 == alias => {}
+        _ => return None,
+    }
+
+    match &identifier.name {
+        Column::Ident(name) => table.find_column(name.0),
+        Column::Star => None,
+    }
+}
+
+/// The operator comparing the same operands the other way around.
+fn flip(op: ExpressionOp) -> Option<ExpressionOp> {
+    Some(match op {
+        ExpressionOp::Equal => ExpressionOp::Equal,
+        ExpressionOp::Greater => ExpressionOp::Less,
+        ExpressionOp::GreaterEqual => ExpressionOp::LessEqual,
+        ExpressionOp::Less => ExpressionOp::Greater,
+        ExpressionOp::LessEqual => ExpressionOp::GreaterEqual,
+        _ => return None,
+    })
+}
@@ -1,6 +1,8 @@
+use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
+use std::{iter, slice};
 
-use crate::data::{Database, Row};
+use crate::data::{ConstraintViolation, Database, Row, Value, ValueError};
 use crate::parse::ast::InsertQuery;
 
 #[derive(Debug)]
@@ -14,16 +16,20 @@ impl Display for Success {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("Table with that name does not exist")]
+    TableNotFound,
+    #[error("number of columns and number of values must match")]
     IncorrectColumnNumber,
+    #[error("columns must exist in the table, and be listed at most once")]
+    InvalidColumns,
+    #[error("value is not compatible with the column's datatype")]
     IncompatibleDatatype,
-}
-
-impl Display for Error {
-    fn fmt(&self, _f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        todo!()
-    }
+    #[error(transparent)]
+    ConstraintViolation(#[from] ConstraintViolation),
+    #[error(transparent)]
+    Value(#[from] ValueError),
 }
 
 type QueryResult = Result<Success, Error>;
@@ -34,28 +40,37 @@ impl Database {
             return Err(Error::IncorrectColumnNumber);
         }
 
-        let table = self.find_table_mut(query.table.0);
+        let table = self.tables.get(query.table.0).ok_or(Error::TableNotFound)?;
         let mut indices = table
             .validate_insert_query_columns(
                 &(query.columns.iter().map(|i| i.0).collect::<Vec<&str>>()),
             )
-            .unwrap();
-        let mut row = table.new_values_vec();
+            .ok_or(Error::InvalidColumns)?;
+        let mut row = table.default_values()?;
         let mut values = query.values;
 
-        while values.len() != 0 {
+        while !values.is_empty() {
             let i = indices.pop().unwrap();
             let value = values.pop().unwrap();
 
-            let value = (&value).into();
+            let value = Value::try_from(&value)?;
             if !table.compatible_type(i, &value) {
                 return Err(Error::IncompatibleDatatype);
             }
 
-            row[i] = value;
+            row[i] = value.cast(table.columns[i].datatype)?;
         }
 
-        table.rows.push(Row(row));
+        let row = Row(row);
+        table.check_insert(&row)?;
+        self.check_references(
+            query.table.0,
+            table,
+            slice::from_ref(&row),
+            table.rows.iter().chain(iter::once(&row)),
+        )?;
+
+        self.find_table_mut(query.table.0).push_row(row);
 
         Ok(Success { num_inserted: 1 })
     }
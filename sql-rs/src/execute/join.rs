@@ -0,0 +1,517 @@
+use super::evaluate;
+use super::operator::{drain, BoxedOperator, Operator, SharedStats};
+use super::select::Error;
+use crate::data::{
+    sorted_by, Column, Database, InnerRows, JoinKeys, JoinMethod, Row, Table, Value,
+};
+use crate::parse::ast::{Expression, ExpressionOp, JoinCondition, JoinKind};
+use crate::plan::LogicalPlan;
+
+/// Join the rows of two operators, choosing how from the join's condition, before any rows are
+/// pulled. `inner_plan` is the plan of the inner operator (the left one for a right join, the
+/// right one otherwise).
+///
+/// A condition requiring columns of the two tables to be equal is an equi-join, which needs only
+/// test the pairs of rows with equal values in those columns: with a sort-merge join if the
+/// inner rows are scanned from a table stored sorted by them, with a hash join otherwise. Any
+/// other condition falls back to a nested loop join. Every way produces the same rows, in the
+/// same order.
+pub(super) fn join<'a>(
+    left: BoxedOperator<'a>,
+    right: BoxedOperator<'a>,
+    kind: JoinKind,
+    condition: &'a JoinCondition<'a>,
+    inner_plan: &LogicalPlan<'_>,
+    database: &'a Database,
+    stats: SharedStats,
+) -> Result<BoxedOperator<'a>, Error> {
+    let mut join = match condition {
+        JoinCondition::On(condition) => {
+            let keys = equi_join_keys(left.columns(), right.columns(), condition);
+            Join::new(left, right, kind, keys, Some(condition), database, stats)
+        }
+        JoinCondition::Using(columns) => {
+            let names: Vec<_> = columns.iter().map(|c| c.0).collect();
+            join_using(left, right, &names, kind, database, stats)?
+        }
+        JoinCondition::Natural => {
+            let names: Vec<_> = left
+                .columns()
+                .iter()
+                .filter(|c| !c.hidden)
+                .map(Column::unqualified_name)
+                .filter(|&name| using_column(right.columns(), name).is_some())
+                .map(str::to_owned)
+                .collect();
+            let names: Vec<_> = names.iter().map(String::as_str).collect();
+            join_using(left, right, &names, kind, database, stats)?
+        }
+        JoinCondition::None => Join::new(left, right, kind, None, None, database, stats),
+    };
+    join.choose_method(inner_plan);
+
+    Ok(Box::new(join))
+}
+
+/// Join on the columns named `names`, which both tables must have, then merge each pair of
+/// columns into one. The merged columns come first, and the columns they're merged from are
+/// hidden.
+///
+/// With no columns to join on (a natural join of tables with no column in common), every pair of
+/// rows matches.
+fn join_using<'a>(
+    left: BoxedOperator<'a>,
+    right: BoxedOperator<'a>,
+    names: &[&str],
+    kind: JoinKind,
+    database: &'a Database,
+    stats: SharedStats,
+) -> Result<Join<'a>, Error> {
+    if names.is_empty() {
+        return Ok(Join::new(left, right, kind, None, None, database, stats));
+    }
+
+    let left_column_count = left.columns().len();
+    let mut keys = JoinKeys {
+        left: Vec::new(),
+        right: Vec::new(),
+    };
+    let mut merged = Vec::new();
+    let mut compared = Vec::new();
+    for &name in names {
+        match (
+            using_column(left.columns(), name),
+            using_column(right.columns(), name),
+        ) {
+            (Some(l), Some(r)) => {
+                merged.push((l, left_column_count + r));
+                // the same as for a condition ON the columns being equal, columns of different
+                // datatypes can be equal without their values being the same, so are compared
+                // rather than matched up by value
+                if left.columns()[l]
+                    .datatype
+                    .same_kind(right.columns()[r].datatype)
+                {
+                    keys.left.push(l);
+                    keys.right.push(r);
+                } else {
+                    compared.push((l, left_column_count + r));
+                }
+            }
+            _ => return Err(Error::JoinColumnNotFound(name.to_owned())),
+        }
+    }
+
+    // the rows a hash (or sort-merge) join pairs up are exactly the rows with equal keys, so
+    // only the columns which aren't keys are left to check
+    let keys = (!keys.left.is_empty()).then_some(keys);
+    let mut join = Join::new(left, right, kind, keys, None, database, stats);
+    join.compared = compared;
+
+    let mut columns: Vec<_> = merged
+        .iter()
+        .zip(names)
+        .map(|(&(l, _), name)| Column::new(name.to_string(), join.columns[l].datatype))
+        .collect();
+    for &(l, r) in &merged {
+        join.columns[l].hidden = true;
+        join.columns[r].hidden = true;
+    }
+    columns.append(&mut join.columns);
+    join.columns = columns;
+    join.merged = merged;
+
+    Ok(join)
+}
+
+/// The rows of two operators, paired up.
+///
+/// Rows are pulled from the outer operator (the right one for a right join, the left one
+/// otherwise) one at a time, and tested against the inner operator's rows it's paired with,
+/// which are all pulled into memory first. A row is produced for each pair for which the
+/// predicate holds. An outer row with no such pair is padded with nulls, unless the join is an
+/// inner (or cross) join. A full join then also produces each inner row which wasn't paired with
+/// any outer row, padded with nulls. The columns of the left table always come first.
+struct Join<'a> {
+    outer: BoxedOperator<'a>,
+    /// The inner operator, until its rows are pulled.
+    inner: Option<BoxedOperator<'a>>,
+    inner_rows: InnerRows,
+    /// Whether each inner row has been paired with an outer row.
+    inner_matched: Vec<bool>,
+    keys: Option<JoinKeys>,
+    method: JoinMethod,
+    kind: JoinKind,
+    predicate: Option<&'a Expression<'a>>,
+    database: &'a Database,
+    stats: SharedStats,
+
+    /// The columns of both tables, as the predicate sees them.
+    joined_columns: Vec<Column>,
+    /// The columns produced, which for a USING join start with the merged columns.
+    columns: Vec<Column>,
+    /// The pairs of columns merged by a USING join, as indices into `joined_columns`.
+    merged: Vec<(usize, usize)>,
+    /// The pairs of columns a USING join compares for each pair of rows, rather than matching
+    /// rows up by, as indices into `joined_columns`.
+    compared: Vec<(usize, usize)>,
+    left_column_count: usize,
+    right_column_count: usize,
+
+    /// The outer row being paired up, the positions of the inner rows it's paired with, and how
+    /// many of those have been tested.
+    outer_row: Option<Row>,
+    outer_matched: bool,
+    candidates: Vec<usize>,
+    tested: usize,
+    /// For a full join, how many inner rows have been checked for being unmatched, once every
+    /// outer row has been paired up.
+    unmatched_checked: usize,
+    /// A buffer each pair of rows is built in, only copied if it's kept.
+    pair: Row,
+}
+
+impl<'a> Join<'a> {
+    fn new(
+        left: BoxedOperator<'a>,
+        right: BoxedOperator<'a>,
+        kind: JoinKind,
+        keys: Option<JoinKeys>,
+        predicate: Option<&'a Expression<'a>>,
+        database: &'a Database,
+        stats: SharedStats,
+    ) -> Self {
+        let left_column_count = left.columns().len();
+        let right_column_count = right.columns().len();
+        let joined_columns: Vec<_> = left
+            .columns()
+            .iter()
+            .chain(right.columns())
+            .cloned()
+            .collect();
+
+        let (outer, inner) = match kind {
+            JoinKind::Right => (right, left),
+            _ => (left, right),
+        };
+
+        Join {
+            outer,
+            inner: Some(inner),
+            inner_rows: InnerRows::all(Vec::new()),
+            inner_matched: Vec::new(),
+            keys,
+            method: JoinMethod::NestedLoop,
+            kind,
+            predicate,
+            database,
+            stats,
+            columns: joined_columns.clone(),
+            joined_columns,
+            merged: Vec::new(),
+            compared: Vec::new(),
+            left_column_count,
+            right_column_count,
+            outer_row: None,
+            outer_matched: false,
+            candidates: Vec::new(),
+            tested: 0,
+            unmatched_checked: 0,
+            pair: Row(Vec::new()),
+        }
+    }
+
+    /// Decide how to find the pairs of rows to test: a nested loop without keys to match rows
+    /// on, and otherwise a sort-merge join if the inner rows come from scanning a table whose
+    /// rows are stored in order of the keys (a scan keeps the table's order), or a hash join.
+    fn choose_method(&mut self, inner_plan: &LogicalPlan<'_>) {
+        self.method = match &self.keys {
+            None => JoinMethod::NestedLoop,
+            Some(keys) => {
+                let inner_keys = match self.kind {
+                    JoinKind::Right => &keys.left,
+                    _ => &keys.right,
+                };
+
+                match self.database.scans_sorted(inner_plan, inner_keys) {
+                    true => JoinMethod::Merge,
+                    false => JoinMethod::Hash,
+                }
+            }
+        };
+
+        self.stats.borrow_mut().method = Some(self.method.to_string());
+    }
+
+    /// Pull every inner row, and arrange them for finding the rows each outer row may match.
+    fn pull_inner_rows(&mut self, mut inner: BoxedOperator<'a>) -> Result<(), Error> {
+        let rows = drain(&mut inner)?;
+        self.inner_matched = vec![false; rows.len()];
+
+        let sorted = self.method == JoinMethod::Merge;
+        self.inner_rows = match (&self.keys, self.kind) {
+            (None, _) => InnerRows::all(rows),
+            (Some(keys), JoinKind::Right) => {
+                InnerRows::keyed(rows, keys.right.clone(), &keys.left, sorted)
+            }
+            (Some(keys), _) => InnerRows::keyed(rows, keys.left.clone(), &keys.right, sorted),
+        };
+
+        Ok(())
+    }
+
+    /// Test the outer row against the next inner row it's paired with, if there is one.
+    fn test_next_candidate(&mut self) -> Result<Option<Option<Row>>, Error> {
+        let (outer_row, &i) = match (self.outer_row.as_ref(), self.candidates.get(self.tested)) {
+            (Some(outer_row), Some(i)) => (outer_row, i),
+            _ => return Ok(None),
+        };
+        self.tested += 1;
+
+        let inner_row = &self.inner_rows.rows[i];
+        let (left, right) = match self.kind {
+            JoinKind::Right => (inner_row, outer_row),
+            _ => (outer_row, inner_row),
+        };
+
+        self.pair.0.clear();
+        self.pair.0.extend_from_slice(&left.0);
+        self.pair.0.extend_from_slice(&right.0);
+        let matches = match self.predicate {
+            Some(predicate) => evaluate(
+                predicate,
+                Some((&self.joined_columns, &self.pair)),
+                Some(self.database),
+            )?
+            .is_true()?,
+            None => true,
+        };
+        let matches = matches && self.columns_equal()?;
+
+        if matches {
+            self.outer_matched = true;
+            self.inner_matched[i] = true;
+            Ok(Some(Some(self.pair.clone())))
+        } else {
+            Ok(Some(None))
+        }
+    }
+
+    /// The outer row, padded with nulls, if it wasn't paired with any inner row and the join
+    /// keeps unmatched outer rows.
+    fn unmatched_outer_row(&self, outer_row: Row) -> Option<Row> {
+        if self.outer_matched {
+            return None;
+        }
+
+        match self.kind {
+            JoinKind::Left | JoinKind::Full => {
+                let mut values = outer_row.0;
+                values.resize(
+                    self.left_column_count + self.right_column_count,
+                    Value::Null,
+                );
+                Some(Row(values))
+            }
+            JoinKind::Right => {
+                let mut values = vec![Value::Null; self.left_column_count];
+                values.extend(outer_row.0);
+                Some(Row(values))
+            }
+            JoinKind::Inner | JoinKind::Cross => None,
+        }
+    }
+
+    /// For a full join, the next inner row which wasn't paired with any outer row, padded with
+    /// nulls.
+    fn next_unmatched_inner_row(&mut self) -> Option<Row> {
+        if !matches!(self.kind, JoinKind::Full) {
+            return None;
+        }
+
+        while self.unmatched_checked < self.inner_rows.rows.len() {
+            let i = self.unmatched_checked;
+            self.unmatched_checked += 1;
+
+            if !self.inner_matched[i] {
+                let mut values = vec![Value::Null; self.left_column_count];
+                values.extend_from_slice(&self.inner_rows.rows[i].0);
+                return Some(Row(values));
+            }
+        }
+
+        None
+    }
+
+    /// Put the merged columns of a USING join first. The merged value is whichever side's isn't
+    /// null because the row was unmatched.
+    fn merge_columns(&self, mut row: Row) -> Row {
+        if self.merged.is_empty() {
+            return row;
+        }
+
+        let mut values: Vec<_> = self
+            .merged
+            .iter()
+            .map(|&(l, r)| match (self.kind, &row.0[l]) {
+                (JoinKind::Right, _) | (JoinKind::Full, Value::Null) => row.0[r].clone(),
+                _ => row.0[l].clone(),
+            })
+            .collect();
+        values.append(&mut row.0);
+
+        Row(values)
+    }
+}
+
+impl Operator for Join<'_> {
+    fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        if let Some(inner) = self.inner.take() {
+            self.pull_inner_rows(inner)?;
+        }
+
+        loop {
+            match self.test_next_candidate()? {
+                Some(Some(row)) => return Ok(Some(self.merge_columns(row))),
+                Some(None) => continue,
+                None => {}
+            }
+
+            // every pair with the current outer row has been tested, so move on to the next
+            if let Some(outer_row) = self.outer_row.take() {
+                if let Some(row) = self.unmatched_outer_row(outer_row) {
+                    return Ok(Some(self.merge_columns(row)));
+                }
+            }
+
+            match self.outer.next()? {
+                Some(outer_row) => {
+                    self.inner_rows.candidates(&outer_row, &mut self.candidates);
+                    self.tested = 0;
+                    self.outer_matched = false;
+                    self.outer_row = Some(outer_row);
+                }
+                None => {
+                    let row = self.next_unmatched_inner_row();
+                    return Ok(row.map(|row| self.merge_columns(row)));
+                }
+            }
+        }
+    }
+}
+
+impl Join<'_> {
+    /// Whether the pair of rows being tested has equal values in each pair of columns compared.
+    /// Every pair is compared, the same as each part of a condition joined by `and` is evaluated.
+    fn columns_equal(&self) -> Result<bool, Error> {
+        let mut equal = true;
+        for &(l, r) in &self.compared {
+            let (l, r) = (self.pair.0[l].clone(), self.pair.0[r].clone());
+            equal &= l.op(ExpressionOp::Equal, r)?.is_true()?;
+        }
+
+        Ok(equal)
+    }
+}
+
+impl Database {
+    /// Whether a plan scans a stored table whose rows are in order of the columns at `columns`
+    /// of the rows produced.
+    fn scans_sorted(&self, plan: &LogicalPlan<'_>, columns: &[usize]) -> bool {
+        let (table, scanned) = match plan {
+            LogicalPlan::Scan { table, columns, .. } => (self.find_table(table), columns),
+            _ => return false,
+        };
+        let columns: Vec<_> = match scanned {
+            Some(scanned) => columns
+                .iter()
+                .map(|&i| table.find_column(&scanned[i]).unwrap())
+                .collect(),
+            None => columns.to_vec(),
+        };
+
+        sorted_by(&table.rows, &columns)
+    }
+}
+
+/// The index of the column a USING join names, leaving out hidden columns.
+fn using_column(columns: &[Column], name: &str) -> Option<usize> {
+    columns
+        .iter()
+        .position(|c| !c.hidden && c.unqualified_name() == name)
+}
+
+/// The pairs of columns a join condition requires to be equal, one of each table, found in the
+/// parts of the condition joined by `and`. `None` if there aren't any.
+fn equi_join_keys(
+    left: &[Column],
+    right: &[Column],
+    condition: &Expression<'_>,
+) -> Option<JoinKeys> {
+    let mut keys = JoinKeys {
+        left: Vec::new(),
+        right: Vec::new(),
+    };
+    add_equi_join_keys(left, right, condition, &mut keys);
+
+    if keys.left.is_empty() {
+        None
+    } else {
+        Some(keys)
+    }
+}
+
+fn add_equi_join_keys(
+    left: &[Column],
+    right: &[Column],
+    condition: &Expression<'_>,
+    keys: &mut JoinKeys,
+) {
+    let b = match condition {
+        Expression::BinaryOp(b) => b,
+        _ => return,
+    };
+
+    match b.op {
+        ExpressionOp::And => {
+            add_equi_join_keys(left, right, &b.left, keys);
+            add_equi_join_keys(left, right, &b.right, keys);
+        }
+        ExpressionOp::Equal => match (side(left, right, &b.left), side(left, right, &b.right)) {
+            // numbers of different datatypes can be equal without their values being the same,
+            // so they can't be matched up by value
+            (Some(Side::Left(l)), Some(Side::Right(r)))
+            | (Some(Side::Right(r)), Some(Side::Left(l)))
+                if left[l].datatype.same_kind(right[r].datatype) =>
+            {
+                keys.left.push(l);
+                keys.right.push(r);
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// A column of one of the tables being joined.
+enum Side {
+    Left(usize),
+    Right(usize),
+}
+
+/// Which table's column an expression names, if it names one. Columns are looked for in the left
+/// table first, the same as when the condition is evaluated over the joined columns.
+fn side(left: &[Column], right: &[Column], expr: &Expression<'_>) -> Option<Side> {
+    let identifier = match expr {
+        Expression::ColumnIdentifier(i) => i,
+        _ => return None,
+    };
+
+    Table::find_column_idx(left, identifier)
+        .map(Side::Left)
+        .or_else(|| Table::find_column_idx(right, identifier).map(Side::Right))
+}
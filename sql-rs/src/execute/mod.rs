@@ -1,93 +1,175 @@
-///! TODO long module description.
+//! TODO long module description.
 
 /// TODO doc
 mod select;
-pub use select::*;
+
+/// Showing the plan a select query is executed with.
+mod explain;
 
 /// TODO doc
 mod insert;
-pub use insert::*;
 
 /// TODO doc
 mod create_table;
 pub use create_table::*;
 
+/// Adding indexes to tables.
+mod create_index;
+
+/// Finding rows with an index, rather than scanning a whole table.
+mod index;
+
+/// Pulling the rows of a select query through the operators of its plan, one at a time.
+mod operator;
+
+/// Evaluating filters and aggregates over batches of column vectors.
+mod vectorized;
+
+/// Choosing how to join tables.
+mod join;
+
 /// TODO doc
 mod update;
-pub use update::*;
+
+/// Removing rows from a table.
+mod delete;
+
+/// Removing tables from the database.
+mod drop_table;
+
+/// Changing the definition of existing tables.
+mod alter_table;
+
+/// Enforcing foreign keys, which span tables.
+mod foreign_key;
 
 /// TODO doc
 mod evaluate;
 pub use evaluate::*;
 
+/// Evaluating calls of functions, such as `now()`, for a row.
+mod function;
+
+/// Grouping rows and evaluating aggregate functions over groups.
+mod aggregate;
+pub use aggregate::*;
+
+/// Grouping queries into transactions, whose changes are kept or undone together.
+mod transaction;
+
+/// Executing queries outside of a transaction, reading a snapshot of the database or changing
+/// its latest version.
+mod snapshot;
+
+/// Writing the changes queries make to the file a database is stored in.
+mod persist;
+
 use std::fmt::{Display, Formatter};
 
-use crate::data::Database;
+use crate::data::{Database, Timestamp, Uuid};
 use crate::parse::ast::Query;
+use crate::storage;
 
 #[derive(Debug)]
 pub enum Success {
     Select(select::Success),
+    Explain(explain::Success),
     Insert(insert::Success),
     CreateTable(create_table::Success),
+    CreateIndex(create_index::Success),
     Update(update::Success),
+    Delete(delete::Success),
+    DropTable(drop_table::Success),
+    AlterTable(alter_table::Success),
+    Transaction(transaction::Success),
 }
 
 impl Display for Success {
     fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
         match self {
             Success::Select(s) => write!(f, "{}", s),
+            Success::Explain(s) => write!(f, "{}", s),
             Success::CreateTable(s) => write!(f, "{}", s),
+            Success::CreateIndex(s) => write!(f, "{}", s),
             Success::Insert(s) => write!(f, "{}", s),
             Success::Update(s) => write!(f, "{}", s),
+            Success::Delete(s) => write!(f, "{}", s),
+            Success::DropTable(s) => write!(f, "{}", s),
+            Success::AlterTable(s) => write!(f, "{}", s),
+            Success::Transaction(s) => write!(f, "{}", s),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum Error {
-    QueryFailed,
-}
-
-impl Display for Error {
-    fn fmt(&self, _f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        todo!()
-    }
-}
-
-impl From<select::Error> for Error {
-    fn from(_: select::Error) -> Self {
-        Error::QueryFailed
-    }
-}
-
-impl From<insert::Error> for Error {
-    fn from(_: insert::Error) -> Self {
-        Error::QueryFailed
-    }
-}
-
-impl From<create_table::Error> for Error {
-    fn from(_: create_table::Error) -> Self {
-        Error::QueryFailed
-    }
-}
-
-impl From<update::Error> for Error {
-    fn from(_: update::Error) -> Self {
-        Error::QueryFailed
-    }
+    #[error(transparent)]
+    Select(#[from] select::Error),
+    #[error(transparent)]
+    Insert(#[from] insert::Error),
+    #[error(transparent)]
+    CreateTable(#[from] create_table::Error),
+    #[error(transparent)]
+    CreateIndex(#[from] create_index::Error),
+    #[error(transparent)]
+    Update(#[from] update::Error),
+    #[error(transparent)]
+    Delete(#[from] delete::Error),
+    #[error(transparent)]
+    DropTable(#[from] drop_table::Error),
+    #[error(transparent)]
+    AlterTable(#[from] alter_table::Error),
+    #[error(transparent)]
+    Transaction(#[from] transaction::Error),
+    #[error(transparent)]
+    Storage(#[from] storage::Error),
 }
 
 impl Database {
+    /// Execute a query, recording it in the write-ahead log first if it may change the
+    /// database, then write any changes it made to the database's file. Within a transaction,
+    /// that's left until the transaction commits.
+    ///
+    /// A query which fails leaves the database as it was.
+    ///
+    /// Every call to `now()` in the query gives the time it began, or within a transaction, the
+    /// time the transaction began. `gen_random_uuid()` generates uuids from a new seed, or from
+    /// the transaction's, which is logged so that the same uuids are generated again.
     pub fn execute(&mut self, query: Query<'_>) -> Result<Success, Error> {
+        Timestamp::set_statement_time(match &self.transaction {
+            Some(transaction) => transaction.started,
+            None => Timestamp::now(),
+        });
+        Uuid::set_statement_seed(Uuid::new_seed(), 0);
+
+        match query {
+            Query::TransactionQuery(query) => {
+                Ok(Success::Transaction(self.execute_transaction(query)?))
+            }
+            query if self.transaction.is_some() => self.execute_in_transaction(query),
+            Query::SelectQuery(_) | Query::ExplainQuery(_) => self.execute_read(query),
+            query => self.execute_write(query),
+        }
+    }
+
+    fn execute_query(&mut self, query: Query<'_>) -> Result<Success, Error> {
         Ok(match query {
             Query::SelectQuery(query) => Success::Select(self.execute_select(&query)?),
+            Query::ExplainQuery(query) => Success::Explain(self.execute_explain(&query)?),
             Query::InsertQuery(query) => Success::Insert(self.execute_insert(query)?),
             Query::CreateTableQuery(query) => {
                 Success::CreateTable(self.execute_create_table(query)?)
             }
+            Query::CreateIndexQuery(query) => {
+                Success::CreateIndex(self.execute_create_index(query)?)
+            }
             Query::UpdateQuery(query) => Success::Update(self.execute_update(query)?),
+            Query::DeleteQuery(query) => Success::Delete(self.execute_delete(query)?),
+            Query::DropTableQuery(query) => Success::DropTable(self.execute_drop_table(query)?),
+            Query::AlterTableQuery(query) => Success::AlterTable(self.execute_alter_table(query)?),
+            Query::TransactionQuery(query) => {
+                Success::Transaction(self.execute_transaction(query)?)
+            }
         })
     }
 }
@@ -0,0 +1,548 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use std::vec;
+
+use super::index::{choose_index, IndexLookup};
+use super::join::join;
+use super::select::{star_values, Error};
+use super::vectorized::BatchAggregation;
+use super::{aggregate_columns, check_columns, evaluate, evaluate_column};
+use crate::data::{Column, Database, Row, Table, Value, BATCH_SIZE};
+use crate::parse::ast::{self, Aggregate, Expression, JoinCondition, JoinKind, OrderByDirection};
+use crate::plan::LogicalPlan;
+
+/// A source of rows, which are pulled from it one at a time.
+///
+/// The operators executing a plan are stacked up the same way as the plan, each pulling rows
+/// from the operators below it only as it needs them. Only operators which need every row before
+/// producing any (such as sorting) hold more than a row at a time, so a query only does the work
+/// needed for the rows pulled from it: `limit 10` stops after 10 rows.
+pub(super) trait Operator {
+    /// The columns of the rows produced.
+    fn columns(&self) -> &[Column];
+
+    /// The next row, or `None` once every row has been produced. Fails if evaluating an
+    /// expression for the row does.
+    fn next(&mut self) -> Result<Option<Row>, Error>;
+}
+
+pub(super) type BoxedOperator<'a> = Box<dyn Operator + 'a>;
+
+/// What executing one operator of a plan did.
+#[derive(Debug, Default)]
+pub(super) struct OperatorStats {
+    /// The number of rows it produced.
+    pub rows: usize,
+    /// How long it took, including the operators below it.
+    pub time: Duration,
+    /// How it went about it, where there's a choice (such as which join algorithm to use).
+    pub method: Option<String>,
+}
+
+/// The stats of an operator, shared between the operator recording them and whoever reads them.
+pub(super) type SharedStats = Rc<RefCell<OperatorStats>>;
+
+impl Database {
+    /// Stack up the operators producing the rows of a plan.
+    ///
+    /// The stats of each operator are added to `stats`, in the order the operators are visited in
+    /// (each before those below it). The rows produced and time taken are only measured when
+    /// `analyze` is set, since measuring slows every row down.
+    pub(super) fn build_operator<'a>(
+        &'a self,
+        plan: &'a LogicalPlan<'a>,
+        analyze: bool,
+        stats: &mut Vec<SharedStats>,
+    ) -> Result<BoxedOperator<'a>, Error> {
+        let operator_stats = SharedStats::default();
+        stats.push(Rc::clone(&operator_stats));
+
+        let operator: BoxedOperator<'a> = match plan {
+            LogicalPlan::Scan {
+                table,
+                alias,
+                filter,
+                columns,
+            } => Box::new(Scan::new(
+                self,
+                self.find_table(table),
+                alias,
+                filter.as_ref(),
+                columns.as_deref(),
+                Rc::clone(&operator_stats),
+            )?),
+            LogicalPlan::Filter { input, predicate } => {
+                let input = self.build_operator(input, analyze, stats)?;
+                check_columns(predicate, input.columns())?;
+
+                Box::new(Filter {
+                    input,
+                    predicate,
+                    database: self,
+                })
+            }
+            LogicalPlan::Project { input, expressions } => {
+                let input = self.build_operator(input, analyze, stats)?;
+                let columns = expressions
+                    .iter()
+                    .map(|expr| evaluate_column(expr, input.columns()))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .concat();
+
+                Box::new(Project {
+                    input,
+                    expressions,
+                    columns,
+                    database: self,
+                })
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                kind,
+                condition,
+            } => {
+                let inner_plan = match kind {
+                    JoinKind::Right => left,
+                    _ => right,
+                };
+                let left = self.build_operator(left, analyze, stats)?;
+                let right = self.build_operator(right, analyze, stats)?;
+                if let JoinCondition::On(condition) = condition {
+                    check_columns(condition, &[left.columns(), right.columns()].concat())?;
+                }
+
+                join(
+                    left,
+                    right,
+                    *kind,
+                    condition,
+                    inner_plan,
+                    self,
+                    Rc::clone(&operator_stats),
+                )?
+            }
+            LogicalPlan::Sort {
+                input,
+                expr,
+                direction,
+            } => {
+                let input = self.build_operator(input, analyze, stats)?;
+                check_columns(expr, input.columns())?;
+
+                Box::new(Sort {
+                    input,
+                    expr,
+                    direction: *direction,
+                    database: self,
+                    rows: None,
+                })
+            }
+            LogicalPlan::Limit { input, count } => {
+                // a limit of null is no limit at all
+                let remaining = match evaluate(count, None, Some(self))? {
+                    Value::Integer(count) => {
+                        usize::try_from(count).map_err(|_| Error::NegativeLimit)?
+                    }
+                    Value::Null => usize::MAX,
+                    value => return Err(Error::NonIntegerLimit(value.datatype())),
+                };
+
+                Box::new(Limit {
+                    input: self.build_operator(input, analyze, stats)?,
+                    remaining,
+                })
+            }
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+            } => {
+                let input = self.build_operator(input, analyze, stats)?;
+                for expr in group_by {
+                    check_columns(expr, input.columns())?;
+                }
+                let columns = aggregate_columns(input.columns(), aggregates)?;
+
+                Box::new(Aggregated {
+                    input,
+                    group_by,
+                    aggregates,
+                    columns,
+                    database: self,
+                    rows: None,
+                })
+            }
+        };
+
+        if analyze {
+            Ok(Box::new(Measured {
+                input: operator,
+                stats: operator_stats,
+            }))
+        } else {
+            Ok(operator)
+        }
+    }
+}
+
+/// Pull every row from an operator into a table.
+pub(super) fn collect(mut operator: BoxedOperator<'_>) -> Result<Table, Error> {
+    let columns = operator.columns().to_vec();
+    let rows = drain(&mut operator)?;
+
+    Ok(Table {
+        columns,
+        rows,
+        constraints: Vec::new(),
+        indexes: Vec::new(),
+    })
+}
+
+/// Pull every row from an operator.
+pub(super) fn drain(operator: &mut BoxedOperator<'_>) -> Result<Vec<Row>, Error> {
+    let mut rows = Vec::new();
+    while let Some(row) = operator.next()? {
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// The rows of a stored table which satisfy a filter, with each column named `alias.column`.
+struct Scan<'a> {
+    database: &'a Database,
+    table: &'a Table,
+    filter: Option<&'a Expression<'a>>,
+    /// Every column of the table, as the filter sees them.
+    table_columns: Vec<Column>,
+    /// The columns produced, and the index of each in the table (if not every column is).
+    columns: Vec<Column>,
+    projection: Option<Vec<usize>>,
+    /// How the rows which may satisfy the filter are found with an index, if one helps.
+    lookup: Option<IndexLookup<'a>>,
+    /// The positions of the rows left to scan, found when the first row is pulled.
+    positions: Option<Box<dyn Iterator<Item = usize> + 'a>>,
+    /// When filtering a batch of rows at a time, the positions of the rows of the last batch
+    /// which satisfied the filter.
+    selected: vec::IntoIter<usize>,
+}
+
+impl<'a> Scan<'a> {
+    fn new(
+        database: &'a Database,
+        table: &'a Table,
+        alias: &'a str,
+        filter: Option<&'a Expression<'a>>,
+        columns: Option<&[String]>,
+        stats: SharedStats,
+    ) -> Result<Self, Error> {
+        let table_columns: Vec<_> = table
+            .columns
+            .iter()
+            .map(|c| Column {
+                name: format!("{}.{}", alias, c.name),
+                ..c.clone()
+            })
+            .collect();
+        if let Some(filter) = filter {
+            check_columns(filter, &table_columns)?;
+        }
+
+        let projection: Option<Vec<_>> = columns.map(|columns| {
+            columns
+                .iter()
+                .map(|name| table.find_column(name).unwrap())
+                .collect()
+        });
+        let columns = match &projection {
+            Some(projection) => projection
+                .iter()
+                .map(|&i| table_columns[i].clone())
+                .collect(),
+            None => table_columns.clone(),
+        };
+
+        // only the rows an index finds for the filter, if there is one
+        let lookup = filter.and_then(|filter| choose_index(table, Some(alias), filter));
+        if let Some(lookup) = &lookup {
+            stats.borrow_mut().method = Some(format!("index lookup using {}", lookup.index.name));
+        }
+
+        Ok(Scan {
+            database,
+            table,
+            filter,
+            table_columns,
+            columns,
+            projection,
+            lookup,
+            positions: None,
+            selected: Vec::new().into_iter(),
+        })
+    }
+}
+
+impl Operator for Scan<'_> {
+    fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        let table = self.table;
+
+        if self.positions.is_none() {
+            self.positions = Some(match &self.lookup {
+                Some(lookup) => Box::new(lookup.positions().into_iter()),
+                None => Box::new(0..table.rows.len()),
+            });
+        }
+
+        let (filter, database, columns) = (self.filter, self.database, &self.table_columns);
+        let positions = self.positions.as_mut().unwrap();
+        let row = match filter {
+            Some(filter) if database.vectorized => loop {
+                if let Some(i) = self.selected.next() {
+                    break &table.rows[i];
+                }
+
+                let batch: Vec<_> = positions.take(BATCH_SIZE).collect();
+                if batch.is_empty() {
+                    return Ok(None);
+                }
+
+                let rows: Vec<_> = batch.iter().map(|&i| &table.rows[i]).collect();
+                let keep = database.filter_batch(filter, columns, &rows)?;
+                self.selected = batch
+                    .into_iter()
+                    .zip(keep)
+                    .filter_map(|(i, keep)| keep.then_some(i))
+                    .collect::<Vec<_>>()
+                    .into_iter();
+            },
+            _ => loop {
+                let row = match positions.next() {
+                    Some(i) => &table.rows[i],
+                    None => return Ok(None),
+                };
+
+                let keep = match filter {
+                    Some(filter) => evaluate(filter, Some((columns, row)), Some(database))?,
+                    None => break row,
+                };
+                if keep.is_true()? {
+                    break row;
+                }
+            },
+        };
+
+        Ok(Some(match &self.projection {
+            Some(projection) => Row(projection.iter().map(|&i| row.0[i].clone()).collect()),
+            None => row.clone(),
+        }))
+    }
+}
+
+struct Filter<'a> {
+    input: BoxedOperator<'a>,
+    predicate: &'a Expression<'a>,
+    database: &'a Database,
+}
+
+impl Operator for Filter<'_> {
+    fn columns(&self) -> &[Column] {
+        self.input.columns()
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        while let Some(row) = self.input.next()? {
+            let columns = self.input.columns();
+            if evaluate(self.predicate, Some((columns, &row)), Some(self.database))?.is_true()? {
+                return Ok(Some(row));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// For each row, the values of a list of expressions, expanding `*` into the columns it matches.
+struct Project<'a> {
+    input: BoxedOperator<'a>,
+    expressions: &'a [Expression<'a>],
+    columns: Vec<Column>,
+    database: &'a Database,
+}
+
+impl Operator for Project<'_> {
+    fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        let row = match self.input.next()? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let columns = self.input.columns();
+        let mut new_row = Vec::with_capacity(self.columns.len());
+
+        for expr in self.expressions {
+            match expr {
+                Expression::ColumnIdentifier(
+                    i @ ast::ColumnIdentifier {
+                        name: ast::Column::Star,
+                        ..
+                    },
+                ) => new_row.append(&mut star_values(i, columns, &row)),
+                _ => new_row.push(evaluate(expr, Some((columns, &row)), Some(self.database))?),
+            }
+        }
+
+        Ok(Some(Row(new_row)))
+    }
+}
+
+/// Every row, in order of an expression. Rows which are equal in it stay in the order they came
+/// in.
+struct Sort<'a> {
+    input: BoxedOperator<'a>,
+    expr: &'a Expression<'a>,
+    direction: OrderByDirection,
+    database: &'a Database,
+    /// The sorted rows, once every row has been pulled.
+    rows: Option<vec::IntoIter<Row>>,
+}
+
+impl Operator for Sort<'_> {
+    fn columns(&self) -> &[Column] {
+        self.input.columns()
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        if self.rows.is_none() {
+            let rows = drain(&mut self.input)?;
+            let columns = self.input.columns();
+            let mut keyed = rows
+                .into_iter()
+                .map(|row| {
+                    let key = evaluate(self.expr, Some((columns, &row)), Some(self.database))?;
+                    Ok((key, row))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            match self.direction {
+                OrderByDirection::Asc => keyed.sort_by(|(a, _), (b, _)| a.cmp(b)),
+                OrderByDirection::Desc => keyed.sort_by(|(a, _), (b, _)| b.cmp(a)),
+            }
+
+            let rows: Vec<_> = keyed.into_iter().map(|(_, row)| row).collect();
+            self.rows = Some(rows.into_iter());
+        }
+
+        Ok(self.rows.as_mut().unwrap().next())
+    }
+}
+
+/// The first rows, up to a count. No more rows are pulled once it's reached.
+struct Limit<'a> {
+    input: BoxedOperator<'a>,
+    remaining: usize,
+}
+
+impl Operator for Limit<'_> {
+    fn columns(&self) -> &[Column] {
+        self.input.columns()
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+        self.input.next()
+    }
+}
+
+/// A row for each group of rows, see [`Database::aggregate_rows`]. Rows are aggregated a batch
+/// at a time when the database is vectorized, see [`BatchAggregation`].
+struct Aggregated<'a> {
+    input: BoxedOperator<'a>,
+    group_by: &'a [Expression<'a>],
+    aggregates: &'a [Aggregate<'a>],
+    columns: Vec<Column>,
+    database: &'a Database,
+    /// The aggregated rows, once every row has been pulled.
+    rows: Option<vec::IntoIter<Row>>,
+}
+
+impl Operator for Aggregated<'_> {
+    fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        if self.rows.is_none() {
+            let rows = if self.database.vectorized {
+                let columns = self.input.columns().to_vec();
+                let mut aggregation =
+                    BatchAggregation::new(&columns, self.group_by, self.aggregates);
+                loop {
+                    let mut batch = Vec::with_capacity(BATCH_SIZE);
+                    while batch.len() < BATCH_SIZE {
+                        match self.input.next()? {
+                            Some(row) => batch.push(row),
+                            None => break,
+                        }
+                    }
+                    if batch.is_empty() {
+                        break;
+                    }
+
+                    aggregation.add(&batch, self.database)?;
+                }
+
+                aggregation.finish()?
+            } else {
+                let rows = drain(&mut self.input)?;
+                self.database.aggregate_rows(
+                    self.input.columns(),
+                    rows,
+                    self.group_by,
+                    self.aggregates,
+                )?
+            };
+
+            self.rows = Some(rows.into_iter());
+        }
+
+        Ok(self.rows.as_mut().unwrap().next())
+    }
+}
+
+/// Another operator, counting the rows it produces and the time it takes.
+struct Measured<'a> {
+    input: BoxedOperator<'a>,
+    stats: SharedStats,
+}
+
+impl Operator for Measured<'_> {
+    fn columns(&self) -> &[Column] {
+        self.input.columns()
+    }
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        let start = Instant::now();
+        let row = self.input.next();
+
+        let mut stats = self.stats.borrow_mut();
+        stats.time += start.elapsed();
+        if matches!(row, Ok(Some(_))) {
+            stats.rows += 1;
+        }
+
+        row
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use super::Error;
+use crate::data::{Database, Timestamp, Uuid};
+use crate::parse::ast::{AlterTableAction, Query};
+use crate::parse::parse_queries;
+use crate::storage::{self, Wal};
+
+impl Database {
+    /// The tables whose rows a query may change other than by adding rows to the end, so they
+    /// have to be written out again in full. `None` if the query changes nothing at all.
+    pub(super) fn rewritten_tables(&self, query: &Query<'_>) -> Option<Vec<String>> {
+        Some(match query {
+            Query::SelectQuery(_) | Query::ExplainQuery(_) | Query::TransactionQuery(_) => {
+                return None
+            }
+            Query::UpdateQuery(query) => vec![query.table.0.to_owned()],
+            // deleting rows may delete or change the rows referencing them, and so on
+            Query::DeleteQuery(query) => {
+                let mut tables = BTreeSet::new();
+                let mut pending = vec![query.table.0.to_owned()];
+                while let Some(table) = pending.pop() {
+                    if tables.insert(table.clone()) {
+                        pending.extend(self.references_to(&table).map(|(t, _, _)| t.to_owned()));
+                    }
+                }
+
+                tables.into_iter().collect()
+            }
+            Query::AlterTableQuery(query) => match &query.action {
+                AlterTableAction::RenameTable(name) => vec![name.0.to_owned()],
+                _ => vec![query.table_name.0.to_owned()],
+            },
+            Query::InsertQuery(_)
+            | Query::CreateTableQuery(_)
+            | Query::CreateIndexQuery(_)
+            | Query::DropTableQuery(_) => Vec::new(),
+        })
+    }
+
+    /// Record the queries changing the database in the write-ahead log, if there is one, before
+    /// their changes are stored, along with the time they began and the seed of the uuids they
+    /// generate, so that executing them again gives the same current time and uuids.
+    pub(super) fn log(
+        &mut self,
+        statements: &str,
+        time: Timestamp,
+        seed: u64,
+    ) -> Result<(), storage::Error> {
+        if let Some(wal) = &mut self.writer.as_mut().unwrap().wal {
+            wal.append_at(statements, time, seed)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the changes made to the tables to the database's file, if it's stored in one. Once
+    /// they're stored, the write-ahead log no longer needs the statements which made them.
+    ///
+    /// If they can't be stored, the statements just logged are removed from the log again, so
+    /// that they aren't executed when it's replayed either.
+    pub(super) fn save(&mut self, rewritten: &[String]) -> Result<(), storage::Error> {
+        let writer = self.writer.as_mut().unwrap();
+        let storage = match &mut writer.storage {
+            Some(storage) => storage,
+            None => return Ok(()),
+        };
+
+        if let Some(wal) = &writer.wal {
+            storage.set_applied(wal.last());
+        }
+        if let Err(error) = storage.save(&self.tables, rewritten) {
+            if let Some(wal) = &mut writer.wal {
+                let _ = wal.remove_last();
+            }
+            return Err(error);
+        }
+
+        // the file records the last statement it holds the changes of, so if the log can't be
+        // emptied, the statements left in it are passed over when it's replayed
+        if let Some(wal) = &mut writer.wal {
+            let _ = wal.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Record every statement changing the database from now on in a write-ahead log, opening
+    /// (or creating) it at `path`. The statements already in the log, which the database doesn't
+    /// hold the changes of, are executed again first.
+    ///
+    /// Returns the number of records executed again, each a single statement or the statements of
+    /// a committed transaction. Those which failed the first time fail again, and are passed over.
+    /// Each record's statements are executed as if at the time they first began, and generate the
+    /// uuids they first did, if it was logged with a time and seed.
+    pub fn open_wal(&mut self, path: &Path) -> Result<usize, Error> {
+        self.lock_writer();
+        let result = self.replay_wal(path);
+        self.tables.clear();
+        self.release_writer();
+
+        result
+    }
+
+    fn replay_wal(&mut self, path: &Path) -> Result<usize, Error> {
+        let storage = &self.writer.as_ref().unwrap().storage;
+        let applied = storage.as_ref().map_or(0, |storage| storage.applied());
+        let (wal, logged) = Wal::open(path, applied)?;
+
+        let mut records = Vec::new();
+        for record in &logged {
+            match parse_queries(&record.statements) {
+                Ok(queries) => records.push((record.time, record.seed, queries)),
+                Err(_) => {
+                    let reason = format!("statement {} of the log doesn't parse", record.sequence);
+                    return Err(storage::Error::Corrupt(reason).into());
+                }
+            }
+        }
+
+        let rewritten = self.with_latest(|database| {
+            let mut rewritten = BTreeSet::new();
+            for (time, seed, queries) in records {
+                Timestamp::set_statement_time(time.unwrap_or_else(Timestamp::now));
+                let seed = seed.unwrap_or_else(Uuid::new_seed);
+                for (index, query) in queries.0.into_iter().enumerate() {
+                    Uuid::set_statement_seed(seed, index);
+                    rewritten.extend(database.rewritten_tables(&query).unwrap_or_default());
+                    let _ = database.execute_query(query);
+                }
+            }
+
+            rewritten
+        });
+
+        self.writer.as_mut().unwrap().wal = Some(wal);
+        if !logged.is_empty() {
+            self.save(&rewritten.into_iter().collect::<Vec<_>>())?;
+        }
+
+        Ok(logged.len())
+    }
+}
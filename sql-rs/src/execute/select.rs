@@ -1,144 +1,193 @@
-use std::cmp::Reverse;
-use std::fmt::{Display, Formatter};
-
-use super::{evaluate, evaluate_column};
-use crate::data::{Database, Row, Table, Value};
-use crate::parse::ast::{self, Expression, OrderByDirection, SelectQuery};
+use super::evaluate::column_indices;
+use super::operator::collect;
+use super::star_columns;
+use crate::data::{Column, Database, Datatype, Row, Table, Value, ValueError};
+use crate::parse::ast::{ColumnIdentifier, Expression, SelectQuery};
+use crate::plan::{optimize, LogicalPlan};
 
 pub type Success = Table;
 
-#[derive(Debug)]
-pub struct Error;
-
-impl Display for Error {
-    fn fmt(&self, _f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        todo!()
-    }
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Table with that name does not exist")]
+    TableNotFound,
+    #[error("aggregate functions are not allowed in WHERE")]
+    AggregateInWhere,
+    #[error("aggregate functions are not allowed in GROUP BY")]
+    AggregateInGroupBy,
+    #[error("aggregate functions are not allowed in LIMIT")]
+    AggregateInLimit,
+    #[error("aggregate function calls cannot be nested")]
+    NestedAggregate,
+    #[error("column \"{0}\" must appear in GROUP BY or be used in an aggregate function")]
+    UngroupedColumn(String),
+    #[error("column `{0}` in USING join does not exist in both tables")]
+    JoinColumnNotFound(String),
+    #[error("LIMIT must not be negative")]
+    NegativeLimit,
+    #[error("LIMIT must be an integer, not {0}")]
+    NonIntegerLimit(Datatype),
+    #[error(transparent)]
+    Value(#[from] ValueError),
 }
 
 type QueryResult = Result<Success, Error>;
 
 impl Database {
-    fn queried_tables(&self, query: &SelectQuery<'_>) -> Table {
-        // start with the root table
-        let mut result = self.find_table(query.table.root_table.name.0).clone();
-        result.prefix_column_names(&format!("{}.", query.table.root_table.as_str()));
-
-        // add all joined tables
-        for join in &query.table.joins {
-            let mut table = self.find_table(join.table.name.0).clone();
-            table.prefix_column_names(&format!("{}.", join.table.as_str()));
-
-            result.join(
-                table,
-                |evaluation_context| {
-                    evaluate(&join.condition, Some(evaluation_context), None).is_true()
-                },
-                join.kind,
-            );
-        }
+    pub fn execute_select(&self, query: &SelectQuery<'_>) -> QueryResult {
+        let plan = self.plan_select(query)?;
+        let operator = self.build_operator(&plan, false, &mut Vec::new())?;
 
-        result
+        collect(operator)
     }
 
-    fn apply_query_transformations(&self, query: &SelectQuery<'_>, result: &mut Table) {
-        if let Some(filter) = &query.filter {
-            result.filter(|evaluation_context| {
-                evaluate(filter, Some(evaluation_context), Some(self)).is_true()
-            });
-        }
+    /// The optimized plan to execute a select query with.
+    pub(super) fn plan_select<'input>(
+        &self,
+        query: &SelectQuery<'input>,
+    ) -> Result<LogicalPlan<'input>, Error> {
+        validate_aggregates(query)?;
 
-        if let Some(sort) = &query.sort {
-            match sort.direction {
-                OrderByDirection::Asc => result.sort(|evaluation_context| {
-                    evaluate(&sort.expr, Some(evaluation_context), None)
-                }),
-                OrderByDirection::Desc => result.sort(|evaluation_context| {
-                    Reverse(evaluate(&sort.expr, Some(evaluation_context), None))
-                }),
+        let plan = LogicalPlan::new(query);
+        validate_plan(&plan, self)?;
+        self.validate_grouping(query, &plan)?;
+
+        Ok(optimize(plan, self))
+    }
+
+    /// Check that the select list, HAVING and ORDER BY of an aggregate query only use a column
+    /// outside of an aggregate function call if the query groups by it, since a group's other
+    /// columns can take any of its rows' values.
+    fn validate_grouping(
+        &self,
+        query: &SelectQuery<'_>,
+        plan: &LogicalPlan<'_>,
+    ) -> Result<(), Error> {
+        let (input, group_by) = match plan {
+            LogicalPlan::Aggregate {
+                input, group_by, ..
+            } => (input, group_by),
+            _ => {
+                return plan
+                    .children()
+                    .into_iter()
+                    .try_for_each(|child| self.validate_grouping(query, child))
             }
-        }
+        };
+
+        let input = self.build_operator(input, false, &mut Vec::new())?;
+        let sort = query.sort.iter().map(|sort| &sort.expr);
+        query
+            .select_list
+            .iter()
+            .chain(&query.having)
+            .chain(sort)
+            .try_for_each(|expr| check_grouped(expr, group_by, input.columns()))
+    }
+}
 
-        if let Some(rows) = &query.limit {
-            result.limit(evaluate(rows, None, None).as_number() as usize);
-        }
+/// Check that `expr` only uses the columns of `group_by` outside of aggregate function calls.
+fn check_grouped(
+    expr: &Expression<'_>,
+    group_by: &[Expression<'_>],
+    columns: &[Column],
+) -> Result<(), Error> {
+    if group_by.iter().any(|g| g.to_string() == expr.to_string()) {
+        return Ok(());
     }
 
-    pub fn execute_select(&self, query: &SelectQuery<'_>) -> QueryResult {
-        let mut result = self.queried_tables(&query);
-        self.apply_query_transformations(&query, &mut result);
+    match expr {
+        Expression::ColumnIdentifier(i) => {
+            for idx in column_indices(i, columns)? {
+                let grouped = group_by.iter().any(|g| match g {
+                    Expression::ColumnIdentifier(g) => {
+                        matches!(column_indices(g, columns).as_deref(), Ok([g]) if *g == idx)
+                    }
+                    _ => false,
+                });
+                if !grouped {
+                    return Err(Error::UngroupedColumn(columns[idx].name.clone()));
+                }
+            }
 
-        Ok(apply_selection(&query, &result))
+            Ok(())
+        }
+        Expression::Function(f) => f
+            .args
+            .iter()
+            .try_for_each(|arg| check_grouped(arg, group_by, columns)),
+        Expression::BinaryOp(b) => {
+            check_grouped(&b.left, group_by, columns)?;
+            check_grouped(&b.right, group_by, columns)
+        }
+        _ => Ok(()),
     }
 }
 
-fn apply_selection(query: &SelectQuery<'_>, result: &Table) -> Table {
-    // generate the columns of the new table
-    let new_columns: Vec<_> = query
-        .select_list
+/// Check that the tables a plan scans exist, and that it only uses subqueries as lists of values.
+fn validate_plan(plan: &LogicalPlan<'_>, database: &Database) -> Result<(), Error> {
+    if plan
+        .scans()
         .iter()
-        .flat_map(|expr| evaluate_column(expr, &result.columns))
-        .collect();
+        .any(|(_, table)| !database.tables.contains_key(*table))
+    {
+        return Err(Error::TableNotFound);
+    }
 
-    // generate the rows of the new table
-    let mut new_rows = Vec::new();
+    if plan
+        .expressions()
+        .into_iter()
+        .any(Expression::contains_scalar_subquery)
+    {
+        return Err(ValueError::ScalarSubquery.into());
+    }
 
-    if matches!(&query.select_list[0], Expression::CountStar) {
-        let mut new_row = Vec::new();
+    plan.children()
+        .into_iter()
+        .try_for_each(|child| validate_plan(child, database))
+}
 
-        for expr in &query.select_list {
-            match expr {
-                Expression::CountStar => {
-                    new_row.push(Value::Number(result.rows.len() as u32));
-                }
-                _ => unreachable!(),
-            }
-        }
+fn validate_aggregates(query: &SelectQuery<'_>) -> Result<(), Error> {
+    if matches!(&query.filter, Some(filter) if filter.contains_aggregate()) {
+        return Err(Error::AggregateInWhere);
+    }
 
-        new_rows.push(Row(new_row));
-    } else {
-        for row in &result.rows {
-            // TODO at this point, we know how many values there are going to be. can
-            // pre-allocate space
-            let mut new_row = Vec::new();
-
-            for expr in &query.select_list {
-                match expr {
-                    Expression::ColumnIdentifier(
-                        i
-                        @
-                        ast::ColumnIdentifier {
-                            name: ast::Column::Star,
-                            ..
-                        },
-                    ) => {
-                        let mut values = (&result.columns)
-                            .iter()
-                            .enumerate()
-                            .filter(|(_, c)| match &i.alias {
-                                None => true,
-                                Some(alias) => {
-                                    if c.name.contains(".") {
-                                        alias.0 == c.name.rsplit_once(".").unwrap().0
-                                    } else {
-                                        alias.0 == c.name
-                                    }
-                                }
-                            })
-                            .map(|(idx, _)| row.0[idx].clone())
-                            .collect();
-                        new_row.append(&mut values);
-                    }
-                    _ => new_row.push(evaluate(expr, Some((&result.columns, row)), None)),
-                }
-            }
+    if query.group_by.iter().any(Expression::contains_aggregate) {
+        return Err(Error::AggregateInGroupBy);
+    }
 
-            new_rows.push(Row(new_row));
-        }
+    if matches!(&query.limit, Some(limit) if limit.contains_aggregate()) {
+        return Err(Error::AggregateInLimit);
+    }
+
+    let sort = query.sort.iter().map(|sort| &sort.expr);
+    if query
+        .select_list
+        .iter()
+        .chain(&query.having)
+        .chain(sort)
+        .any(contains_nested_aggregate)
+    {
+        return Err(Error::NestedAggregate);
     }
 
-    Table {
-        columns: new_columns,
-        rows: new_rows,
+    Ok(())
+}
+
+fn contains_nested_aggregate(expr: &Expression<'_>) -> bool {
+    match expr {
+        Expression::Aggregate(a) => matches!(&a.arg, Some(arg) if arg.contains_aggregate()),
+        Expression::Function(f) => f.args.iter().any(contains_nested_aggregate),
+        Expression::BinaryOp(b) => {
+            contains_nested_aggregate(&b.left) || contains_nested_aggregate(&b.right)
+        }
+        _ => false,
     }
 }
+
+/// The values of the columns matched by `alias.*` (or `*`), in table order.
+pub(super) fn star_values(i: &ColumnIdentifier<'_>, columns: &[Column], row: &Row) -> Vec<Value> {
+    star_columns(i, columns)
+        .map(|idx| row.0[idx].clone())
+        .collect()
+}
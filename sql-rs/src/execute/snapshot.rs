@@ -0,0 +1,78 @@
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use super::{Error, Success};
+use crate::data::{Database, Timestamp, Uuid};
+use crate::parse::ast::Query;
+
+impl Database {
+    /// Execute a query reading the database against a snapshot of the latest committed version
+    /// of each table, which writers leave as it is for as long as the query reads it.
+    pub(super) fn execute_read(&mut self, query: Query<'_>) -> Result<Success, Error> {
+        self.tables = self.shared.snapshot();
+        let result = self.execute_query(query);
+        self.tables.clear();
+
+        result
+    }
+
+    /// Execute a query changing the database as a transaction of its own, once this handle holds
+    /// the right to write. It's recorded in the write-ahead log, executed against the latest
+    /// version of every table, whose changed versions are then committed, and its changes are
+    /// written to the database's file.
+    pub(super) fn execute_write(&mut self, query: Query<'_>) -> Result<Success, Error> {
+        self.lock_writer();
+        let result = self.execute_latest(query);
+        self.tables.clear();
+        self.release_writer();
+
+        result
+    }
+
+    /// Execute a query changing the database, committing its changes only once they're stored,
+    /// so that one which fails to be stored leaves the database as it was.
+    fn execute_latest(&mut self, query: Query<'_>) -> Result<Success, Error> {
+        self.log(
+            &format!("{};", query),
+            Timestamp::statement_time(),
+            Uuid::statement_seed(),
+        )?;
+
+        // the query is executed against this handle's own snapshot, copying each table it changes,
+        // so that other handles can take snapshots in the meantime. No other handle can commit
+        // while this one holds the right to write, so the snapshot is still the latest version of
+        // every table once the query is done, and a query which fails leaves it uncommitted.
+        self.tables = self.shared.snapshot();
+        let rewritten = self.rewritten_tables(&query).unwrap_or_default();
+        let success = self.execute_query(query)?;
+        self.save(&rewritten)?;
+
+        let replaced = mem::replace(&mut *self.shared.committed(), self.tables.clone());
+        // the versions replaced may be freed, which takes a while for a big table, so only once
+        // snapshots can be taken again
+        drop(replaced);
+
+        Ok(success)
+    }
+
+    /// Change the latest version of every table, committing the changed versions once `change`
+    /// returns (or panics), and leaving the latest versions in `tables`. This handle must hold the
+    /// right to write.
+    ///
+    /// A table no snapshot holds is changed where it is, rather than copied first. Taking a
+    /// snapshot waits until the changes are committed, so this is only for quick changes.
+    pub(super) fn with_latest<T>(&mut self, change: impl FnOnce(&mut Database) -> T) -> T {
+        let shared = Arc::clone(&self.shared);
+        let mut committed = shared.committed();
+        self.tables = mem::take(&mut *committed);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| change(self)));
+        *committed = self.tables.clone();
+
+        result.unwrap_or_else(|panic| {
+            drop(committed);
+            panic::resume_unwind(panic)
+        })
+    }
+}
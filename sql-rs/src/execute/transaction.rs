@@ -0,0 +1,279 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::mem;
+use std::sync::Arc;
+
+use crate::data::{Database, Timestamp, Transaction, Uuid};
+use crate::parse::ast::{AlterTableAction, Query, TransactionQuery};
+use crate::storage;
+
+#[derive(Debug)]
+pub enum Success {
+    Begin,
+    Commit,
+    Rollback,
+    Savepoint,
+    Release,
+}
+
+impl Display for Success {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Success::Begin => write!(f, "BEGIN"),
+            Success::Commit => write!(f, "COMMIT"),
+            Success::Rollback => write!(f, "ROLLBACK"),
+            Success::Savepoint => write!(f, "SAVEPOINT"),
+            Success::Release => write!(f, "RELEASE"),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("a transaction is already in progress")]
+    AlreadyInTransaction,
+    #[error("there is no transaction in progress")]
+    NoTransaction,
+    #[error("could not serialize access: table `{0}` was changed since the transaction began")]
+    Conflict(String),
+    #[error("savepoint `{0}` does not exist")]
+    SavepointNotFound(String),
+    #[error("a query in the transaction failed, so queries are ignored until it's rolled back (or rolled back to a savepoint)")]
+    Failed,
+    #[error(transparent)]
+    Storage(#[from] storage::Error),
+}
+
+type QueryResult = Result<Success, Error>;
+
+impl Database {
+    pub fn execute_transaction(&mut self, query: TransactionQuery<'_>) -> QueryResult {
+        match query {
+            TransactionQuery::Begin => self.begin(),
+            TransactionQuery::Commit => self.commit(),
+            TransactionQuery::Rollback => self.rollback(),
+            TransactionQuery::Savepoint(name) => self.savepoint(name.0),
+            TransactionQuery::RollbackToSavepoint(name) => self.rollback_to_savepoint(name.0),
+            TransactionQuery::ReleaseSavepoint(name) => self.release_savepoint(name.0),
+        }
+    }
+
+    /// Begin a transaction, so that the changes made from now on are only kept once it commits.
+    /// Its queries read a snapshot of the database taken now, along with their own changes.
+    pub fn begin(&mut self) -> QueryResult {
+        if self.transaction.is_some() {
+            return Err(Error::AlreadyInTransaction);
+        }
+        self.tables = self.shared.snapshot();
+        self.transaction = Some(Transaction {
+            started: Timestamp::statement_time(),
+            seed: Uuid::new_seed(),
+            ..Transaction::default()
+        });
+
+        Ok(Success::Begin)
+    }
+
+    /// Keep the changes made in the transaction, recording them in the write-ahead log as one
+    /// record, then writing them to the database's file and replacing the committed versions of
+    /// the tables it changed. A transaction in which a query failed, or whose changes can't be
+    /// stored, is rolled back instead.
+    pub fn commit(&mut self) -> QueryResult {
+        let transaction = self.transaction.take().ok_or(Error::NoTransaction)?;
+        let result = match transaction.failed {
+            true => Ok(Success::Rollback),
+            false => self.publish(transaction).map(|_| Success::Commit),
+        };
+
+        self.tables.clear();
+        self.release_writer();
+
+        result
+    }
+
+    /// Undo every change made in the transaction.
+    pub fn rollback(&mut self) -> QueryResult {
+        self.transaction.take().ok_or(Error::NoTransaction)?;
+        self.tables.clear();
+        self.release_writer();
+
+        Ok(Success::Rollback)
+    }
+
+    fn publish(&mut self, transaction: Transaction) -> Result<(), Error> {
+        if transaction.statements.is_empty() {
+            return Ok(());
+        }
+        self.log(
+            &transaction.statements.join(" "),
+            transaction.started,
+            transaction.seed,
+        )?;
+
+        let mut tables = self.shared.snapshot();
+        for name in transaction.changed() {
+            match self.tables.get(name) {
+                Some(table) => tables.insert(name.clone(), Arc::clone(table)),
+                None => tables.remove(name),
+            };
+        }
+        // tables changed by others before the transaction first wrote are stored as they are now,
+        // and no one else can commit while it holds the right to write
+        self.tables = tables;
+        self.save(&transaction.rewritten.into_iter().collect::<Vec<_>>())?;
+
+        let replaced = mem::replace(&mut *self.shared.committed(), self.tables.clone());
+        drop(replaced);
+
+        Ok(())
+    }
+
+    /// Mark a point in the transaction which it can be rolled back to. A savepoint may have the
+    /// same name as an earlier one, which it hides until it's released.
+    pub fn savepoint(&mut self, name: &str) -> QueryResult {
+        let transaction = self.transaction.as_mut().ok_or(Error::NoTransaction)?;
+        if transaction.failed {
+            return Err(Error::Failed);
+        }
+        transaction.savepoint(name);
+
+        Ok(Success::Savepoint)
+    }
+
+    /// Undo the changes made since a savepoint, keeping the savepoint but forgetting any after
+    /// it. A transaction in which a query failed after the savepoint can be used again.
+    pub fn rollback_to_savepoint(&mut self, name: &str) -> QueryResult {
+        let transaction = self.transaction.as_mut().ok_or(Error::NoTransaction)?;
+        if !transaction.rollback_to(name, &mut self.tables) {
+            return Err(Error::SavepointNotFound(name.to_owned()));
+        }
+
+        Ok(Success::Rollback)
+    }
+
+    /// Forget a savepoint, and any after it, keeping the changes made since.
+    pub fn release_savepoint(&mut self, name: &str) -> QueryResult {
+        let transaction = self.transaction.as_mut().ok_or(Error::NoTransaction)?;
+        if transaction.failed {
+            return Err(Error::Failed);
+        }
+        if !transaction.release(name) {
+            return Err(Error::SavepointNotFound(name.to_owned()));
+        }
+
+        Ok(Success::Release)
+    }
+
+    /// Execute a query within the transaction. Its changes are neither seen by other handles,
+    /// logged, nor written to the database's file until the transaction commits, and if it fails,
+    /// the transaction can only be rolled back.
+    ///
+    /// Before its first change, the transaction waits for the right to write, which it keeps
+    /// until it ends. A query which would change a table (or check a foreign key against one)
+    /// committed since the transaction's snapshot was taken fails instead, rather than lose
+    /// those changes.
+    pub(super) fn execute_in_transaction(
+        &mut self,
+        query: Query<'_>,
+    ) -> Result<super::Success, super::Error> {
+        if self.transaction.as_ref().unwrap().failed {
+            return Err(Error::Failed.into());
+        }
+
+        let changes = self.rewritten_tables(&query).map(|mut rewritten| {
+            let changed = self.changed_tables(&query);
+            // the table a row is inserted into may have been dropped, or emptied and filled
+            // again, earlier in the transaction, so only inserts alone leave it to be appended to
+            if !matches!(query, Query::InsertQuery(_)) {
+                rewritten.extend(changed.iter().cloned());
+            }
+
+            (format!("{};", query), rewritten, changed)
+        });
+
+        if let Some((_, _, changed)) = &changes {
+            // a query changing the database generates uuids from the transaction's seed, the same
+            // as it does when the transaction is executed again from the write-ahead log
+            let transaction = self.transaction.as_ref().unwrap();
+            Uuid::set_statement_seed(transaction.seed, transaction.statements.len());
+
+            self.lock_writer();
+            if let Some(table) = self.conflicting_table(changed) {
+                self.transaction.as_mut().unwrap().failed = true;
+                return Err(Error::Conflict(table).into());
+            }
+
+            let transaction = self.transaction.as_mut().unwrap();
+            transaction.keep(&self.tables, changed);
+        }
+
+        let result = self.execute_query(query);
+
+        let transaction = self.transaction.as_mut().unwrap();
+        match (&result, changes) {
+            (Ok(_), Some((statement, rewritten, _))) => {
+                transaction.statements.push(statement);
+                transaction.rewritten.extend(rewritten);
+            }
+            (Ok(_), None) => {}
+            (Err(_), _) => transaction.failed = true,
+        }
+
+        result
+    }
+
+    /// The first table a query changing `changed` depends on whose committed version isn't the
+    /// one the transaction read, if there is one. Tables the transaction already changed were
+    /// checked the first time, and no one else can commit while it holds the right to write.
+    fn conflicting_table(&self, changed: &[String]) -> Option<String> {
+        let transaction = self.transaction.as_ref().unwrap();
+        let mut tables: BTreeSet<_> = changed.iter().cloned().collect();
+        for name in changed {
+            tables.extend(self.referencing_tables(name));
+            if let Some(table) = self.tables.get(name) {
+                tables.extend(
+                    table
+                        .constraints
+                        .iter()
+                        .filter_map(|c| c.referenced_table())
+                        .map(str::to_owned),
+                );
+            }
+        }
+
+        let committed = self.shared.committed();
+        tables
+            .into_iter()
+            .filter(|name| !transaction.changed().any(|changed| changed == name))
+            .find(|name| match (self.tables.get(name), committed.get(name)) {
+                (Some(read), Some(latest)) => !Arc::ptr_eq(read, latest),
+                (read, latest) => read.is_some() != latest.is_some(),
+            })
+    }
+
+    /// The tables a query may change, which the transaction has to keep as they were before it.
+    fn changed_tables(&self, query: &Query<'_>) -> Vec<String> {
+        let mut tables = self.rewritten_tables(query).unwrap_or_default();
+        let table = match query {
+            Query::InsertQuery(query) => query.table.0,
+            Query::CreateTableQuery(query) => query.table_name.0,
+            Query::CreateIndexQuery(query) => query.table_name.0,
+            Query::DropTableQuery(query) => query.table_name.0,
+            Query::AlterTableQuery(query) => query.table_name.0,
+            _ => return tables,
+        };
+        tables.push(table.to_owned());
+
+        // renaming a table, or a column, renames it in the foreign keys referencing it too
+        if let Query::AlterTableQuery(query) = query {
+            if matches!(
+                query.action,
+                AlterTableAction::RenameTable(_) | AlterTableAction::RenameColumn { .. }
+            ) {
+                tables.extend(self.referencing_tables(table));
+            }
+        }
+
+        tables
+    }
+}
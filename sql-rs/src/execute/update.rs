@@ -1,7 +1,10 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
 
-use crate::data::Database;
-use crate::execute::evaluate;
+use super::index::index_lookup;
+use crate::data::{ConstraintViolation, Database, Value, ValueError};
+use crate::execute::{check_columns, evaluate};
 use crate::parse::ast::UpdateQuery;
 
 #[derive(Debug)]
@@ -15,38 +18,71 @@ impl Display for Success {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("Table with that name does not exist")]
+    TableNotFound,
+    #[error("columns must exist in the table")]
+    InvalidColumns,
+    #[error("value is not compatible with the column's datatype")]
     IncompatibleDatatype,
-}
-
-impl Display for Error {
-    fn fmt(&self, _f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        todo!()
-    }
+    #[error(transparent)]
+    ConstraintViolation(#[from] ConstraintViolation),
+    #[error(transparent)]
+    Value(#[from] ValueError),
 }
 
 type QueryResult = Result<Success, Error>;
 
 impl Database {
     pub fn execute_update(&mut self, query: UpdateQuery<'_>) -> QueryResult {
-        let table = self.find_table_mut(query.table.0);
+        let table = self.tables.get(query.table.0).ok_or(Error::TableNotFound)?;
+        if query.filter.contains_scalar_subquery() {
+            return Err(ValueError::ScalarSubquery.into());
+        }
+        check_columns(&query.filter, &table.columns)?;
         let assignment_names: Vec<_> = query.assignments.iter().map(|a| a.column.0).collect();
-        let indices = table.get_update_indices(assignment_names.as_slice());
-        let rows_to_update = table.filter_mut(|evaluation_context| {
-            evaluate(&query.filter, Some(evaluation_context), None).is_true()
-        });
-        let num_updated = rows_to_update.len() as u32;
-
-        for row in rows_to_update {
-            let values = query.assignments.iter().map(|assignment| &assignment.value);
-            for (&idx, value) in indices.iter().zip(values) {
-                // TODO fix partial updates possible here after introducing error conditions
-                // TODO fix possible to assign to incompatible data type
-                row.0[idx] = value.into();
+        let indices = table
+            .get_update_indices(assignment_names.as_slice())
+            .ok_or(Error::InvalidColumns)?;
+        let mut values = Vec::with_capacity(indices.len());
+        for (&idx, assignment) in indices.iter().zip(&query.assignments) {
+            let value = Value::try_from(&assignment.value)?;
+            if !table.compatible_type(idx, &value) {
+                return Err(Error::IncompatibleDatatype);
             }
+
+            values.push(value.cast(table.columns[idx].datatype)?);
         }
 
+        // build the updated rows on the side, so that the table is left untouched if they
+        // violate a constraint
+        let mut changed = BTreeMap::new();
+        let positions = index_lookup(table, None, &query.filter)
+            .unwrap_or_else(|| (0..table.rows.len()).collect());
+        for i in positions {
+            let row = &table.rows[i];
+            if evaluate(&query.filter, Some((&table.columns, row)), Some(self))?.is_true()? {
+                let mut row = row.clone();
+                for (&idx, value) in indices.iter().zip(&values) {
+                    row.0[idx] = value.clone();
+                }
+                changed.insert(i, row);
+            }
+        }
+
+        table.check_update(&changed)?;
+        self.check_references(
+            query.table.0,
+            table,
+            changed.values(),
+            table.updated_rows(&changed),
+        )?;
+        self.check_referenced_keys(query.table.0, table, &changed)?;
+
+        let num_updated = changed.len() as u32;
+        self.find_table_mut(query.table.0).update_rows(changed);
+
         Ok(Success { num_updated })
     }
 }
@@ -0,0 +1,517 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+use super::aggregate::{summable, undefined_function, Total};
+use super::evaluate;
+use crate::data::{
+    Batch, Bitmap, Column, Database, Datatype, Row, Table, Value, ValueError, Values, Vector,
+};
+use crate::parse::ast::{self, Aggregate, AggregateFunction, Expression, ExpressionOp, Literal};
+
+/// Evaluate an expression for every row of a batch at once.
+///
+/// Only column references, constants, and the comparison, arithmetic and logical operators are
+/// evaluated this way, and only over operands of the datatypes they expect. For anything else
+/// (or if evaluating could fail, such as overflowing), this is `None`, and the expression has to
+/// be evaluated row by row instead.
+pub fn evaluate_vector<'a>(
+    expr: &'a Expression<'a>,
+    columns: &[Column],
+    batch: &Batch<'a>,
+) -> Option<Vector<'a>> {
+    match expr {
+        Expression::ColumnIdentifier(i) => {
+            let idx = Table::find_column_idx(columns, i)?;
+
+            batch.vectors[idx].clone()
+        }
+        Expression::Constant(v) => Vector::repeat(v, batch.len),
+        Expression::Literal(l) => {
+            let values = match l {
+                Literal::String(s) => Values::Text(vec![*s; batch.len]),
+                Literal::Number(_) => match Value::try_from(l).ok()? {
+                    Value::Integer(n) => Values::Integer(vec![n; batch.len]),
+                    Value::Real(x) => Values::Real(vec![x; batch.len]),
+                    _ => return None,
+                },
+                Literal::Boolean(b) => Values::Boolean(vec![b.parse().ok()?; batch.len]),
+                Literal::Typed(..) | Literal::Blob(_) => return None,
+            };
+
+            Some(Vector {
+                values,
+                nulls: Bitmap::new(batch.len),
+            })
+        }
+        Expression::BinaryOp(b) => {
+            let left = evaluate_vector(&b.left, columns, batch)?;
+            let right = evaluate_vector(&b.right, columns, batch)?;
+
+            binary_op(b.op, left, right)
+        }
+        Expression::Aggregate(_) | Expression::Function(_) | Expression::Subquery(_) => None,
+    }
+}
+
+fn binary_op<'a>(op: ExpressionOp, left: Vector<'a>, right: Vector<'a>) -> Option<Vector<'a>> {
+    // comparisons and arithmetic with an unknown value are unknown
+    let nulls = left.nulls.union(&right.nulls);
+
+    macro_rules! zip {
+        ($l:expr, $r:expr, |$a:ident, $b:ident| $f:expr) => {
+            $l.iter().zip($r.iter()).map(|(&$a, &$b)| $f).collect()
+        };
+    }
+
+    let values = match (op, &left.values, &right.values) {
+        (ExpressionOp::And | ExpressionOp::Or, Values::Boolean(l), Values::Boolean(r)) => {
+            return Some(logical(op, l, r, &left.nulls, &right.nulls));
+        }
+        (ExpressionOp::Equal, Values::Integer(l), Values::Integer(r)) => {
+            Values::Boolean(zip!(l, r, |a, b| a == b))
+        }
+        (ExpressionOp::Equal, Values::Real(l), Values::Real(r)) => {
+            Values::Boolean(zip!(l, r, |a, b| a == b))
+        }
+        (ExpressionOp::Equal, Values::Text(l), Values::Text(r)) => {
+            Values::Boolean(zip!(l, r, |a, b| a == b))
+        }
+        (ExpressionOp::Equal, Values::Boolean(l), Values::Boolean(r)) => {
+            Values::Boolean(zip!(l, r, |a, b| a == b))
+        }
+        // numbers of different datatypes are promoted first, which is left to be done row by row
+        (_, Values::Integer(_), Values::Real(_)) | (_, Values::Real(_), Values::Integer(_)) => {
+            return None
+        }
+        // values of different datatypes are never equal
+        (ExpressionOp::Equal, _, _) => Values::Boolean(vec![false; left.len()]),
+        (ExpressionOp::Plus | ExpressionOp::Minus, Values::Integer(l), Values::Integer(r)) => {
+            // a null's placeholder can't overflow, so only the other values can fail
+            let values: Vec<_> = l
+                .iter()
+                .zip(r)
+                .enumerate()
+                .map(|(i, (&a, &b))| match (nulls.get(i), op) {
+                    (true, _) => Some(0),
+                    (false, ExpressionOp::Plus) => a.checked_add(b),
+                    (false, _) => a.checked_sub(b),
+                })
+                .collect::<Option<_>>()?;
+            Values::Integer(values)
+        }
+        (ExpressionOp::Plus | ExpressionOp::Minus, Values::Real(l), Values::Real(r)) => {
+            let values: Vec<f64> = match op {
+                ExpressionOp::Plus => zip!(l, r, |a, b| a + b),
+                _ => zip!(l, r, |a, b| a - b),
+            };
+            if !values.iter().all(|x| x.is_finite()) {
+                return None;
+            }
+            Values::Real(values)
+        }
+        (_, Values::Integer(l), Values::Integer(r)) => Values::Boolean(compare(op, l, r)?),
+        (_, Values::Real(l), Values::Real(r)) => Values::Boolean(compare(op, l, r)?),
+        (_, Values::Text(l), Values::Text(r)) => Values::Boolean(compare(op, l, r)?),
+        _ => return None,
+    };
+
+    Some(Vector { values, nulls })
+}
+
+fn compare<T: PartialOrd>(op: ExpressionOp, l: &[T], r: &[T]) -> Option<Vec<bool>> {
+    let compare: fn(&T, &T) -> bool = match op {
+        ExpressionOp::GreaterEqual => |a, b| a >= b,
+        ExpressionOp::LessEqual => |a, b| a <= b,
+        ExpressionOp::Greater => |a, b| a > b,
+        ExpressionOp::Less => |a, b| a < b,
+        _ => return None,
+    };
+
+    Some(l.iter().zip(r).map(|(a, b)| compare(a, b)).collect())
+}
+
+/// Three-valued logic: false and unknown is false, true or unknown is true.
+fn logical<'a>(
+    op: ExpressionOp,
+    l: &[bool],
+    r: &[bool],
+    l_nulls: &Bitmap,
+    r_nulls: &Bitmap,
+) -> Vector<'a> {
+    let mut nulls = Bitmap::new(l.len());
+    let values = (0..l.len())
+        .map(|i| {
+            let l = (!l_nulls.get(i)).then_some(l[i]);
+            let r = (!r_nulls.get(i)).then_some(r[i]);
+            let result = match op {
+                ExpressionOp::And => match (l, r) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                },
+                _ => match (l, r) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                },
+            };
+
+            result.unwrap_or_else(|| {
+                nulls.set(i);
+                false
+            })
+        })
+        .collect();
+
+    Vector {
+        values: Values::Boolean(values),
+        nulls,
+    }
+}
+
+/// The indices of the columns an expression references, which a batch needs to evaluate it.
+fn referenced_columns(exprs: &[&Expression<'_>], columns: &[Column]) -> Vec<usize> {
+    exprs
+        .iter()
+        .flat_map(|expr| expr.column_identifiers())
+        .filter_map(|i| match i.name {
+            ast::Column::Star => None,
+            _ => Table::find_column_idx(columns, i),
+        })
+        .collect()
+}
+
+impl Database {
+    /// Whether each of `rows` satisfies a filter, evaluated over them as a batch where it can be
+    /// (and row by row where it can't).
+    pub fn filter_batch(
+        &self,
+        filter: &Expression<'_>,
+        columns: &[Column],
+        rows: &[&Row],
+    ) -> Result<Vec<bool>, ValueError> {
+        let needed = referenced_columns(&[filter], columns);
+        let vector = Batch::new(columns, rows, &needed)
+            .and_then(|batch| evaluate_vector(filter, columns, &batch));
+
+        match vector {
+            Some(Vector {
+                values: Values::Boolean(values),
+                nulls,
+            }) => Ok(values
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| v && !nulls.get(i))
+                .collect()),
+            _ => rows
+                .iter()
+                .map(|row| evaluate(filter, Some((columns, row)), Some(self))?.is_true())
+                .collect(),
+        }
+    }
+}
+
+/// Aggregates rows a batch at a time, the same as [`Database::aggregate_rows`] does all at once.
+pub struct BatchAggregation<'a> {
+    columns: &'a [Column],
+    group_by: &'a [Expression<'a>],
+    aggregates: &'a [Aggregate<'a>],
+    /// The first row of each group, and its aggregates' running totals, in the order the groups
+    /// first appear.
+    groups: Vec<(Row, Vec<Accumulator>)>,
+    group_indices: BTreeMap<Vec<Value>, usize>,
+}
+
+impl<'a> BatchAggregation<'a> {
+    pub fn new(
+        columns: &'a [Column],
+        group_by: &'a [Expression<'a>],
+        aggregates: &'a [Aggregate<'a>],
+    ) -> Self {
+        BatchAggregation {
+            columns,
+            group_by,
+            aggregates,
+            groups: Vec::new(),
+            group_indices: BTreeMap::new(),
+        }
+    }
+
+    /// Add a batch of rows to their groups.
+    pub fn add(&mut self, rows: &[Row], database: &Database) -> Result<(), ValueError> {
+        let rows: Vec<_> = rows.iter().collect();
+        let args: Vec<_> = self
+            .aggregates
+            .iter()
+            .filter_map(|a| a.arg.as_deref())
+            .chain(self.group_by)
+            .collect();
+        let needed = referenced_columns(&args, self.columns);
+        let batch = Batch::new(self.columns, &rows, &needed);
+
+        let columns = self.columns;
+
+        // which group each row belongs to
+        let keys: Vec<Vec<Value>> = self
+            .group_by
+            .iter()
+            .map(|expr| evaluate_all(expr, columns, &rows, batch.as_ref(), database))
+            .collect::<Result<_, _>>()?;
+        let mut group_of_row = Vec::with_capacity(rows.len());
+        for (i, row) in rows.iter().enumerate() {
+            let key: Vec<_> = keys.iter().map(|k| k[i].clone()).collect();
+            let group = match self.group_indices.get(&key) {
+                Some(&group) => group,
+                None => {
+                    self.group_indices.insert(key, self.groups.len());
+                    self.groups.push(((*row).clone(), self.new_accumulators()));
+                    self.groups.len() - 1
+                }
+            };
+            group_of_row.push(group);
+        }
+
+        for (a, aggregate) in self.aggregates.iter().enumerate() {
+            let arg = match &aggregate.arg {
+                // count(*) counts rows, nulls and all
+                None => {
+                    for &group in &group_of_row {
+                        self.groups[group].1[a].count += 1;
+                    }
+                    continue;
+                }
+                Some(arg) => arg,
+            };
+
+            let vector = batch
+                .as_ref()
+                .and_then(|batch| evaluate_vector(arg, columns, batch));
+            match (&vector, self.group_by.is_empty()) {
+                // without groups, the whole vector goes into one total
+                (Some(vector), true) if !aggregate.distinct => {
+                    self.groups[0].1[a].add_vector(vector)?;
+                }
+                _ => {
+                    let values = match vector {
+                        Some(vector) => (0..rows.len()).map(|i| vector.get(i)).collect(),
+                        None => evaluate_all(arg, columns, &rows, None, database)?,
+                    };
+                    for (value, &group) in values.into_iter().zip(&group_of_row) {
+                        self.groups[group].1[a].add(value)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A row for each group: its first row (or nulls, with no groups and no rows), followed by
+    /// the value of each aggregate.
+    pub fn finish(mut self) -> Result<Vec<Row>, ValueError> {
+        if self.groups.is_empty() && self.group_by.is_empty() {
+            let nulls = Row(vec![Value::Null; self.columns.len()]);
+            self.groups.push((nulls, self.new_accumulators()));
+        }
+
+        let aggregates = self.aggregates;
+        self.groups
+            .into_iter()
+            .map(|(mut row, accumulators)| {
+                for (aggregate, accumulator) in aggregates.iter().zip(accumulators) {
+                    row.0.push(accumulator.finish(aggregate.function)?);
+                }
+
+                Ok(row)
+            })
+            .collect()
+    }
+
+    fn new_accumulators(&self) -> Vec<Accumulator> {
+        self.aggregates
+            .iter()
+            .map(|a| Accumulator::new(a.distinct))
+            .collect()
+    }
+}
+
+/// The value of an expression for each of `rows`, evaluated over their batch if possible, else
+/// row by row.
+fn evaluate_all<'a>(
+    expr: &'a Expression<'a>,
+    columns: &[Column],
+    rows: &[&Row],
+    batch: Option<&Batch<'a>>,
+    database: &Database,
+) -> Result<Vec<Value>, ValueError> {
+    match batch.and_then(|batch| evaluate_vector(expr, columns, batch)) {
+        Some(vector) => Ok((0..rows.len()).map(|i| vector.get(i)).collect()),
+        None => rows
+            .iter()
+            .map(|row| evaluate(expr, Some((columns, row)), Some(database)))
+            .collect(),
+    }
+}
+
+/// The running total of an aggregate over a group. Only non-null values are added (except for
+/// `count(*)`, which counts rows).
+#[derive(Debug)]
+struct Accumulator {
+    count: i64,
+    total: Total,
+    min: Option<Value>,
+    max: Option<Value>,
+    /// The datatype of a value which can't be summed, if one was added, failing a sum or average.
+    unsummable: Option<Datatype>,
+    /// For a DISTINCT aggregate, every different value, totalled up at the end instead.
+    distinct: Option<BTreeSet<Value>>,
+}
+
+impl Accumulator {
+    fn new(distinct: bool) -> Self {
+        Accumulator {
+            count: 0,
+            total: Total::default(),
+            min: None,
+            max: None,
+            unsummable: None,
+            distinct: distinct.then(BTreeSet::new),
+        }
+    }
+
+    fn add(&mut self, value: Value) -> Result<(), ValueError> {
+        if matches!(value, Value::Null) {
+            return Ok(());
+        }
+
+        if let Some(distinct) = &mut self.distinct {
+            distinct.insert(value);
+            return Ok(());
+        }
+
+        self.count += 1;
+        match summable(&value) {
+            true => self.total.add(value.clone())?,
+            false => self.unsummable = Some(value.datatype()),
+        }
+        if self.min.as_ref().is_none_or(|min| value < *min) {
+            self.min = Some(value.clone());
+        }
+        if self.max.as_ref().is_none_or(|max| value > *max) {
+            self.max = Some(value);
+        }
+
+        Ok(())
+    }
+
+    /// Add every non-null value of a vector, working on the typed values directly.
+    fn add_vector(&mut self, vector: &Vector<'_>) -> Result<(), ValueError> {
+        let present = |i: &usize| !vector.nulls.get(*i);
+
+        match &vector.values {
+            Values::Integer(values) => {
+                let mut present = (0..values.len()).filter(present).map(|i| values[i]);
+                let first = match present.next() {
+                    Some(first) => first,
+                    None => return Ok(()),
+                };
+
+                let (mut count, mut sum, mut min, mut max) = (1, first as i128, first, first);
+                for n in present {
+                    count += 1;
+                    sum += n as i128;
+                    min = min.min(n);
+                    max = max.max(n);
+                }
+
+                self.count += count;
+                self.total.add_integers(sum);
+                self.add_extremes(Value::Integer(min), Value::Integer(max));
+            }
+            Values::Real(values) => {
+                let mut present = (0..values.len()).filter(present).map(|i| values[i]);
+                let first = match present.next() {
+                    Some(first) => first,
+                    None => return Ok(()),
+                };
+
+                // each value is added onto the running total in turn, so the total is rounded the
+                // same as adding row by row
+                let mut sum = match self.total.others {
+                    Some(Value::Real(sum)) => sum + first,
+                    _ => first,
+                };
+                let (mut count, mut min, mut max) = (1, first, first);
+                for x in present {
+                    count += 1;
+                    sum += x;
+                    min = if x < min { x } else { min };
+                    max = if x > max { x } else { max };
+                }
+                if !sum.is_finite() {
+                    return Err(ValueError::OutOfRange(Datatype::Real));
+                }
+
+                self.count += count;
+                self.total.others = Some(Value::Real(sum));
+                self.add_extremes(Value::Real(min), Value::Real(max));
+            }
+            Values::Text(values) => {
+                let mut present = (0..values.len()).filter(present).map(|i| values[i]);
+                let first = match present.next() {
+                    Some(first) => first,
+                    None => return Ok(()),
+                };
+
+                let (mut count, mut min, mut max) = (1, first, first);
+                for s in present {
+                    count += 1;
+                    min = min.min(s);
+                    max = max.max(s);
+                }
+
+                self.count += count;
+                self.unsummable = Some(Datatype::Text);
+                self.add_extremes(Value::Text(min.to_owned()), Value::Text(max.to_owned()));
+            }
+            Values::Boolean(_) => {
+                for i in (0..vector.len()).filter(present) {
+                    self.add(vector.get(i))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_extremes(&mut self, min: Value, max: Value) {
+        if self.min.as_ref().is_none_or(|m| min < *m) {
+            self.min = Some(min);
+        }
+        if self.max.as_ref().is_none_or(|m| max > *m) {
+            self.max = Some(max);
+        }
+    }
+
+    fn finish(mut self, function: AggregateFunction) -> Result<Value, ValueError> {
+        if let Some(distinct) = self.distinct.take() {
+            for value in distinct {
+                self.add(value)?;
+            }
+        }
+
+        if let (AggregateFunction::Sum | AggregateFunction::Avg, Some(datatype)) =
+            (function, self.unsummable)
+        {
+            return Err(undefined_function(function, datatype));
+        }
+
+        Ok(match function {
+            AggregateFunction::Count => Value::Integer(self.count),
+            AggregateFunction::Min => self.min.unwrap_or(Value::Null),
+            AggregateFunction::Max => self.max.unwrap_or(Value::Null),
+            AggregateFunction::Sum => self.total.sum()?.unwrap_or(Value::Null),
+            AggregateFunction::Avg => self.total.average(self.count)?.unwrap_or(Value::Null),
+        })
+    }
+}
@@ -11,3 +11,9 @@ pub mod execute;
 
 /// Parsing SQL.
 pub mod parse;
+
+/// Planning how to execute a query.
+pub mod plan;
+
+/// Storing a database in a file.
+pub mod storage;
@@ -9,13 +9,33 @@ use ansi_term::Colour;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
-use sql_rs::data::Database;
+use sql_rs::data::{Database, Uuid};
+use sql_rs::execute::Error;
 use sql_rs::parse::parse_queries;
 
 #[derive(Debug, StructOpt)]
 struct Opt {
     #[structopt(short, parse(from_os_str))]
     seed_file: Option<PathBuf>,
+
+    /// The file the database is stored in, created if it doesn't exist. Without one, the
+    /// database is lost on exit.
+    #[structopt(long, parse(from_os_str))]
+    db: Option<PathBuf>,
+
+    /// A write-ahead log to record every change to the database in, created if it doesn't exist.
+    /// The statements already in it are executed again first, to rebuild the database.
+    #[structopt(long, parse(from_os_str))]
+    wal: Option<PathBuf>,
+
+    /// Evaluate filters and aggregates over batches of column vectors, rather than row by row.
+    #[structopt(long)]
+    vectorized: bool,
+
+    /// Seed the generator of uuids, so that `gen_random_uuid()` generates the same uuids each
+    /// time the same statements are executed.
+    #[structopt(long)]
+    uuid_seed: Option<u64>,
 }
 
 pub fn seed(database: &mut Database, seed_file: PathBuf) {
@@ -23,9 +43,20 @@ pub fn seed(database: &mut Database, seed_file: PathBuf) {
     let queries = parse_queries(&seed);
 
     match queries {
+        // the seed is executed in a transaction, so a failing query leaves the database as it was
         Ok(queries) => {
-            for query in queries.0 {
-                database.execute(query).unwrap();
+            let result = database.begin().map_err(Error::from).and_then(|_| {
+                for query in queries.0 {
+                    database.execute(query)?;
+                }
+                database.commit()?;
+                Ok(())
+            });
+
+            if let Err(e) = result {
+                let _ = database.rollback();
+                println!("{}", e);
+                process::exit(1);
             }
         }
         Err(parse_error) => {
@@ -74,8 +105,24 @@ pub fn console(database: &mut Database) {
 
 fn main() {
     let opt = Opt::from_args();
+    if let Some(seed) = opt.uuid_seed {
+        Uuid::seed(seed);
+    }
 
-    let mut db = Database::new();
+    let mut db = match opt.db {
+        Some(path) => Database::open(&path).unwrap_or_else(|e| {
+            println!("{}", e);
+            process::exit(1);
+        }),
+        None => Database::new(),
+    };
+    if let Some(path) = opt.wal {
+        if let Err(e) = db.open_wal(&path) {
+            println!("{}", e);
+            process::exit(1);
+        }
+    }
+    db.vectorized = opt.vectorized;
     if let Some(seed_file) = opt.seed_file {
         seed(&mut db, seed_file);
     }
@@ -0,0 +1,96 @@
+use std::fmt::{Display, Formatter};
+
+use super::Expression;
+
+/// An aggregate function call, such as `count(*)` or `sum(distinct a.amount)`.
+///
+/// Aggregates are evaluated over every row of a group rather than over a single row. `arg` is
+/// `None` only for `count(*)`, which counts rows regardless of their values.
+#[derive(Debug, Clone)]
+pub struct Aggregate<'input> {
+    pub function: AggregateFunction,
+    pub distinct: bool,
+    pub arg: Option<Box<Expression<'input>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+impl Display for Aggregate<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match &self.arg {
+            None => write!(f, "{}(*)", self.function),
+            Some(arg) if self.distinct => write!(f, "{}(distinct {})", self.function, arg),
+            Some(arg) => write!(f, "{}({})", self.function, arg),
+        }
+    }
+}
+
+impl Display for AggregateFunction {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let name = match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Avg => "avg",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for Aggregate<'input> {
+    fn from(aggregate: Pair<'input, Rule>) -> Self {
+        assert_eq!(aggregate.as_rule(), Rule::aggregate);
+
+        let mut inner = aggregate.into_inner();
+        let first = inner.next().unwrap();
+        match first.as_rule() {
+            Rule::count_star => Aggregate {
+                function: AggregateFunction::Count,
+                distinct: false,
+                arg: None,
+            },
+            _ => {
+                let function = first.into();
+                let mut next = inner.next().unwrap();
+                let distinct = next.as_rule() == Rule::distinct;
+                if distinct {
+                    next = inner.next().unwrap();
+                }
+                let arg = Some(Box::new(next.into()));
+
+                Aggregate {
+                    function,
+                    distinct,
+                    arg,
+                }
+            }
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for AggregateFunction {
+    fn from(aggregate_function: Pair<'_, Rule>) -> Self {
+        assert_eq!(aggregate_function.as_rule(), Rule::aggregate_function);
+
+        match aggregate_function.as_str() {
+            "count" => AggregateFunction::Count,
+            "sum" => AggregateFunction::Sum,
+            "min" => AggregateFunction::Min,
+            "max" => AggregateFunction::Max,
+            "avg" => AggregateFunction::Avg,
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::{Display, Formatter};
+
+use super::{CreateTableColumn, Identifier};
+
+/// A query changing the definition of an existing table.
+///
+/// Each query performs a single action. Actions changing the columns of the table also rewrite
+/// every existing row to match.
+#[derive(Debug)]
+pub struct AlterTableQuery<'input> {
+    pub table_name: Identifier<'input>,
+    pub action: AlterTableAction<'input>,
+}
+
+#[derive(Debug)]
+pub enum AlterTableAction<'input> {
+    /// Add a column, filling it for existing rows with its default (or null).
+    AddColumn(CreateTableColumn<'input>),
+    DropColumn(Identifier<'input>),
+    RenameColumn {
+        from: Identifier<'input>,
+        to: Identifier<'input>,
+    },
+    RenameTable(Identifier<'input>),
+}
+
+impl Display for AlterTableQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "alter table {} ", self.table_name)?;
+
+        match &self.action {
+            AlterTableAction::AddColumn(column) => write!(f, "add column {}", column),
+            AlterTableAction::DropColumn(column) => write!(f, "drop column {}", column),
+            AlterTableAction::RenameColumn { from, to } => {
+                write!(f, "rename column {} to {}", from, to)
+            }
+            AlterTableAction::RenameTable(name) => write!(f, "rename to {}", name),
+        }
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for AlterTableQuery<'input> {
+    fn from(alter_table_query: Pair<'input, Rule>) -> Self {
+        assert_eq!(alter_table_query.as_rule(), Rule::alter_table_query);
+
+        let mut inner = alter_table_query.into_inner();
+        let table_name = inner.next().unwrap().into();
+        let action = inner.next().unwrap().into();
+
+        AlterTableQuery { table_name, action }
+    }
+}
+
+impl<'input> From<Pair<'input, Rule>> for AlterTableAction<'input> {
+    fn from(action: Pair<'input, Rule>) -> Self {
+        let rule = action.as_rule();
+        let mut inner = action.into_inner();
+
+        match rule {
+            Rule::add_column => AlterTableAction::AddColumn(inner.next().unwrap().into()),
+            Rule::drop_column => AlterTableAction::DropColumn(inner.next().unwrap().into()),
+            Rule::rename_column => {
+                let from = inner.next().unwrap().into();
+                let to = inner.next().unwrap().into();
+
+                AlterTableAction::RenameColumn { from, to }
+            }
+            Rule::rename_table => AlterTableAction::RenameTable(inner.next().unwrap().into()),
+            _ => unreachable!(),
+        }
+    }
+}
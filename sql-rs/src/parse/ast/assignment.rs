@@ -1,3 +1,5 @@
+use std::fmt::{Display, Formatter};
+
 use super::{Identifier, Listable, Literal};
 
 /// TODO short description.
@@ -9,6 +11,12 @@ pub struct Assignment<'input> {
     pub value: Literal<'input>,
 }
 
+impl Display for Assignment<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{} = {}", self.column, self.value)
+    }
+}
+
 use crate::parse::Rule;
 use pest::iterators::Pair;
 
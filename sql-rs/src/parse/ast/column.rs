@@ -1,7 +1,7 @@
 use super::Identifier;
 
 /// Identifies a column (or set of columns) once a table is specified.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Column<'input> {
     Star,
     Ident(Identifier<'input>),
@@ -4,7 +4,7 @@ use super::{Column, Identifier};
 ///
 /// Either the column name alone is sufficient (if the column name is globally unique) or the
 /// column is specified with `alias.column`.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ColumnIdentifier<'input> {
     pub alias: Option<Identifier<'input>>,
     pub name: Column<'input>,
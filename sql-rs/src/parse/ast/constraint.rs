@@ -0,0 +1,116 @@
+use std::fmt::{Display, Formatter};
+
+use super::{comma_separated, Expression, Identifier, List, References};
+
+/// A constraint written as part of a column definition, applying to that column alone.
+///
+/// A column's default value isn't a constraint as such, but is declared in the same position.
+#[derive(Debug)]
+pub enum ColumnConstraint<'input> {
+    PrimaryKey,
+    Unique,
+    NotNull,
+    Default(Expression<'input>),
+    Check(Expression<'input>),
+    References(References<'input>),
+}
+
+/// A constraint written as its own element of a `create table` column list, which may span
+/// several columns.
+#[derive(Debug)]
+pub enum TableConstraint<'input> {
+    PrimaryKey(Vec<Identifier<'input>>),
+    Unique(Vec<Identifier<'input>>),
+    Check(Expression<'input>),
+    ForeignKey {
+        columns: Vec<Identifier<'input>>,
+        references: References<'input>,
+    },
+}
+
+impl Display for ColumnConstraint<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ColumnConstraint::PrimaryKey => write!(f, "primary key"),
+            ColumnConstraint::Unique => write!(f, "unique"),
+            ColumnConstraint::NotNull => write!(f, "not null"),
+            ColumnConstraint::Default(expression) => write!(f, "default {}", expression),
+            ColumnConstraint::Check(expression) => write!(f, "check ({})", expression),
+            ColumnConstraint::References(references) => write!(f, "{}", references),
+        }
+    }
+}
+
+impl Display for TableConstraint<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            TableConstraint::PrimaryKey(columns) => {
+                write!(f, "primary key ({})", comma_separated(columns))
+            }
+            TableConstraint::Unique(columns) => write!(f, "unique ({})", comma_separated(columns)),
+            TableConstraint::Check(expression) => write!(f, "check ({})", expression),
+            TableConstraint::ForeignKey {
+                columns,
+                references,
+            } => write!(
+                f,
+                "foreign key ({}) {}",
+                comma_separated(columns),
+                references
+            ),
+        }
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for ColumnConstraint<'input> {
+    fn from(column_constraint: Pair<'input, Rule>) -> Self {
+        assert_eq!(column_constraint.as_rule(), Rule::column_constraint);
+
+        let constraint = column_constraint.into_inner().next().unwrap();
+        match constraint.as_rule() {
+            Rule::primary_key => ColumnConstraint::PrimaryKey,
+            Rule::unique => ColumnConstraint::Unique,
+            Rule::not_null => ColumnConstraint::NotNull,
+            Rule::column_default => {
+                ColumnConstraint::Default(constraint.into_inner().next().unwrap().into())
+            }
+            Rule::check => ColumnConstraint::Check(constraint.into_inner().next().unwrap().into()),
+            Rule::references => ColumnConstraint::References(constraint.into()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'input> From<Pair<'input, Rule>> for TableConstraint<'input> {
+    fn from(table_constraint: Pair<'input, Rule>) -> Self {
+        assert_eq!(table_constraint.as_rule(), Rule::table_constraint);
+
+        let mut inner = table_constraint.into_inner();
+        let kind = inner.next().unwrap();
+        match kind.as_rule() {
+            Rule::check => return TableConstraint::Check(kind.into_inner().next().unwrap().into()),
+            Rule::foreign_key => {
+                let mut inner = kind.into_inner();
+                let columns: List<Identifier<'input>> = inner.next().unwrap().into();
+                let references = inner.next().unwrap().into();
+
+                return TableConstraint::ForeignKey {
+                    columns: columns.0,
+                    references,
+                };
+            }
+            _ => {}
+        }
+
+        let columns: List<Identifier<'input>> = inner.next().unwrap().into();
+
+        match kind.as_rule() {
+            Rule::primary_key => TableConstraint::PrimaryKey(columns.0),
+            Rule::unique => TableConstraint::Unique(columns.0),
+            _ => unreachable!(),
+        }
+    }
+}
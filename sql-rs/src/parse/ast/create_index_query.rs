@@ -0,0 +1,71 @@
+use std::fmt::{Display, Formatter};
+
+use super::{comma_separated, Identifier, List};
+use crate::data::IndexMethod;
+
+/// A query adding an index over some columns of a table.
+///
+/// Without `using`, the index is a btree.
+#[derive(Debug)]
+pub struct CreateIndexQuery<'input> {
+    pub index_name: Identifier<'input>,
+    pub table_name: Identifier<'input>,
+    pub columns: Vec<Identifier<'input>>,
+    pub method: IndexMethod,
+}
+
+impl Display for CreateIndexQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let method = match self.method {
+            IndexMethod::BTree => "btree",
+            IndexMethod::Hash => "hash",
+        };
+
+        write!(
+            f,
+            "create index {} on {} using {} ({})",
+            self.index_name,
+            self.table_name,
+            method,
+            comma_separated(&self.columns)
+        )
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for CreateIndexQuery<'input> {
+    fn from(create_index_query: Pair<'input, Rule>) -> Self {
+        assert_eq!(create_index_query.as_rule(), Rule::create_index_query);
+
+        let mut inner = create_index_query.into_inner();
+        let index_name = inner.next().unwrap().into();
+        let table_name = inner.next().unwrap().into();
+        let mut next = inner.next().unwrap();
+        let mut method = IndexMethod::default();
+        if next.as_rule() == Rule::index_method {
+            method = next.into();
+            next = inner.next().unwrap();
+        }
+        let columns: List<Identifier<'input>> = next.into();
+
+        CreateIndexQuery {
+            index_name,
+            table_name,
+            columns: columns.0,
+            method,
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for IndexMethod {
+    fn from(index_method: Pair<'_, Rule>) -> Self {
+        assert_eq!(index_method.as_rule(), Rule::index_method);
+
+        match index_method.as_str() {
+            "hash" => IndexMethod::Hash,
+            _ => IndexMethod::BTree,
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use super::{Identifier, Listable};
+use std::fmt::{Display, Formatter};
+
+use super::{ColumnConstraint, Identifier};
 use crate::data::Datatype;
 
 /// TODO quick description.
@@ -8,6 +10,18 @@ use crate::data::Datatype;
 pub struct CreateTableColumn<'input> {
     pub name: Identifier<'input>,
     pub datatype: Datatype,
+    pub constraints: Vec<ColumnConstraint<'input>>,
+}
+
+impl Display for CreateTableColumn<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{} {}", self.name, self.datatype)?;
+        for constraint in &self.constraints {
+            write!(f, " {}", constraint)?;
+        }
+
+        Ok(())
+    }
 }
 
 use crate::parse::Rule;
@@ -20,13 +34,12 @@ impl<'input> From<Pair<'input, Rule>> for CreateTableColumn<'input> {
         let mut inner = create_table_column.into_inner();
         let name = inner.next().unwrap().into();
         let datatype = inner.next().unwrap().into();
+        let constraints = inner.map(From::from).collect();
 
-        CreateTableColumn { name, datatype }
-    }
-}
-
-impl<'input> Listable for CreateTableColumn<'input> {
-    fn get_rule() -> Rule {
-        Rule::create_table_column_list
+        CreateTableColumn {
+            name,
+            datatype,
+            constraints,
+        }
     }
 }
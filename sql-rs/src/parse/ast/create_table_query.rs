@@ -1,4 +1,6 @@
-use super::{CreateTableColumn, Identifier, List};
+use std::fmt::{Display, Formatter};
+
+use super::{comma_separated, CreateTableColumn, Identifier, TableConstraint};
 
 /// TODO quick description.
 ///
@@ -7,6 +9,23 @@ use super::{CreateTableColumn, Identifier, List};
 pub struct CreateTableQuery<'input> {
     pub table_name: Identifier<'input>,
     pub columns: Vec<CreateTableColumn<'input>>,
+    pub constraints: Vec<TableConstraint<'input>>,
+}
+
+impl Display for CreateTableQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "create table {} ({}",
+            self.table_name,
+            comma_separated(&self.columns)
+        )?;
+        for constraint in &self.constraints {
+            write!(f, ", {}", constraint)?;
+        }
+
+        write!(f, ")")
+    }
 }
 
 use crate::parse::Rule;
@@ -18,11 +37,22 @@ impl<'input> From<Pair<'input, Rule>> for CreateTableQuery<'input> {
 
         let mut inner = create_table_query.into_inner();
         let table_name = inner.next().unwrap().into();
-        let columns: List<CreateTableColumn<'input>> = inner.next().unwrap().into();
+
+        // columns and table constraints may be interleaved
+        let mut columns = Vec::new();
+        let mut constraints = Vec::new();
+        for element in inner.next().unwrap().into_inner() {
+            match element.as_rule() {
+                Rule::create_table_column => columns.push(element.into()),
+                Rule::table_constraint => constraints.push(element.into()),
+                _ => unreachable!(),
+            }
+        }
 
         CreateTableQuery {
             table_name,
-            columns: columns.0,
+            columns,
+            constraints,
         }
     }
 }
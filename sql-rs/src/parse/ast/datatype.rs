@@ -6,10 +6,33 @@ impl From<Pair<'_, Rule>> for Datatype {
     fn from(datatype: Pair<'_, Rule>) -> Self {
         assert_eq!(datatype.as_rule(), Rule::datatype);
 
-        match datatype.as_str() {
-            "number" => Datatype::Number,
+        let inner = datatype.into_inner().next().unwrap();
+        if inner.as_rule() == Rule::numeric_datatype {
+            // a precision too large to parse is as invalid as any other too large
+            let mut numbers = inner
+                .into_inner()
+                .map(|number| number.as_str().parse().unwrap_or(u32::MAX));
+
+            return match (numbers.next(), numbers.next()) {
+                (Some(precision), scale) => {
+                    Datatype::Numeric(Some((precision, scale.unwrap_or(0))))
+                }
+                (None, _) => Datatype::Numeric(None),
+            };
+        }
+
+        match inner.as_str() {
+            "integer" | "int" | "bigint" | "number" => Datatype::Integer,
+            "real" | "double" => Datatype::Real,
             "text" => Datatype::Text,
             "boolean" => Datatype::Boolean,
+            "blob" | "bytea" => Datatype::Blob,
+            "date" => Datatype::Date,
+            "time" => Datatype::Time,
+            "timestamp" => Datatype::Timestamp,
+            "interval" => Datatype::Interval,
+            "json" => Datatype::Json,
+            "uuid" => Datatype::Uuid,
             _ => unreachable!(),
         }
     }
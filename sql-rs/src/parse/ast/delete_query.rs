@@ -0,0 +1,42 @@
+use std::fmt::{Display, Formatter};
+
+use super::{Expression, Identifier};
+
+/// A query removing rows from a table.
+///
+/// Every row for which `filter` evaluates to true is removed. Without a filter, every row is
+/// removed.
+#[derive(Debug)]
+pub struct DeleteQuery<'input> {
+    pub table: Identifier<'input>,
+    pub filter: Option<Expression<'input>>,
+}
+
+impl Display for DeleteQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "delete from {}", self.table)?;
+        if let Some(filter) = &self.filter {
+            write!(f, " where {}", filter)?;
+        }
+
+        Ok(())
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for DeleteQuery<'input> {
+    fn from(delete_query: Pair<'input, Rule>) -> Self {
+        assert_eq!(delete_query.as_rule(), Rule::delete_query);
+
+        let mut inner = delete_query.into_inner();
+
+        let table = inner.next().unwrap().into();
+        let filter = inner
+            .next()
+            .map(|where_clause| where_clause.into_inner().next().unwrap().into());
+
+        DeleteQuery { table, filter }
+    }
+}
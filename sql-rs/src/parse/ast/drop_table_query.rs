@@ -0,0 +1,44 @@
+use std::fmt::{Display, Formatter};
+
+use super::Identifier;
+
+/// A query removing a table, and all of its rows, from the database.
+///
+/// With `if exists`, dropping a table that doesn't exist isn't an error.
+#[derive(Debug)]
+pub struct DropTableQuery<'input> {
+    pub table_name: Identifier<'input>,
+    pub if_exists: bool,
+}
+
+impl Display for DropTableQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        if self.if_exists {
+            write!(f, "drop table if exists {}", self.table_name)
+        } else {
+            write!(f, "drop table {}", self.table_name)
+        }
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for DropTableQuery<'input> {
+    fn from(drop_table_query: Pair<'input, Rule>) -> Self {
+        assert_eq!(drop_table_query.as_rule(), Rule::drop_table_query);
+
+        let mut inner = drop_table_query.into_inner();
+        let mut next = inner.next().unwrap();
+        let if_exists = next.as_rule() == Rule::if_exists;
+        if if_exists {
+            next = inner.next().unwrap();
+        }
+        let table_name = next.into();
+
+        DropTableQuery {
+            table_name,
+            if_exists,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt::{Display, Formatter};
+
+use super::SelectQuery;
+
+/// A query showing the plan a select query is executed with.
+///
+/// With `analyze`, the select query is executed too, to show what each part of the plan did.
+#[derive(Debug)]
+pub struct ExplainQuery<'input> {
+    pub analyze: bool,
+    pub query: SelectQuery<'input>,
+}
+
+impl Display for ExplainQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        if self.analyze {
+            write!(f, "explain analyze {}", self.query)
+        } else {
+            write!(f, "explain {}", self.query)
+        }
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for ExplainQuery<'input> {
+    fn from(explain_query: Pair<'input, Rule>) -> Self {
+        assert_eq!(explain_query.as_rule(), Rule::explain_query);
+
+        let mut inner = explain_query.into_inner();
+        let mut next = inner.next().unwrap();
+        let analyze = next.as_rule() == Rule::analyze;
+        if analyze {
+            next = inner.next().unwrap();
+        }
+
+        ExplainQuery {
+            analyze,
+            query: next.into(),
+        }
+    }
+}
@@ -1,24 +1,30 @@
+use std::fmt::{Display, Formatter};
+
 use lazy_static::lazy_static;
 
 use pest::prec_climber::{Operator, PrecClimber};
 
-use super::{ColumnIdentifier, Listable, Literal, SelectQuery};
+use super::{Aggregate, Column, ColumnIdentifier, FunctionCall, Listable, Literal, SelectQuery};
+use crate::data::{Hex, Value};
 
 /// An expression that can be evaluated.
 ///
 /// An AST version of expressions for SQL. Parsed from tokens using a PrecClimber. In this form,
 /// the rest of the code can evaluate expressions without requiring knowledge of pest.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Expression<'input> {
-    /// Hack to get the most common aggregate function working.
-    CountStar,
+    Aggregate(Aggregate<'input>),
+    Function(FunctionCall<'input>),
     Literal(Literal<'input>),
     ColumnIdentifier(ColumnIdentifier<'input>),
     BinaryOp(BinaryOp<'input>),
     Subquery(Box<SelectQuery<'input>>),
+    /// A value worked out while planning a query, rather than parsed, such as the result of
+    /// folding a constant expression.
+    Constant(Value),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BinaryOp<'input> {
     pub op: ExpressionOp,
     pub left: Box<Expression<'input>>,
@@ -37,6 +43,10 @@ pub enum ExpressionOp {
     Equal,
     Plus,
     Minus,
+    /// A field of json, as json (`->`).
+    JsonGet,
+    /// A field of json, as text (`->>`).
+    JsonGetText,
 }
 
 lazy_static! {
@@ -52,6 +62,7 @@ lazy_static! {
                 | Operator::new(less, Left)
                 | Operator::new(equal, Left),
             Operator::new(plus, Left) | Operator::new(minus, Left),
+            Operator::new(json_get, Left) | Operator::new(json_get_text, Left),
         ])
     };
 }
@@ -70,7 +81,8 @@ impl<'input> From<Pair<'input, Rule>> for Expression<'input> {
                 Rule::select_query => Expression::Subquery(Box::new(pair.into())),
                 Rule::expression => pair.into(),
                 Rule::literal => Expression::Literal(pair.into()),
-                Rule::count_star => Expression::CountStar,
+                Rule::aggregate => Expression::Aggregate(pair.into()),
+                Rule::function_call => Expression::Function(pair.into()),
                 _ => unreachable!(),
             },
             |left: Expression<'_>, op: Pair<Rule>, right: Expression<'_>| {
@@ -84,6 +96,170 @@ impl<'input> From<Pair<'input, Rule>> for Expression<'input> {
     }
 }
 
+impl<'input> Expression<'input> {
+    /// Whether an aggregate function call appears anywhere in this expression (not counting
+    /// subqueries, which are aggregated on their own).
+    pub fn contains_aggregate(&self) -> bool {
+        match self {
+            Expression::Aggregate(_) => true,
+            Expression::Function(f) => f.args.iter().any(Expression::contains_aggregate),
+            Expression::BinaryOp(b) => b.left.contains_aggregate() || b.right.contains_aggregate(),
+            _ => false,
+        }
+    }
+
+    /// Every aggregate function call in this expression (not counting those inside subqueries).
+    pub fn aggregates(&self) -> Vec<&Aggregate<'input>> {
+        match self {
+            Expression::Aggregate(a) => vec![a],
+            Expression::Function(f) => f.args.iter().flat_map(Expression::aggregates).collect(),
+            Expression::BinaryOp(b) => {
+                let mut aggregates = b.left.aggregates();
+                aggregates.append(&mut b.right.aggregates());
+                aggregates
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn contains_subquery(&self) -> bool {
+        match self {
+            Expression::Subquery(_) => true,
+            Expression::Aggregate(a) => matches!(&a.arg, Some(arg) if arg.contains_subquery()),
+            Expression::Function(f) => f.args.iter().any(Expression::contains_subquery),
+            Expression::BinaryOp(b) => b.left.contains_subquery() || b.right.contains_subquery(),
+            _ => false,
+        }
+    }
+
+    /// Whether this expression uses a subquery as a value of its own, rather than as the list of
+    /// values on the right of IN (the only place one can be used).
+    pub fn contains_scalar_subquery(&self) -> bool {
+        match self {
+            Expression::Subquery(_) => true,
+            Expression::Aggregate(a) => {
+                matches!(&a.arg, Some(arg) if arg.contains_scalar_subquery())
+            }
+            Expression::Function(f) => f.args.iter().any(Expression::contains_scalar_subquery),
+            Expression::BinaryOp(b)
+                if matches!(b.op, ExpressionOp::In)
+                    && matches!(*b.right, Expression::Subquery(_)) =>
+            {
+                b.left.contains_scalar_subquery()
+            }
+            Expression::BinaryOp(b) => {
+                b.left.contains_scalar_subquery() || b.right.contains_scalar_subquery()
+            }
+            _ => false,
+        }
+    }
+
+    /// Every column referenced by this expression (not counting references inside subqueries).
+    pub fn column_identifiers(&self) -> Vec<&ColumnIdentifier<'input>> {
+        match self {
+            Expression::ColumnIdentifier(i) => vec![i],
+            Expression::Aggregate(a) => match &a.arg {
+                Some(arg) => arg.column_identifiers(),
+                None => Vec::new(),
+            },
+            Expression::Function(f) => f
+                .args
+                .iter()
+                .flat_map(Expression::column_identifiers)
+                .collect(),
+            Expression::BinaryOp(b) => {
+                let mut identifiers = b.left.column_identifiers();
+                identifiers.append(&mut b.right.column_identifiers());
+                identifiers
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Rename every unqualified reference to the column `from`.
+    pub fn rename_column(&mut self, from: &str, to: &'input str) {
+        match self {
+            Expression::ColumnIdentifier(ColumnIdentifier {
+                alias: None,
+                name: Column::Ident(name),
+            }) if name.0 == from => name.0 = to,
+            Expression::Aggregate(a) => {
+                if let Some(arg) = &mut a.arg {
+                    arg.rename_column(from, to);
+                }
+            }
+            Expression::Function(f) => {
+                for arg in &mut f.args {
+                    arg.rename_column(from, to);
+                }
+            }
+            Expression::BinaryOp(b) => {
+                b.left.rename_column(from, to);
+                b.right.rename_column(from, to);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Expressions are displayed as SQL which parses back into the same expression. Nested binary
+/// operations are always parenthesized, rather than relying on precedence.
+impl Display for Expression<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Expression::Aggregate(a) => write!(f, "{}", a),
+            Expression::Function(call) => write!(f, "{}", call),
+            Expression::Literal(l) => write!(f, "{}", l),
+            Expression::ColumnIdentifier(i) => write!(f, "{}", i.as_string()),
+            Expression::BinaryOp(b) => {
+                write_operand(f, &b.left)?;
+                write!(f, " {} ", b.op)?;
+                write_operand(f, &b.right)
+            }
+            Expression::Subquery(sq) => write!(f, "({})", sq),
+            Expression::Constant(Value::Text(s)) => write!(f, "'{}'", s),
+            Expression::Constant(
+                v @ (Value::Date(_)
+                | Value::Time(_)
+                | Value::Timestamp(_)
+                | Value::Interval(_)
+                | Value::Json(_)
+                | Value::Uuid(_)),
+            ) => write!(f, "{} '{}'", v.datatype(), v),
+            Expression::Constant(Value::Blob(b)) => write!(f, "x'{}'", Hex(b)),
+            Expression::Constant(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+fn write_operand(f: &mut Formatter, operand: &Expression<'_>) -> Result<(), std::fmt::Error> {
+    match operand {
+        Expression::BinaryOp(_) => write!(f, "({})", operand),
+        _ => write!(f, "{}", operand),
+    }
+}
+
+impl Display for ExpressionOp {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let op = match self {
+            ExpressionOp::GreaterEqual => ">=",
+            ExpressionOp::LessEqual => "<=",
+            ExpressionOp::Greater => ">",
+            ExpressionOp::Less => "<",
+            ExpressionOp::And => "and",
+            ExpressionOp::Or => "or",
+            ExpressionOp::In => "in",
+            ExpressionOp::Equal => "=",
+            ExpressionOp::Plus => "+",
+            ExpressionOp::Minus => "-",
+            ExpressionOp::JsonGet => "->",
+            ExpressionOp::JsonGetText => "->>",
+        };
+
+        write!(f, "{}", op)
+    }
+}
+
 impl From<Pair<'_, Rule>> for ExpressionOp {
     fn from(operation: Pair<'_, Rule>) -> Self {
         use Rule::*;
@@ -100,6 +276,8 @@ impl From<Pair<'_, Rule>> for ExpressionOp {
                 || rule == equal
                 || rule == plus
                 || rule == minus
+                || rule == json_get
+                || rule == json_get_text
         );
 
         match rule {
@@ -113,6 +291,8 @@ impl From<Pair<'_, Rule>> for ExpressionOp {
             equal => ExpressionOp::Equal,
             plus => ExpressionOp::Plus,
             minus => ExpressionOp::Minus,
+            json_get => ExpressionOp::JsonGet,
+            json_get_text => ExpressionOp::JsonGetText,
             _ => unreachable!(),
         }
     }
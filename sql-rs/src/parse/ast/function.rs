@@ -0,0 +1,88 @@
+use std::fmt::{Display, Formatter};
+
+use super::Expression;
+use crate::data::DateField;
+
+/// A call of a function evaluated for each row, such as `now()` or
+/// `extract(year from e.created_at)`. `substring(x from 2 for 3)` is a call with the arguments
+/// `x`, `2` and `3`, the same as `substring(x, 2, 3)`.
+#[derive(Debug, Clone)]
+pub struct FunctionCall<'input> {
+    pub function: Function,
+    pub args: Vec<Expression<'input>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Function {
+    Now,
+    Extract(DateField),
+    DateTrunc,
+    Length,
+    Substring,
+    JsonExtract,
+    GenRandomUuid,
+}
+
+impl Display for FunctionCall<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self.function {
+            Function::Extract(field) => write!(f, "extract({} from {})", field, self.args[0]),
+            function => {
+                let args: Vec<String> = self.args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", function, args.join(", "))
+            }
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let name = match self {
+            Function::Now => "now",
+            Function::Extract(_) => "extract",
+            Function::DateTrunc => "date_trunc",
+            Function::Length => "length",
+            Function::Substring => "substring",
+            Function::JsonExtract => "json_extract",
+            Function::GenRandomUuid => "gen_random_uuid",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for FunctionCall<'input> {
+    fn from(function_call: Pair<'input, Rule>) -> Self {
+        assert_eq!(function_call.as_rule(), Rule::function_call);
+
+        let call = function_call.into_inner().next().unwrap();
+        let function = match call.as_rule() {
+            Rule::now => Function::Now,
+            Rule::extract => Function::Extract(
+                call.clone()
+                    .into_inner()
+                    .next()
+                    .unwrap()
+                    .as_str()
+                    .parse()
+                    .unwrap(),
+            ),
+            Rule::date_trunc => Function::DateTrunc,
+            Rule::length => Function::Length,
+            Rule::substring => Function::Substring,
+            Rule::json_extract => Function::JsonExtract,
+            Rule::gen_random_uuid => Function::GenRandomUuid,
+            _ => unreachable!(),
+        };
+        let args = call
+            .into_inner()
+            .filter(|pair| pair.as_rule() == Rule::expression)
+            .map(Expression::from)
+            .collect();
+
+        FunctionCall { function, args }
+    }
+}
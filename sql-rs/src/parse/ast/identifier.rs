@@ -1,11 +1,19 @@
+use std::fmt::{Display, Formatter};
+
 use super::Listable;
 
 /// An identifier.
 ///
 /// Used for either a table name, a column name, or an alias.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Identifier<'input>(pub &'input str);
 
+impl Display for Identifier<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
 use crate::parse::Rule;
 use pest::iterators::Pair;
 
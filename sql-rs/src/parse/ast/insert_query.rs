@@ -1,4 +1,6 @@
-use super::{Identifier, List, Literal};
+use std::fmt::{Display, Formatter};
+
+use super::{comma_separated, Identifier, List, Literal};
 
 /// TODO quick description.
 ///
@@ -10,6 +12,18 @@ pub struct InsertQuery<'input> {
     pub values: Vec<Literal<'input>>,
 }
 
+impl Display for InsertQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "insert into {} ({}) values ({})",
+            self.table,
+            comma_separated(&self.columns),
+            comma_separated(&self.values)
+        )
+    }
+}
+
 use crate::parse::Rule;
 use pest::iterators::Pair;
 
@@ -1,13 +1,15 @@
-use super::{Expression, TableIdentifier};
+use std::fmt::{Display, Formatter};
+
+use super::{Expression, Identifier, List, TableIdentifier};
 
 /// TODO quick description.
 ///
 /// TODO long description.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Join<'input> {
     pub kind: JoinKind,
     pub table: TableIdentifier<'input>,
-    pub condition: Expression<'input>,
+    pub condition: JoinCondition<'input>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -15,6 +17,54 @@ pub enum JoinKind {
     Inner,
     Left,
     Right,
+    /// Every row of both tables appears, with the other table's columns null if unmatched.
+    Full,
+    /// Every row of one table is paired with every row of the other.
+    Cross,
+}
+
+/// How the rows of the tables in a join are matched up.
+#[derive(Debug, Clone)]
+pub enum JoinCondition<'input> {
+    On(Expression<'input>),
+    /// Rows match if they're equal in each of the listed columns, which both tables have. The
+    /// columns appear once in the joined table, rather than once from each table.
+    Using(Vec<Identifier<'input>>),
+    /// Like `Using`, with every column the tables have in common.
+    Natural,
+    /// Every pair of rows matches, for a cross join.
+    None,
+}
+
+impl Display for Join<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        if let JoinCondition::Natural = self.condition {
+            write!(f, "natural ")?;
+        }
+        write!(f, "{} {}", self.kind, self.table)?;
+
+        match &self.condition {
+            JoinCondition::On(condition) => write!(f, " on {}", condition),
+            JoinCondition::Using(columns) => {
+                let columns: Vec<_> = columns.iter().map(|c| c.0).collect();
+
+                write!(f, " using ({})", columns.join(", "))
+            }
+            JoinCondition::Natural | JoinCondition::None => Ok(()),
+        }
+    }
+}
+
+impl Display for JoinKind {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            JoinKind::Inner => write!(f, "join"),
+            JoinKind::Left => write!(f, "left join"),
+            JoinKind::Right => write!(f, "right join"),
+            JoinKind::Full => write!(f, "full join"),
+            JoinKind::Cross => write!(f, "cross join"),
+        }
+    }
 }
 
 use crate::parse::Rule;
@@ -22,12 +72,37 @@ use pest::iterators::Pair;
 
 impl<'input> From<Pair<'input, Rule>> for Join<'input> {
     fn from(join: Pair<'input, Rule>) -> Self {
+        if join.as_rule() == Rule::cross_join_clause {
+            return Join {
+                kind: JoinKind::Cross,
+                table: join.into_inner().next().unwrap().into(),
+                condition: JoinCondition::None,
+            };
+        }
+
         assert_eq!(join.as_rule(), Rule::join_clause);
 
         let mut inner = join.into_inner();
-        let kind = inner.next().unwrap().into();
+        let mut next = inner.next().unwrap();
+        let natural = next.as_rule() == Rule::natural;
+        if natural {
+            next = inner.next().unwrap();
+        }
+        let kind = next.into();
         let table = inner.next().unwrap().into();
-        let condition = inner.next().unwrap().into();
+        let condition = match inner.next() {
+            None => JoinCondition::Natural,
+            Some(condition) => match condition.as_rule() {
+                Rule::expression => JoinCondition::On(condition.into()),
+                Rule::using => {
+                    let columns: List<Identifier<'input>> =
+                        condition.into_inner().next().unwrap().into();
+
+                    JoinCondition::Using(columns.0)
+                }
+                _ => unreachable!(),
+            },
+        };
 
         Join {
             kind,
@@ -45,6 +120,7 @@ impl<'input> From<Pair<'input, Rule>> for JoinKind {
             Rule::inner_join => JoinKind::Inner,
             Rule::left_join => JoinKind::Left,
             Rule::right_join => JoinKind::Right,
+            Rule::full_join => JoinKind::Full,
             _ => unreachable!(),
         }
     }
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use crate::parse::Rule;
 
 /// Helper trait for parsing a repeated rule into a Vec via List.
@@ -24,3 +26,10 @@ where
         List(list.into_inner().map(From::from).collect())
     }
 }
+
+/// The items of a list separated by commas, as they're written in SQL.
+pub(super) fn comma_separated<T: Display>(items: &[T]) -> String {
+    let items: Vec<_> = items.iter().map(|item| item.to_string()).collect();
+
+    items.join(", ")
+}
@@ -1,15 +1,34 @@
+use std::fmt::{Display, Formatter};
+
 use super::Listable;
+use crate::data::{decode_hex, Datatype, Value, ValueError};
 
 /// A literal value.
 ///
-/// Possible literals are string literals, number literals, and boolean literals.
+/// Possible literals are string literals, number literals, and boolean literals, along with
+/// strings written as values of another datatype, such as `date '2024-01-15'`, and blobs
+/// written as hex digits, such as `x'DEADBEEF'`.
 // TODO consider removing this type, and using the Value type from the data module, leaving the
 // implementation of From<Pair<..>> here
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Literal<'input> {
     String(&'input str),
     Number(&'input str),
     Boolean(&'input str),
+    Typed(Datatype, &'input str),
+    Blob(&'input str),
+}
+
+impl Display for Literal<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Literal::String(s) => write!(f, "'{}'", s),
+            Literal::Number(n) => write!(f, "{}", n),
+            Literal::Boolean(b) => write!(f, "{}", b),
+            Literal::Typed(datatype, s) => write!(f, "{} '{}'", datatype, s),
+            Literal::Blob(hex) => write!(f, "x'{}'", hex),
+        }
+    }
 }
 
 use crate::parse::Rule;
@@ -29,6 +48,26 @@ impl<'input> From<Pair<'input, Rule>> for Literal<'input> {
             }
             Rule::number_literal => Literal::Number(inner_literal.as_str().trim()),
             Rule::boolean_literal => Literal::Boolean(inner_literal.as_str()),
+            Rule::typed_literal => {
+                let mut inner = inner_literal.into_inner();
+                let datatype = match inner.next().unwrap().as_str() {
+                    "date" => Datatype::Date,
+                    "time" => Datatype::Time,
+                    "timestamp" => Datatype::Timestamp,
+                    "interval" => Datatype::Interval,
+                    "json" => Datatype::Json,
+                    "uuid" => Datatype::Uuid,
+                    _ => unreachable!(),
+                };
+                let string_contents = inner.next().unwrap().into_inner().next().unwrap();
+
+                Literal::Typed(datatype, string_contents.as_str())
+            }
+            Rule::blob_literal => {
+                let hex = inner_literal.into_inner().next().unwrap();
+
+                Literal::Blob(hex.as_str())
+            }
             _ => unreachable!(),
         }
     }
@@ -40,13 +79,41 @@ impl<'input> Listable for Literal<'input> {
     }
 }
 
-use crate::data::Value;
-impl<'input> From<&Literal<'input>> for Value {
-    fn from(literal: &Literal<'input>) -> Self {
-        match *literal {
+use std::convert::TryFrom;
+
+/// The value of a literal. A number is real if it has an exponent, numeric if it has a decimal
+/// point (or is too large for an integer), and an integer otherwise, and fails if it's out of
+/// range of its datatype. A typed string fails if it isn't a valid value of its datatype, and a
+/// blob if it isn't pairs of hex digits.
+impl<'input> TryFrom<&Literal<'input>> for Value {
+    type Error = ValueError;
+
+    fn try_from(literal: &Literal<'input>) -> Result<Self, Self::Error> {
+        Ok(match *literal {
             Literal::String(s) => Value::Text(s.to_owned()),
-            Literal::Number(n) => Value::Number(n.parse().unwrap()),
+            Literal::Number(n) if n.contains(['e', 'E']) => {
+                let x: f64 = n.parse().unwrap();
+                if !x.is_finite() {
+                    return Err(ValueError::OutOfRange(Datatype::Real));
+                }
+
+                Value::Real(x)
+            }
+            Literal::Number(n) => match n.parse() {
+                Ok(n) => Value::Integer(n),
+                Err(_) => Value::Numeric(
+                    n.parse()
+                        .map_err(|_| ValueError::OutOfRange(Datatype::Numeric(None)))?,
+                ),
+            },
             Literal::Boolean(b) => Value::Boolean(b.parse().unwrap()),
-        }
+            Literal::Typed(datatype, s) => Value::parse(s, datatype)?,
+            Literal::Blob(hex) => {
+                Value::Blob(decode_hex(hex).ok_or_else(|| ValueError::InvalidSyntax {
+                    datatype: Datatype::Blob,
+                    input: hex.to_owned(),
+                })?)
+            }
+        })
     }
 }
@@ -36,6 +36,9 @@ pub use join::*;
 mod order_by;
 pub use order_by::*;
 
+mod explain_query;
+pub use explain_query::*;
+
 mod insert_query;
 pub use insert_query::*;
 
@@ -48,8 +51,13 @@ pub use create_table_query::*;
 mod create_table_column;
 pub use create_table_column::*;
 
+mod constraint;
+pub use constraint::*;
+
+mod references;
+pub use references::*;
+
 mod datatype;
-pub use datatype::*;
 
 mod update_query;
 pub use update_query::*;
@@ -57,6 +65,27 @@ pub use update_query::*;
 mod assignment;
 pub use assignment::*;
 
+mod delete_query;
+pub use delete_query::*;
+
+mod create_index_query;
+pub use create_index_query::*;
+
+mod drop_table_query;
+pub use drop_table_query::*;
+
+mod alter_table_query;
+pub use alter_table_query::*;
+
+mod transaction_query;
+pub use transaction_query::*;
+
+mod aggregate;
+pub use aggregate::*;
+
+mod function;
+pub use function::*;
+
 // Template
 // /// <Quick description.>
 // ///
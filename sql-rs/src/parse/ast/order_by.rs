@@ -1,23 +1,29 @@
+use std::fmt::{Display, Formatter};
+
 use super::Expression;
 
 /// TODO quick description.
 ///
 /// TODO long description.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OrderBy<'input> {
     pub expr: Expression<'input>,
     pub direction: OrderByDirection,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum OrderByDirection {
+    #[default]
     Asc,
     Desc,
 }
 
-impl Default for OrderByDirection {
-    fn default() -> Self {
-        OrderByDirection::Asc
+impl Display for OrderBy<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self.direction {
+            OrderByDirection::Asc => write!(f, "{} asc", self.expr),
+            OrderByDirection::Desc => write!(f, "{} desc", self.expr),
+        }
     }
 }
 
@@ -1,4 +1,9 @@
-use super::{CreateTableQuery, InsertQuery, SelectQuery, UpdateQuery};
+use std::fmt::{Display, Formatter};
+
+use super::{
+    AlterTableQuery, CreateIndexQuery, CreateTableQuery, DeleteQuery, DropTableQuery, ExplainQuery,
+    InsertQuery, SelectQuery, TransactionQuery, UpdateQuery,
+};
 
 /// The root of the AST, representing a single query.
 ///
@@ -6,9 +11,34 @@ use super::{CreateTableQuery, InsertQuery, SelectQuery, UpdateQuery};
 #[derive(Debug)]
 pub enum Query<'input> {
     SelectQuery(SelectQuery<'input>),
+    ExplainQuery(ExplainQuery<'input>),
     InsertQuery(InsertQuery<'input>),
     CreateTableQuery(CreateTableQuery<'input>),
+    CreateIndexQuery(CreateIndexQuery<'input>),
     UpdateQuery(UpdateQuery<'input>),
+    DeleteQuery(DeleteQuery<'input>),
+    DropTableQuery(DropTableQuery<'input>),
+    AlterTableQuery(AlterTableQuery<'input>),
+    TransactionQuery(TransactionQuery<'input>),
+}
+
+/// Queries are displayed as SQL which parses back into the same query, without the semicolon
+/// ending it.
+impl Display for Query<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Query::SelectQuery(query) => write!(f, "{}", query),
+            Query::ExplainQuery(query) => write!(f, "{}", query),
+            Query::InsertQuery(query) => write!(f, "{}", query),
+            Query::CreateTableQuery(query) => write!(f, "{}", query),
+            Query::CreateIndexQuery(query) => write!(f, "{}", query),
+            Query::UpdateQuery(query) => write!(f, "{}", query),
+            Query::DeleteQuery(query) => write!(f, "{}", query),
+            Query::DropTableQuery(query) => write!(f, "{}", query),
+            Query::AlterTableQuery(query) => write!(f, "{}", query),
+            Query::TransactionQuery(query) => write!(f, "{}", query),
+        }
+    }
 }
 
 use crate::parse::Rule;
@@ -22,9 +52,15 @@ impl<'input> From<Pair<'input, Rule>> for Query<'input> {
         let query = inner.next().unwrap();
         match query.as_rule() {
             Rule::select_query => Query::SelectQuery(query.into()),
+            Rule::explain_query => Query::ExplainQuery(query.into()),
             Rule::insert_query => Query::InsertQuery(query.into()),
             Rule::create_table_query => Query::CreateTableQuery(query.into()),
+            Rule::create_index_query => Query::CreateIndexQuery(query.into()),
             Rule::update_query => Query::UpdateQuery(query.into()),
+            Rule::delete_query => Query::DeleteQuery(query.into()),
+            Rule::drop_table_query => Query::DropTableQuery(query.into()),
+            Rule::alter_table_query => Query::AlterTableQuery(query.into()),
+            Rule::transaction_query => Query::TransactionQuery(query.into()),
             _ => unreachable!(),
         }
     }
@@ -0,0 +1,73 @@
+use std::fmt::{Display, Formatter};
+
+use super::{comma_separated, Identifier, List};
+use crate::data::ReferentialAction;
+
+/// The target of a foreign key: a table, and optionally which of its columns are referenced.
+///
+/// With no columns listed, the foreign key references the table's primary key. `on_delete`
+/// says what happens to referencing rows when a referenced row is deleted.
+#[derive(Debug)]
+pub struct References<'input> {
+    pub table: Identifier<'input>,
+    pub columns: Vec<Identifier<'input>>,
+    pub on_delete: ReferentialAction,
+}
+
+impl Display for References<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "references {}", self.table)?;
+        if !self.columns.is_empty() {
+            write!(f, " ({})", comma_separated(&self.columns))?;
+        }
+
+        match self.on_delete {
+            ReferentialAction::Restrict => Ok(()),
+            ReferentialAction::Cascade => write!(f, " on delete cascade"),
+            ReferentialAction::SetNull => write!(f, " on delete set null"),
+        }
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for References<'input> {
+    fn from(references: Pair<'input, Rule>) -> Self {
+        assert_eq!(references.as_rule(), Rule::references);
+
+        let mut inner = references.into_inner();
+        let table = inner.next().unwrap().into();
+        let mut columns = Vec::new();
+        let mut on_delete = ReferentialAction::default();
+
+        for pair in inner {
+            match pair.as_rule() {
+                Rule::identifier_list => {
+                    let list: List<Identifier<'input>> = pair.into();
+                    columns = list.0;
+                }
+                Rule::on_delete => on_delete = pair.into_inner().next().unwrap().into(),
+                _ => unreachable!(),
+            }
+        }
+
+        References {
+            table,
+            columns,
+            on_delete,
+        }
+    }
+}
+
+impl From<Pair<'_, Rule>> for ReferentialAction {
+    fn from(referential_action: Pair<'_, Rule>) -> Self {
+        assert_eq!(referential_action.as_rule(), Rule::referential_action);
+
+        match referential_action.as_str() {
+            "cascade" => ReferentialAction::Cascade,
+            "restrict" => ReferentialAction::Restrict,
+            _ => ReferentialAction::SetNull,
+        }
+    }
+}
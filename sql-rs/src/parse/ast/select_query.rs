@@ -1,17 +1,61 @@
+use std::fmt::{Display, Formatter};
+
 use super::{Expression, List, OrderBy, TableExpression};
 
 /// TODO quick description.
 ///
 /// TODO long description.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SelectQuery<'input> {
     pub select_list: Vec<Expression<'input>>,
     pub table: TableExpression<'input>,
     pub filter: Option<Expression<'input>>,
+    pub group_by: Vec<Expression<'input>>,
+    pub having: Option<Expression<'input>>,
     pub sort: Option<OrderBy<'input>>,
     pub limit: Option<Expression<'input>>,
 }
 
+impl<'input> SelectQuery<'input> {
+    /// Whether the query collapses its rows into groups, either explicitly with GROUP BY or
+    /// HAVING, or implicitly by selecting or ordering by an aggregate.
+    pub fn is_aggregate(&self) -> bool {
+        !self.group_by.is_empty()
+            || self.having.is_some()
+            || self.select_list.iter().any(Expression::contains_aggregate)
+            || matches!(&self.sort, Some(sort) if sort.expr.contains_aggregate())
+    }
+}
+
+impl Display for SelectQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "select {} from {}", join(&self.select_list), self.table)?;
+        if let Some(filter) = &self.filter {
+            write!(f, " where {}", filter)?;
+        }
+        if !self.group_by.is_empty() {
+            write!(f, " group by {}", join(&self.group_by))?;
+        }
+        if let Some(having) = &self.having {
+            write!(f, " having {}", having)?;
+        }
+        if let Some(sort) = &self.sort {
+            write!(f, " order by {}", sort)?;
+        }
+        if let Some(limit) = &self.limit {
+            write!(f, " limit {}", limit)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn join(expressions: &[Expression<'_>]) -> String {
+    let expressions: Vec<_> = expressions.iter().map(|e| e.to_string()).collect();
+
+    expressions.join(", ")
+}
+
 use crate::parse::Rule;
 use pest::iterators::Pair;
 
@@ -24,6 +68,8 @@ impl<'input> From<Pair<'input, Rule>> for SelectQuery<'input> {
         let select_list: List<Expression<'input>> = inner.next().unwrap().into();
         let table = inner.next().unwrap().into();
         let mut filter = None;
+        let mut group_by = Vec::new();
+        let mut having = None;
         let mut sort = None;
         let mut limit = None;
 
@@ -32,6 +78,12 @@ impl<'input> From<Pair<'input, Rule>> for SelectQuery<'input> {
             match pair {
                 Some(pair) => match pair.as_rule() {
                     Rule::where_clause => filter = Some(pair.into_inner().next().unwrap().into()),
+                    Rule::group_by_clause => {
+                        let list: List<Expression<'input>> =
+                            pair.into_inner().next().unwrap().into();
+                        group_by = list.0;
+                    }
+                    Rule::having_clause => having = Some(pair.into_inner().next().unwrap().into()),
                     Rule::order_by_clause => sort = Some(pair.into()),
                     Rule::limit_clause => limit = Some(pair.into_inner().next().unwrap().into()),
                     _ => unreachable!(),
@@ -44,6 +96,8 @@ impl<'input> From<Pair<'input, Rule>> for SelectQuery<'input> {
             select_list: select_list.0,
             table,
             filter,
+            group_by,
+            having,
             sort,
             limit,
         }
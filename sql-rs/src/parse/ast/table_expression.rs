@@ -1,14 +1,27 @@
+use std::fmt::{Display, Formatter};
+
 use super::{Join, TableIdentifier};
 
 /// TODO quick description.
 ///
 /// TODO long description.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TableExpression<'input> {
     pub root_table: TableIdentifier<'input>,
     pub joins: Vec<Join<'input>>,
 }
 
+impl Display for TableExpression<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.root_table)?;
+        for join in &self.joins {
+            write!(f, " {}", join)?;
+        }
+
+        Ok(())
+    }
+}
+
 use crate::parse::Rule;
 use pest::iterators::Pair;
 
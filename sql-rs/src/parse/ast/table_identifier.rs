@@ -1,10 +1,12 @@
+use std::fmt::{Display, Formatter};
+
 use super::Identifier;
 
 /// A table name with a possible alias.
 ///
 /// Used when defining which tables a select query operates on
 /// and establishes the context for expression evaluation using alias.column syntax.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TableIdentifier<'input> {
     pub name: Identifier<'input>,
     pub alias: Option<Identifier<'input>>,
@@ -19,6 +21,15 @@ impl<'input> TableIdentifier<'input> {
     }
 }
 
+impl Display for TableIdentifier<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match &self.alias {
+            Some(alias) => write!(f, "{} as {}", self.name.0, alias.0),
+            None => write!(f, "{}", self.name.0),
+        }
+    }
+}
+
 use crate::parse::Rule;
 use pest::iterators::Pair;
 
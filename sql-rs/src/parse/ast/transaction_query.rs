@@ -0,0 +1,59 @@
+use std::fmt::{Display, Formatter};
+
+use super::Identifier;
+
+/// A query beginning or ending a transaction, or marking a savepoint within one.
+///
+/// The changes made by the queries in a transaction are kept together when it's committed, or
+/// all undone when it's rolled back. Rolling back to a savepoint only undoes the changes made
+/// since it, and releasing it keeps them.
+#[derive(Debug)]
+pub enum TransactionQuery<'input> {
+    Begin,
+    Commit,
+    Rollback,
+    Savepoint(Identifier<'input>),
+    RollbackToSavepoint(Identifier<'input>),
+    ReleaseSavepoint(Identifier<'input>),
+}
+
+impl Display for TransactionQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            TransactionQuery::Begin => write!(f, "begin"),
+            TransactionQuery::Commit => write!(f, "commit"),
+            TransactionQuery::Rollback => write!(f, "rollback"),
+            TransactionQuery::Savepoint(name) => write!(f, "savepoint {}", name),
+            TransactionQuery::RollbackToSavepoint(name) => {
+                write!(f, "rollback to savepoint {}", name)
+            }
+            TransactionQuery::ReleaseSavepoint(name) => write!(f, "release savepoint {}", name),
+        }
+    }
+}
+
+use crate::parse::Rule;
+use pest::iterators::Pair;
+
+impl<'input> From<Pair<'input, Rule>> for TransactionQuery<'input> {
+    fn from(transaction_query: Pair<'input, Rule>) -> Self {
+        assert_eq!(transaction_query.as_rule(), Rule::transaction_query);
+
+        let query = transaction_query.into_inner().next().unwrap();
+        let rule = query.as_rule();
+        let mut inner = query.into_inner();
+        match rule {
+            Rule::begin => TransactionQuery::Begin,
+            Rule::commit => TransactionQuery::Commit,
+            Rule::rollback => TransactionQuery::Rollback,
+            Rule::savepoint => TransactionQuery::Savepoint(inner.next().unwrap().into()),
+            Rule::rollback_to_savepoint => {
+                TransactionQuery::RollbackToSavepoint(inner.next().unwrap().into())
+            }
+            Rule::release_savepoint => {
+                TransactionQuery::ReleaseSavepoint(inner.next().unwrap().into())
+            }
+            _ => unreachable!(),
+        }
+    }
+}
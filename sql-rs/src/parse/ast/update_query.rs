@@ -1,4 +1,6 @@
-use super::{Assignment, Expression, Identifier, List};
+use std::fmt::{Display, Formatter};
+
+use super::{comma_separated, Assignment, Expression, Identifier, List};
 
 /// TODO quick description.
 ///
@@ -10,6 +12,18 @@ pub struct UpdateQuery<'input> {
     pub filter: Expression<'input>,
 }
 
+impl Display for UpdateQuery<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "update {} set {} where {}",
+            self.table,
+            comma_separated(&self.assignments),
+            self.filter
+        )
+    }
+}
+
 use crate::parse::Rule;
 use pest::iterators::Pair;
 
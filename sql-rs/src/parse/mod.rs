@@ -7,8 +7,17 @@ use parser::{QueryParser, Rule};
 pub mod ast;
 
 /// The main entry function for the parser module. Parses a list of queries from a &str.
+#[allow(clippy::result_large_err)]
 pub fn parse_queries(source: &str) -> Result<ast::Queries<'_>, Error<Rule>> {
     let mut parse = QueryParser::parse(Rule::queries, source)?;
 
     Ok(parse.next().unwrap().into())
 }
+
+/// Parses a single expression from a &str, such as one stored as part of a table's definition.
+#[allow(clippy::result_large_err)]
+pub fn parse_expression(source: &str) -> Result<ast::Expression<'_>, Error<Rule>> {
+    let mut parse = QueryParser::parse(Rule::expression, source)?;
+
+    Ok(parse.next().unwrap().into())
+}
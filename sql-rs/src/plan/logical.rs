@@ -0,0 +1,300 @@
+use std::fmt::{Display, Formatter};
+
+use crate::parse::ast::{
+    Aggregate, Expression, JoinCondition, JoinKind, OrderByDirection, SelectQuery, TableIdentifier,
+};
+
+/// A tree of relational operators, each producing rows from the rows of the operators below it.
+#[derive(Debug)]
+pub enum LogicalPlan<'input> {
+    /// The rows of a stored table, with each column named `alias.column`. Only the rows
+    /// satisfying `filter` are produced, with only `columns` (or every column, if `None`).
+    Scan {
+        table: &'input str,
+        alias: &'input str,
+        filter: Option<Expression<'input>>,
+        columns: Option<Vec<String>>,
+    },
+    /// The rows satisfying a predicate.
+    Filter {
+        input: Box<LogicalPlan<'input>>,
+        predicate: Expression<'input>,
+    },
+    /// For each row, the values of a list of expressions.
+    Project {
+        input: Box<LogicalPlan<'input>>,
+        expressions: Vec<Expression<'input>>,
+    },
+    Join {
+        left: Box<LogicalPlan<'input>>,
+        right: Box<LogicalPlan<'input>>,
+        kind: JoinKind,
+        condition: JoinCondition<'input>,
+    },
+    Sort {
+        input: Box<LogicalPlan<'input>>,
+        expr: Expression<'input>,
+        direction: OrderByDirection,
+    },
+    Limit {
+        input: Box<LogicalPlan<'input>>,
+        count: Expression<'input>,
+    },
+    /// A row for each group of rows with equal values of `group_by` (or a single group of every
+    /// row, without any): the group's first row (or nulls, for an empty group), followed by the
+    /// value of each of `aggregates` over the group.
+    Aggregate {
+        input: Box<LogicalPlan<'input>>,
+        group_by: Vec<Expression<'input>>,
+        aggregates: Vec<Aggregate<'input>>,
+    },
+}
+
+impl<'input> LogicalPlan<'input> {
+    /// The plan of a select query before it's optimized. Tables are scanned and joined, then the
+    /// remaining clauses are applied in the order SQL defines: WHERE, GROUP BY (and aggregates),
+    /// HAVING, ORDER BY, LIMIT, and finally the select list.
+    pub fn new(query: &SelectQuery<'input>) -> Self {
+        let mut plan = scan(&query.table.root_table);
+        for join in &query.table.joins {
+            plan = LogicalPlan::Join {
+                left: Box::new(plan),
+                right: Box::new(scan(&join.table)),
+                kind: join.kind,
+                condition: join.condition.clone(),
+            };
+        }
+
+        if let Some(filter) = &query.filter {
+            plan = LogicalPlan::Filter {
+                input: Box::new(plan),
+                predicate: filter.clone(),
+            };
+        }
+
+        if query.is_aggregate() {
+            plan = LogicalPlan::Aggregate {
+                input: Box::new(plan),
+                group_by: query.group_by.clone(),
+                aggregates: query_aggregates(query),
+            };
+
+            if let Some(having) = &query.having {
+                plan = LogicalPlan::Filter {
+                    input: Box::new(plan),
+                    predicate: having.clone(),
+                };
+            }
+        }
+
+        if let Some(sort) = &query.sort {
+            plan = LogicalPlan::Sort {
+                input: Box::new(plan),
+                expr: sort.expr.clone(),
+                direction: sort.direction,
+            };
+        }
+
+        if let Some(limit) = &query.limit {
+            plan = LogicalPlan::Limit {
+                input: Box::new(plan),
+                count: limit.clone(),
+            };
+        }
+
+        LogicalPlan::Project {
+            input: Box::new(plan),
+            expressions: query.select_list.clone(),
+        }
+    }
+
+    /// The operators this one takes its rows from.
+    pub fn children(&self) -> Vec<&LogicalPlan<'input>> {
+        match self {
+            LogicalPlan::Scan { .. } => Vec::new(),
+            LogicalPlan::Join { left, right, .. } => vec![left, right],
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. }
+            | LogicalPlan::Aggregate { input, .. } => vec![input],
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut LogicalPlan<'input>> {
+        match self {
+            LogicalPlan::Scan { .. } => Vec::new(),
+            LogicalPlan::Join { left, right, .. } => vec![left, right],
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Project { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. }
+            | LogicalPlan::Aggregate { input, .. } => vec![input],
+        }
+    }
+
+    /// The expressions this operator evaluates (not counting those of the operators below it).
+    pub fn expressions(&self) -> Vec<&Expression<'input>> {
+        match self {
+            LogicalPlan::Scan { filter, .. } => filter.iter().collect(),
+            LogicalPlan::Filter { predicate, .. } => vec![predicate],
+            LogicalPlan::Project { expressions, .. } => expressions.iter().collect(),
+            LogicalPlan::Join { condition, .. } => match condition {
+                JoinCondition::On(condition) => vec![condition],
+                _ => Vec::new(),
+            },
+            LogicalPlan::Sort { expr, .. } => vec![expr],
+            LogicalPlan::Limit { count, .. } => vec![count],
+            LogicalPlan::Aggregate {
+                group_by,
+                aggregates,
+                ..
+            } => group_by
+                .iter()
+                .chain(aggregates.iter().filter_map(|a| a.arg.as_deref()))
+                .collect(),
+        }
+    }
+
+    pub fn expressions_mut(&mut self) -> Vec<&mut Expression<'input>> {
+        match self {
+            LogicalPlan::Scan { filter, .. } => filter.iter_mut().collect(),
+            LogicalPlan::Filter { predicate, .. } => vec![predicate],
+            LogicalPlan::Project { expressions, .. } => expressions.iter_mut().collect(),
+            LogicalPlan::Join { condition, .. } => match condition {
+                JoinCondition::On(condition) => vec![condition],
+                _ => Vec::new(),
+            },
+            LogicalPlan::Sort { expr, .. } => vec![expr],
+            LogicalPlan::Limit { count, .. } => vec![count],
+            LogicalPlan::Aggregate {
+                group_by,
+                aggregates,
+                ..
+            } => group_by
+                .iter_mut()
+                .chain(aggregates.iter_mut().filter_map(|a| a.arg.as_deref_mut()))
+                .collect(),
+        }
+    }
+
+    /// The tables scanned by this plan, in the order their columns appear, as `(alias, table)`.
+    pub fn scans(&self) -> Vec<(&'input str, &'input str)> {
+        match self {
+            LogicalPlan::Scan { table, alias, .. } => vec![(*alias, *table)],
+            _ => self.children().into_iter().flat_map(Self::scans).collect(),
+        }
+    }
+
+    /// Whether this plan contains a USING or NATURAL join, which merges columns of its tables.
+    pub fn merges_columns(&self) -> bool {
+        match self {
+            LogicalPlan::Join {
+                condition: JoinCondition::Using(_) | JoinCondition::Natural,
+                ..
+            } => true,
+            _ => self.children().into_iter().any(Self::merges_columns),
+        }
+    }
+}
+
+/// A plan is displayed as a description of its top operator, not including those below it.
+impl Display for LogicalPlan<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            LogicalPlan::Scan {
+                table,
+                alias,
+                filter,
+                columns,
+            } => {
+                write!(f, "scan {}", table)?;
+                if alias != table {
+                    write!(f, " as {}", alias)?;
+                }
+                if let Some(columns) = columns {
+                    write!(f, " ({})", columns.join(", "))?;
+                }
+                if let Some(filter) = filter {
+                    write!(f, " where {}", filter)?;
+                }
+
+                Ok(())
+            }
+            LogicalPlan::Filter { predicate, .. } => write!(f, "filter {}", predicate),
+            LogicalPlan::Project { expressions, .. } => {
+                write!(f, "project {}", join(expressions))
+            }
+            LogicalPlan::Join {
+                kind, condition, ..
+            } => match condition {
+                JoinCondition::On(condition) => write!(f, "{} on {}", kind, condition),
+                JoinCondition::Using(columns) => {
+                    let columns: Vec<_> = columns.iter().map(|c| c.0).collect();
+
+                    write!(f, "{} using ({})", kind, columns.join(", "))
+                }
+                JoinCondition::Natural => write!(f, "natural {}", kind),
+                JoinCondition::None => write!(f, "{}", kind),
+            },
+            LogicalPlan::Sort {
+                expr, direction, ..
+            } => match direction {
+                OrderByDirection::Asc => write!(f, "sort by {} asc", expr),
+                OrderByDirection::Desc => write!(f, "sort by {} desc", expr),
+            },
+            LogicalPlan::Limit { count, .. } => write!(f, "limit {}", count),
+            LogicalPlan::Aggregate {
+                group_by,
+                aggregates,
+                ..
+            } => {
+                write!(f, "aggregate")?;
+                if !aggregates.is_empty() {
+                    write!(f, " {}", join(aggregates))?;
+                }
+                if !group_by.is_empty() {
+                    write!(f, " group by {}", join(group_by))?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn join<T: Display>(items: &[T]) -> String {
+    let items: Vec<_> = items.iter().map(T::to_string).collect();
+
+    items.join(", ")
+}
+
+fn scan<'input>(table: &TableIdentifier<'input>) -> LogicalPlan<'input> {
+    LogicalPlan::Scan {
+        table: table.name.0,
+        alias: match &table.alias {
+            Some(alias) => alias.0,
+            None => table.name.0,
+        },
+        filter: None,
+        columns: None,
+    }
+}
+
+/// Every distinct aggregate function call of a query, which needs computing for each group.
+fn query_aggregates<'input>(query: &SelectQuery<'input>) -> Vec<Aggregate<'input>> {
+    let mut aggregates: Vec<Aggregate<'input>> = Vec::new();
+    let sort = query.sort.iter().map(|sort| &sort.expr);
+    for expr in query.select_list.iter().chain(&query.having).chain(sort) {
+        for aggregate in expr.aggregates() {
+            if !aggregates
+                .iter()
+                .any(|a| a.to_string() == aggregate.to_string())
+            {
+                aggregates.push(aggregate.clone());
+            }
+        }
+    }
+
+    aggregates
+}
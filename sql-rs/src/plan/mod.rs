@@ -0,0 +1,11 @@
+//! Planning how to execute a select query.
+//!
+//! A query is first turned into a logical plan, a tree of relational operators with one for each
+//! clause of the query. The optimizer then rewrites the plan into one which produces the same
+//! rows with less work, before it's executed.
+
+mod logical;
+pub use logical::*;
+
+mod optimize;
+pub use optimize::*;
@@ -0,0 +1,448 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::mem;
+
+use super::LogicalPlan;
+use crate::data::{Database, Value};
+use crate::parse::ast::{
+    BinaryOp, Column, ColumnIdentifier, Expression, ExpressionOp, JoinCondition, JoinKind,
+};
+
+/// Rewrite a plan into one producing the same rows with less work, by applying each rule in
+/// turn.
+///
+/// - Constant folding evaluates the parts of expressions which don't depend on a row once, rather
+///   than for every row.
+/// - Predicate pushdown moves each part of a filter as far down the plan as it can go, so rows
+///   are filtered out when a table is scanned rather than after it's joined.
+/// - Projection pruning has each scan copy only the columns the rest of the plan uses.
+pub fn optimize<'input>(mut plan: LogicalPlan<'input>, database: &Database) -> LogicalPlan<'input> {
+    fold_constants(&mut plan);
+    let mut plan = push_down_predicates(plan, database);
+    prune_projections(&mut plan, database);
+
+    plan
+}
+
+// constant folding
+
+fn fold_constants(plan: &mut LogicalPlan<'_>) {
+    for expr in plan.expressions_mut() {
+        fold(expr);
+    }
+
+    for child in plan.children_mut() {
+        fold_constants(child);
+    }
+}
+
+/// Replace each operation of constant operands with its value.
+fn fold(expr: &mut Expression<'_>) {
+    match expr {
+        Expression::Literal(l) => {
+            // a number out of range is left to fail when the query is executed
+            if let Ok(value) = Value::try_from(&*l) {
+                *expr = Expression::Constant(value);
+            }
+        }
+        Expression::Aggregate(a) => {
+            if let Some(arg) = &mut a.arg {
+                fold(arg);
+            }
+        }
+        Expression::Function(f) => {
+            for arg in &mut f.args {
+                fold(arg);
+            }
+        }
+        Expression::BinaryOp(b) => {
+            fold(&mut b.left);
+            fold(&mut b.right);
+
+            let folded = match (&*b.left, &*b.right) {
+                (Expression::Constant(l), Expression::Constant(r)) => fold_values(b.op, l, r),
+                (Expression::Constant(c), other) | (other, Expression::Constant(c)) => {
+                    simplify(b.op, c, other)
+                }
+                _ => None,
+            };
+            if let Some(folded) = folded {
+                *expr = folded;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The value of an operation on two constants, unless working it out could fail, in which case
+/// it's left for when the query is executed (and fails there, the same as without folding).
+fn fold_values<'input>(op: ExpressionOp, l: &Value, r: &Value) -> Option<Expression<'input>> {
+    let value = match (op, l, r) {
+        (ExpressionOp::In, _, _) => return None,
+        (ExpressionOp::And | ExpressionOp::Or, l, r) => {
+            let logical = |v: &Value| matches!(v, Value::Boolean(_) | Value::Null);
+            if !logical(l) || !logical(r) {
+                return None;
+            }
+
+            l.clone().op(op, r.clone()).ok()?
+        }
+        (_, Value::Null, _) | (_, _, Value::Null) | (ExpressionOp::Equal, _, _) => {
+            l.clone().op(op, r.clone()).ok()?
+        }
+        (_, l, r) if l.is_numeric() && r.is_numeric() => l.clone().op(op, r.clone()).ok()?,
+        (ExpressionOp::Plus | ExpressionOp::Minus, _, _) => return None,
+        (_, Value::Text(_), Value::Text(_)) => l.clone().op(op, r.clone()).ok()?,
+        _ => return None,
+    };
+
+    Some(Expression::Constant(value))
+}
+
+/// An `and` (or `or`) with a constant boolean operand, simplified to the other operand or to the
+/// constant itself.
+fn simplify<'input>(
+    op: ExpressionOp,
+    constant: &Value,
+    other: &Expression<'input>,
+) -> Option<Expression<'input>> {
+    match (op, constant) {
+        (ExpressionOp::And, Value::Boolean(true)) | (ExpressionOp::Or, Value::Boolean(false)) => {
+            Some(other.clone())
+        }
+        (ExpressionOp::And, Value::Boolean(false)) | (ExpressionOp::Or, Value::Boolean(true)) => {
+            Some(Expression::Constant(constant.clone()))
+        }
+        _ => None,
+    }
+}
+
+// predicate pushdown
+
+/// The tables visible to an expression, for working out which of them its columns belong to.
+struct Scope<'input> {
+    /// The `(alias, table)` of each table, in the order their columns appear.
+    scans: Vec<(&'input str, &'input str)>,
+    /// Whether some columns are merged by a USING (or NATURAL) join, so an unqualified column
+    /// may not belong to any one table.
+    merges_columns: bool,
+}
+
+impl<'input> Scope<'input> {
+    fn new(plans: &[&LogicalPlan<'input>]) -> Self {
+        Scope {
+            scans: plans.iter().flat_map(|plan| plan.scans()).collect(),
+            merges_columns: plans.iter().any(|plan| plan.merges_columns()),
+        }
+    }
+
+    /// The aliases of the tables an expression's columns belong to, if every one can be worked
+    /// out. An expression with a subquery or an aggregate can't be moved, so has none.
+    fn aliases(&self, expr: &Expression<'_>, database: &Database) -> Option<BTreeSet<&'input str>> {
+        if expr.contains_subquery() || expr.contains_aggregate() {
+            return None;
+        }
+
+        expr.column_identifiers()
+            .into_iter()
+            .map(|identifier| self.alias(identifier, database))
+            .collect()
+    }
+
+    /// The alias of the table a column belongs to. An unqualified column belongs to the first
+    /// table which has it, the same as when it's evaluated.
+    fn alias(&self, identifier: &ColumnIdentifier<'_>, database: &Database) -> Option<&'input str> {
+        let name = match &identifier.name {
+            Column::Ident(name) => name.0,
+            Column::Star => return None,
+        };
+
+        let mut scans = self.scans.iter().filter(|(alias, table)| {
+            database.find_table(table).find_column(name).is_some()
+                && identifier.alias.as_ref().is_none_or(|a| a.0 == *alias)
+        });
+
+        match &identifier.alias {
+            None if self.merges_columns => None,
+            _ => scans.next().map(|(alias, _)| *alias),
+        }
+    }
+}
+
+fn push_down_predicates<'input>(
+    plan: LogicalPlan<'input>,
+    database: &Database,
+) -> LogicalPlan<'input> {
+    match plan {
+        LogicalPlan::Filter {
+            mut input,
+            predicate,
+        } => {
+            let scope = Scope::new(&[&input]);
+            let mut remaining = Vec::new();
+            for conjunct in conjuncts(predicate) {
+                if let Expression::Constant(Value::Boolean(true)) = conjunct {
+                    continue;
+                }
+
+                match scope.aliases(&conjunct, database) {
+                    Some(aliases) if !aliases.is_empty() => {
+                        remaining.extend(push_predicate(&mut input, conjunct, &aliases));
+                    }
+                    _ => remaining.push(conjunct),
+                }
+            }
+
+            let input = push_down_predicates(*input, database);
+            match conjoin(remaining) {
+                Some(predicate) => LogicalPlan::Filter {
+                    input: Box::new(input),
+                    predicate,
+                },
+                None => input,
+            }
+        }
+        LogicalPlan::Join {
+            mut left,
+            mut right,
+            kind,
+            condition: JoinCondition::On(condition),
+        } => {
+            let scope = Scope::new(&[&left, &right]);
+            let left_aliases: BTreeSet<_> = left.scans().into_iter().map(|(a, _)| a).collect();
+
+            // a condition on only one side of an inner join can filter that side first, but only
+            // the inner side of an outer join, whose rows aren't all kept anyway
+            let (push_left, push_right) = match kind {
+                JoinKind::Inner | JoinKind::Cross => (true, true),
+                JoinKind::Left => (false, true),
+                JoinKind::Right => (true, false),
+                JoinKind::Full => (false, false),
+            };
+
+            let mut remaining = Vec::new();
+            for conjunct in conjuncts(condition) {
+                let aliases = match scope.aliases(&conjunct, database) {
+                    Some(aliases) if !aliases.is_empty() => aliases,
+                    _ => {
+                        remaining.push(conjunct);
+                        continue;
+                    }
+                };
+
+                let on_left = aliases.is_subset(&left_aliases);
+                let on_right = aliases.is_disjoint(&left_aliases);
+                if on_left && push_left {
+                    remaining.extend(push_predicate(&mut left, conjunct, &aliases));
+                } else if on_right && push_right {
+                    remaining.extend(push_predicate(&mut right, conjunct, &aliases));
+                } else {
+                    remaining.push(conjunct);
+                }
+            }
+
+            LogicalPlan::Join {
+                left: Box::new(push_down_predicates(*left, database)),
+                right: Box::new(push_down_predicates(*right, database)),
+                kind,
+                condition: JoinCondition::On(
+                    conjoin(remaining).unwrap_or(Expression::Constant(Value::Boolean(true))),
+                ),
+            }
+        }
+        mut plan => {
+            for child in plan.children_mut() {
+                let owned = mem::replace(child, placeholder());
+                *child = push_down_predicates(owned, database);
+            }
+
+            plan
+        }
+    }
+}
+
+/// Move a filter on the tables `aliases` down into a plan, returning it if it can't be.
+///
+/// A filter on the tables of one side of a join is pushed into that side, unless it's the side
+/// of an outer join whose rows are all kept. Otherwise a filter over an inner (or cross) join is
+/// added to its condition. A filter reaching the scan of its table becomes part of the scan.
+fn push_predicate<'input>(
+    plan: &mut LogicalPlan<'input>,
+    predicate: Expression<'input>,
+    aliases: &BTreeSet<&'input str>,
+) -> Option<Expression<'input>> {
+    match plan {
+        LogicalPlan::Scan { alias, filter, .. } if aliases.iter().all(|a| a == alias) => {
+            *filter = conjoin(filter.take().into_iter().chain([predicate]).collect());
+            None
+        }
+        LogicalPlan::Join {
+            left,
+            right,
+            kind,
+            condition,
+        } => {
+            let in_left = |a: &&str| left.scans().iter().any(|(alias, _)| alias == a);
+            let in_right = |a: &&str| right.scans().iter().any(|(alias, _)| alias == a);
+
+            let mut predicate = predicate;
+            if aliases.iter().all(in_left)
+                && matches!(kind, JoinKind::Inner | JoinKind::Cross | JoinKind::Left)
+            {
+                predicate = push_predicate(left, predicate, aliases)?;
+            } else if aliases.iter().all(in_right)
+                && matches!(kind, JoinKind::Inner | JoinKind::Cross | JoinKind::Right)
+            {
+                predicate = push_predicate(right, predicate, aliases)?;
+            }
+
+            match (kind, condition) {
+                (JoinKind::Inner, JoinCondition::On(condition)) => {
+                    let existing = mem::replace(condition, placeholder_expression());
+                    *condition = and(existing, predicate);
+                    None
+                }
+                (kind @ JoinKind::Cross, condition @ JoinCondition::None) => {
+                    *kind = JoinKind::Inner;
+                    *condition = JoinCondition::On(predicate);
+                    None
+                }
+                _ => Some(predicate),
+            }
+        }
+        _ => Some(predicate),
+    }
+}
+
+/// The parts of an expression joined by `and`.
+fn conjuncts(expr: Expression<'_>) -> Vec<Expression<'_>> {
+    match expr {
+        Expression::BinaryOp(b) if matches!(b.op, ExpressionOp::And) => {
+            let mut parts = conjuncts(*b.left);
+            parts.append(&mut conjuncts(*b.right));
+            parts
+        }
+        _ => vec![expr],
+    }
+}
+
+/// The expressions joined by `and`, or `None` if there aren't any.
+fn conjoin(exprs: Vec<Expression<'_>>) -> Option<Expression<'_>> {
+    exprs.into_iter().reduce(and)
+}
+
+fn and<'input>(left: Expression<'input>, right: Expression<'input>) -> Expression<'input> {
+    Expression::BinaryOp(BinaryOp {
+        op: ExpressionOp::And,
+        left: Box::new(left),
+        right: Box::new(right),
+    })
+}
+
+/// Something to leave in place of a plan (or expression) briefly, while it's being rewritten.
+fn placeholder<'input>() -> LogicalPlan<'input> {
+    LogicalPlan::Scan {
+        table: "",
+        alias: "",
+        filter: None,
+        columns: None,
+    }
+}
+
+fn placeholder_expression<'input>() -> Expression<'input> {
+    Expression::Constant(Value::Null)
+}
+
+// projection pruning
+
+/// The columns of each table the plan uses, by alias.
+#[derive(Default)]
+struct RequiredColumns<'input> {
+    columns: BTreeMap<&'input str, BTreeSet<String>>,
+    /// Tables every column of which are used, such as by `*`.
+    all: BTreeSet<&'input str>,
+    /// Columns used without naming their table, which could be any table's.
+    unqualified: BTreeSet<String>,
+}
+
+fn prune_projections(plan: &mut LogicalPlan<'_>, database: &Database) {
+    let mut required = RequiredColumns::default();
+    collect_required(plan, &mut required);
+    prune(plan, &required, database);
+}
+
+fn collect_required<'input>(plan: &LogicalPlan<'input>, required: &mut RequiredColumns<'input>) {
+    let scans = plan.scans();
+    for expr in plan.expressions() {
+        for identifier in expr.column_identifiers() {
+            match (&identifier.alias, &identifier.name) {
+                (None, Column::Star) => required.all.extend(scans.iter().map(|(a, _)| *a)),
+                (Some(alias), Column::Star) => {
+                    required.all.insert(alias.0);
+                }
+                (None, Column::Ident(name)) => {
+                    required.unqualified.insert(name.0.to_owned());
+                }
+                (Some(alias), Column::Ident(name)) => {
+                    required
+                        .columns
+                        .entry(alias.0)
+                        .or_default()
+                        .insert(name.0.to_owned());
+                }
+            }
+        }
+    }
+
+    match plan {
+        // the columns a natural join is on depend on every column of its tables
+        LogicalPlan::Join {
+            condition: JoinCondition::Natural,
+            ..
+        } => required.all.extend(scans.iter().map(|(a, _)| *a)),
+        LogicalPlan::Join {
+            condition: JoinCondition::Using(columns),
+            ..
+        } => required
+            .unqualified
+            .extend(columns.iter().map(|c| c.0.to_owned())),
+        _ => {}
+    }
+
+    for child in plan.children() {
+        collect_required(child, required);
+    }
+}
+
+fn prune(plan: &mut LogicalPlan<'_>, required: &RequiredColumns<'_>, database: &Database) {
+    if let LogicalPlan::Scan {
+        table,
+        alias,
+        columns,
+        ..
+    } = plan
+    {
+        if !required.all.contains(alias) {
+            let qualified = required.columns.get(alias);
+            let table_columns = &database.find_table(table).columns;
+            let used: Vec<_> = table_columns
+                .iter()
+                .map(|c| &c.name)
+                .filter(|&name| {
+                    required.unqualified.contains(name)
+                        || qualified.is_some_and(|q| q.contains(name))
+                })
+                .cloned()
+                .collect();
+
+            // leave a scan of every column as it is
+            if used.len() < table_columns.len() {
+                *columns = Some(used);
+            }
+        }
+    }
+
+    for child in plan.children_mut() {
+        prune(child, required, database);
+    }
+}
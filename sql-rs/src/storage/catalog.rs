@@ -0,0 +1,201 @@
+use super::encode::{corrupt, decode_row, Decoder, Encoder};
+use super::Error;
+use crate::data::{Column, Constraint, IndexMethod, ReferentialAction, Table};
+
+/// The catalog's record of a table: its name, definition, and where its rows are stored.
+#[derive(Debug)]
+pub struct CatalogEntry {
+    pub name: String,
+    columns: Vec<Column>,
+    constraints: Vec<Constraint>,
+    /// The name, columns, and method of each index.
+    indexes: Vec<(String, Vec<String>, IndexMethod)>,
+    /// The first page of the table's heap, or 0 if it has no rows.
+    pub first_page: u32,
+    /// The number of rows in the table's heap.
+    pub records: usize,
+}
+
+impl CatalogEntry {
+    pub fn new(name: &str, table: &Table, first_page: u32, records: usize) -> Self {
+        CatalogEntry {
+            name: name.to_owned(),
+            columns: table.columns.clone(),
+            constraints: table.constraints.clone(),
+            indexes: table
+                .indexes
+                .iter()
+                .map(|index| (index.name.clone(), index.columns.clone(), index.method()))
+                .collect(),
+            first_page,
+            records,
+        }
+    }
+
+    /// The table this entry describes, holding the rows read from its heap.
+    pub fn table(&self, records: Vec<Vec<u8>>) -> Result<Table, Error> {
+        let rows = records
+            .iter()
+            .map(|record| decode_row(record))
+            .collect::<Result<Vec<_>, _>>()?;
+        if rows.iter().any(|row| row.0.len() != self.columns.len()) {
+            return Err(corrupt("row doesn't match its table's columns"));
+        }
+
+        let mut table = Table {
+            columns: self.columns.clone(),
+            rows,
+            constraints: self.constraints.clone(),
+            indexes: Vec::new(),
+        };
+        for (name, columns, method) in &self.indexes {
+            table.create_index(name.clone(), columns.clone(), *method);
+        }
+
+        Ok(table)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.str(&self.name);
+        encoder.u32(self.first_page);
+        encoder.u32(self.records as u32);
+
+        encoder.u32(self.columns.len() as u32);
+        for column in &self.columns {
+            encoder.str(&column.name);
+            encoder.datatype(column.datatype);
+            encoder.bool(column.hidden);
+        }
+
+        encoder.u32(self.constraints.len() as u32);
+        for constraint in &self.constraints {
+            encode_constraint(&mut encoder, constraint);
+        }
+
+        encoder.u32(self.indexes.len() as u32);
+        for (name, columns, method) in &self.indexes {
+            encoder.str(name);
+            encoder.strings(columns);
+            encoder.u8(match method {
+                IndexMethod::BTree => 0,
+                IndexMethod::Hash => 1,
+            });
+        }
+
+        encoder.finish()
+    }
+
+    pub fn decode(record: &[u8]) -> Result<Self, Error> {
+        let mut decoder = Decoder::new(record);
+        let name = decoder.str()?;
+        let first_page = decoder.u32()?;
+        let records = decoder.u32()? as usize;
+
+        let columns = (0..decoder.u32()?)
+            .map(|_| {
+                Ok(Column {
+                    name: decoder.str()?,
+                    datatype: decoder.datatype()?,
+                    hidden: decoder.bool()?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let constraints = (0..decoder.u32()?)
+            .map(|_| decode_constraint(&mut decoder))
+            .collect::<Result<_, _>>()?;
+
+        let indexes = (0..decoder.u32()?)
+            .map(|_| {
+                let name = decoder.str()?;
+                let columns = decoder.strings()?;
+                let method = match decoder.u8()? {
+                    0 => IndexMethod::BTree,
+                    1 => IndexMethod::Hash,
+                    _ => return Err(corrupt("invalid index method")),
+                };
+
+                Ok((name, columns, method))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        decoder.finish()?;
+
+        Ok(CatalogEntry {
+            name,
+            columns,
+            constraints,
+            indexes,
+            first_page,
+            records,
+        })
+    }
+}
+
+fn encode_constraint(encoder: &mut Encoder, constraint: &Constraint) {
+    match constraint {
+        Constraint::PrimaryKey(columns) => {
+            encoder.u8(0);
+            encoder.strings(columns);
+        }
+        Constraint::Unique(columns) => {
+            encoder.u8(1);
+            encoder.strings(columns);
+        }
+        Constraint::NotNull(column) => {
+            encoder.u8(2);
+            encoder.str(column);
+        }
+        Constraint::Default { column, expression } => {
+            encoder.u8(3);
+            encoder.str(column);
+            encoder.str(expression);
+        }
+        Constraint::Check(expression) => {
+            encoder.u8(4);
+            encoder.str(expression);
+        }
+        Constraint::ForeignKey {
+            columns,
+            table,
+            referenced_columns,
+            on_delete,
+        } => {
+            encoder.u8(5);
+            encoder.strings(columns);
+            encoder.str(table);
+            encoder.strings(referenced_columns);
+            encoder.u8(match on_delete {
+                ReferentialAction::Restrict => 0,
+                ReferentialAction::Cascade => 1,
+                ReferentialAction::SetNull => 2,
+            });
+        }
+    }
+}
+
+fn decode_constraint(decoder: &mut Decoder<'_>) -> Result<Constraint, Error> {
+    Ok(match decoder.u8()? {
+        0 => Constraint::PrimaryKey(decoder.strings()?),
+        1 => Constraint::Unique(decoder.strings()?),
+        2 => Constraint::NotNull(decoder.str()?),
+        3 => Constraint::Default {
+            column: decoder.str()?,
+            expression: decoder.str()?,
+        },
+        4 => Constraint::Check(decoder.str()?),
+        5 => Constraint::ForeignKey {
+            columns: decoder.strings()?,
+            table: decoder.str()?,
+            referenced_columns: decoder.strings()?,
+            on_delete: match decoder.u8()? {
+                0 => ReferentialAction::Restrict,
+                1 => ReferentialAction::Cascade,
+                2 => ReferentialAction::SetNull,
+                _ => return Err(corrupt("invalid referential action")),
+            },
+        },
+        _ => return Err(corrupt("invalid constraint")),
+    })
+}
@@ -0,0 +1,319 @@
+//! The encoding of rows and table definitions as records.
+//!
+//! Numbers are little-endian, strings and lists are prefixed by their length, and each variant
+//! of an enum by a tag byte.
+
+use std::convert::{TryFrom, TryInto};
+
+use super::Error;
+use crate::data::{Datatype, Date, Decimal, Interval, Row, Time, Timestamp, Uuid, Value};
+
+/// Builds up a record.
+#[derive(Debug, Default)]
+pub struct Encoder(Vec<u8>);
+
+impl Encoder {
+    pub fn finish(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn f64(&mut self, value: f64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn i128(&mut self, value: i128) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value);
+    }
+
+    pub fn str(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    pub fn strings(&mut self, values: &[String]) {
+        self.u32(values.len() as u32);
+        for value in values {
+            self.str(value);
+        }
+    }
+
+    pub fn datatype(&mut self, datatype: Datatype) {
+        self.u8(match datatype {
+            Datatype::Integer => 0,
+            Datatype::Text => 1,
+            Datatype::Boolean => 2,
+            Datatype::Real => 3,
+            Datatype::Numeric(None) => 4,
+            Datatype::Numeric(Some(_)) => 5,
+            Datatype::Date => 6,
+            Datatype::Time => 7,
+            Datatype::Timestamp => 8,
+            Datatype::Interval => 9,
+            Datatype::Blob => 10,
+            Datatype::Json => 11,
+            Datatype::Uuid => 12,
+        });
+        if let Datatype::Numeric(Some((precision, scale))) = datatype {
+            self.u32(precision);
+            self.u32(scale);
+        }
+    }
+
+    pub fn value(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.u8(0),
+            Value::Integer(n) => {
+                self.u8(4);
+                self.i64(*n);
+            }
+            Value::Real(x) => {
+                self.u8(5);
+                self.f64(*x);
+            }
+            Value::Numeric(d) => {
+                self.u8(6);
+                self.i128(d.mantissa());
+                self.u32(d.scale());
+            }
+            Value::Text(s) => {
+                self.u8(2);
+                self.str(s);
+            }
+            Value::Boolean(b) => {
+                self.u8(3);
+                self.bool(*b);
+            }
+            Value::Date(d) => {
+                self.u8(7);
+                self.i64(d.days());
+            }
+            Value::Time(t) => {
+                self.u8(8);
+                self.i64(t.micros());
+            }
+            Value::Timestamp(t) => {
+                self.u8(9);
+                self.i64(t.micros());
+            }
+            Value::Interval(i) => {
+                self.u8(10);
+                self.i64(i.months() as i64);
+                self.i64(i.days() as i64);
+                self.i64(i.micros());
+            }
+            Value::Blob(b) => {
+                self.u8(11);
+                self.bytes(b);
+            }
+            Value::Json(j) => {
+                self.u8(12);
+                self.str(&j.to_string());
+            }
+            Value::Uuid(u) => {
+                self.u8(13);
+                self.0.extend_from_slice(u.as_bytes());
+            }
+            Value::List(_) => unreachable!("lists aren't stored in tables"),
+        }
+    }
+}
+
+/// Reads a record back, failing if it ends early or holds something which can't be decoded.
+#[derive(Debug)]
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Decoder { bytes }
+    }
+
+    /// Check that the whole record has been read.
+    pub fn finish(self) -> Result<(), Error> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(corrupt("record is longer than expected"))
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.bytes.len() {
+            return Err(corrupt("record ends early"));
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Error> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    pub fn i64(&mut self) -> Result<i64, Error> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+
+        Ok(i64::from_le_bytes(bytes))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, Error> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+
+        Ok(f64::from_le_bytes(bytes))
+    }
+
+    pub fn i128(&mut self) -> Result<i128, Error> {
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(self.take(16)?);
+
+        Ok(i128::from_le_bytes(bytes))
+    }
+
+    pub fn bool(&mut self) -> Result<bool, Error> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(corrupt("invalid boolean")),
+        }
+    }
+
+    pub fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.u32()? as usize;
+
+        Ok(self.take(len)?.to_vec())
+    }
+
+    pub fn str(&mut self) -> Result<String, Error> {
+        String::from_utf8(self.bytes()?).map_err(|_| corrupt("invalid utf-8"))
+    }
+
+    pub fn strings(&mut self) -> Result<Vec<String>, Error> {
+        (0..self.u32()?).map(|_| self.str()).collect()
+    }
+
+    pub fn datatype(&mut self) -> Result<Datatype, Error> {
+        match self.u8()? {
+            0 => Ok(Datatype::Integer),
+            1 => Ok(Datatype::Text),
+            2 => Ok(Datatype::Boolean),
+            3 => Ok(Datatype::Real),
+            4 => Ok(Datatype::Numeric(None)),
+            5 => Ok(Datatype::Numeric(Some((self.u32()?, self.u32()?)))),
+            6 => Ok(Datatype::Date),
+            7 => Ok(Datatype::Time),
+            8 => Ok(Datatype::Timestamp),
+            9 => Ok(Datatype::Interval),
+            10 => Ok(Datatype::Blob),
+            11 => Ok(Datatype::Json),
+            12 => Ok(Datatype::Uuid),
+            _ => Err(corrupt("invalid datatype")),
+        }
+    }
+
+    /// Integers were once stored in 4 bytes with tag 1, which are still read.
+    pub fn value(&mut self) -> Result<Value, Error> {
+        match self.u8()? {
+            0 => Ok(Value::Null),
+            1 => Ok(Value::Integer(self.u32()?.into())),
+            2 => Ok(Value::Text(self.str()?)),
+            3 => Ok(Value::Boolean(self.bool()?)),
+            4 => Ok(Value::Integer(self.i64()?)),
+            5 => match self.f64()? {
+                x if x.is_finite() => Ok(Value::Real(x)),
+                _ => Err(corrupt("invalid real")),
+            },
+            6 => {
+                let mantissa = self.i128()?;
+                Decimal::new(mantissa, self.u32()?)
+                    .map(Value::Numeric)
+                    .ok_or_else(|| corrupt("invalid numeric"))
+            }
+            7 => Date::from_days(self.i64()?)
+                .map(Value::Date)
+                .ok_or_else(|| corrupt("invalid date")),
+            8 => Time::from_micros(self.i64()?)
+                .map(Value::Time)
+                .ok_or_else(|| corrupt("invalid time")),
+            9 => Timestamp::from_micros(self.i64()?)
+                .map(Value::Timestamp)
+                .ok_or_else(|| corrupt("invalid timestamp")),
+            10 => {
+                let months = i32::try_from(self.i64()?);
+                let days = i32::try_from(self.i64()?);
+                match (months, days) {
+                    (Ok(months), Ok(days)) => {
+                        Ok(Value::Interval(Interval::new(months, days, self.i64()?)))
+                    }
+                    _ => Err(corrupt("invalid interval")),
+                }
+            }
+            11 => Ok(Value::Blob(self.bytes()?)),
+            12 => self
+                .str()?
+                .parse()
+                .map(Value::Json)
+                .map_err(|_| corrupt("invalid json")),
+            13 => Ok(Value::Uuid(Uuid::from_bytes(
+                self.take(16)?.try_into().unwrap(),
+            ))),
+            _ => Err(corrupt("invalid value")),
+        }
+    }
+}
+
+pub fn corrupt(reason: &str) -> Error {
+    Error::Corrupt(reason.to_owned())
+}
+
+/// A row's record: the number of values, then each value.
+pub fn row(row: &Row) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    encoder.u32(row.0.len() as u32);
+    for value in &row.0 {
+        encoder.value(value);
+    }
+
+    encoder.finish()
+}
+
+pub fn decode_row(record: &[u8]) -> Result<Row, Error> {
+    let mut decoder = Decoder::new(record);
+    let values = (0..decoder.u32()?)
+        .map(|_| decoder.value())
+        .collect::<Result<_, _>>()?;
+    decoder.finish()?;
+
+    Ok(Row(values))
+}
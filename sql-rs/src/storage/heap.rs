@@ -0,0 +1,256 @@
+use std::collections::HashSet;
+use std::mem;
+
+use super::{Error, Page, Pager, Slot, MAX_RECORD_SIZE};
+
+/// A chain of pages holding records in order, such as the rows of a table.
+///
+/// The number of records is stored along with the first page, since the last page may hold more:
+/// records are appended to it where it is, and appending them may be cut short by a crash before
+/// the new number of records is stored.
+#[derive(Debug, Default, Clone)]
+pub struct Heap {
+    pages: Vec<u32>,
+    /// The overflow pages of the records too large to fit in a page.
+    overflow: Vec<u32>,
+    records: usize,
+    /// The number of records of the heap in its last page.
+    last: usize,
+}
+
+impl Heap {
+    /// Read the heap of `count` records starting at page `first` (empty if it's 0), and every
+    /// record in it.
+    pub fn read(
+        pager: &mut Pager,
+        first: u32,
+        count: usize,
+    ) -> Result<(Self, Vec<Vec<u8>>), Error> {
+        let mut heap = Heap::default();
+        let mut records = Vec::new();
+        let mut seen = HashSet::new();
+
+        let mut next = (first != 0).then_some(first);
+        while let Some(number) = next {
+            let page = read_once(pager, number, &mut seen)?;
+            heap.last = 0;
+            for slot in page.records().take(count - records.len()) {
+                records.push(match slot {
+                    Slot::Record(record) => record.to_vec(),
+                    Slot::Overflow { len, first } => {
+                        read_overflow(pager, len, first, &mut seen, &mut heap.overflow)?
+                    }
+                });
+                heap.last += 1;
+            }
+            heap.pages.push(number);
+            next = page.next().filter(|_| records.len() < count);
+        }
+        if records.len() < count {
+            return Err(Error::Corrupt(String::from("heap cut short")));
+        }
+        heap.records = count;
+
+        Ok((heap, records))
+    }
+
+    /// The first page of the heap, or 0 if it has none.
+    pub fn first_page(&self) -> u32 {
+        self.pages.first().copied().unwrap_or(0)
+    }
+
+    /// The number of records in the heap.
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Every page of the heap.
+    pub fn page_numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.pages.iter().chain(&self.overflow).copied()
+    }
+
+    /// Replace every record of the heap, writing them to new pages. Its old pages are freed once
+    /// the new ones are stored in their place.
+    pub fn write(
+        &mut self,
+        pager: &mut Pager,
+        records: impl Iterator<Item = Vec<u8>>,
+    ) -> Result<(), Error> {
+        let old = mem::take(self);
+        let mut count = 0;
+        let pages = pack(
+            pager,
+            Vec::new(),
+            records.inspect(|_| count += 1),
+            &mut self.overflow,
+        )?;
+
+        let numbers: Vec<_> = pages.iter().map(|_| pager.allocate()).collect();
+        self.last = pages.last().map_or(0, Page::len);
+        write_chain(pager, pages, &numbers)?;
+        self.pages = numbers;
+        self.records = count;
+        old.free(pager);
+
+        Ok(())
+    }
+
+    /// Add records after the last record of the heap, filling up its last page first. The
+    /// records already in the last page are written again as they are, so it's written where it
+    /// is.
+    pub fn append(
+        &mut self,
+        pager: &mut Pager,
+        records: impl Iterator<Item = Vec<u8>>,
+    ) -> Result<(), Error> {
+        let last = match self.pages.last() {
+            Some(&number) => truncate(&pager.read(number)?, self.last),
+            None => Page::new(),
+        };
+
+        let mut count = 0;
+        let pages = pack(
+            pager,
+            vec![last],
+            records.inspect(|_| count += 1),
+            &mut self.overflow,
+        )?;
+
+        let mut numbers: Vec<_> = self.pages.last().copied().into_iter().collect();
+        while numbers.len() < pages.len() {
+            numbers.push(pager.allocate());
+        }
+
+        self.last = pages.last().map_or(0, Page::len);
+        write_chain(pager, pages, &numbers)?;
+        self.pages
+            .extend(numbers.into_iter().skip(self.pages.len().min(1)));
+        self.records += count;
+
+        Ok(())
+    }
+
+    /// Free every page of the heap.
+    pub fn free(self, pager: &mut Pager) {
+        for number in self.page_numbers() {
+            pager.free(number);
+        }
+    }
+}
+
+/// A page holding the first `len` records of `page`, as it was before any others were added to
+/// it.
+fn truncate(page: &Page, len: usize) -> Page {
+    let mut truncated = Page::new();
+    for slot in page.records().take(len) {
+        truncated.insert(&slot);
+    }
+
+    truncated
+}
+
+/// Read a page of a heap, checking that no page is chained to twice.
+fn read_once(pager: &mut Pager, number: u32, seen: &mut HashSet<u32>) -> Result<Page, Error> {
+    if !seen.insert(number) {
+        return Err(Error::Corrupt(format!(
+            "page {} is chained to twice",
+            number
+        )));
+    }
+
+    pager.read(number)
+}
+
+/// Read a record of `len` bytes from the chain of overflow pages starting at `first`, adding
+/// the pages to `overflow`.
+fn read_overflow(
+    pager: &mut Pager,
+    len: usize,
+    first: u32,
+    seen: &mut HashSet<u32>,
+    overflow: &mut Vec<u32>,
+) -> Result<Vec<u8>, Error> {
+    let mut record = Vec::new();
+
+    let mut next = Some(first);
+    while record.len() < len {
+        let number = next.ok_or_else(|| Error::Corrupt(String::from("record cut short")))?;
+        let page = read_once(pager, number, seen)?;
+        match page.records().next() {
+            Some(Slot::Record(part)) => record.extend_from_slice(part),
+            _ => {
+                return Err(Error::Corrupt(String::from(
+                    "overflow page without a record",
+                )))
+            }
+        }
+        overflow.push(number);
+        next = page.next();
+    }
+
+    if record.len() != len || next.is_some() {
+        return Err(Error::Corrupt(String::from(
+            "record longer than its length",
+        )));
+    }
+
+    Ok(record)
+}
+
+/// Pack records into pages, after those already in `pages`. Empty if there are no pages or
+/// records at all. Records too large to fit in a page are written to overflow pages of their
+/// own first, which are added to `overflow`.
+fn pack(
+    pager: &mut Pager,
+    mut pages: Vec<Page>,
+    records: impl Iterator<Item = Vec<u8>>,
+    overflow: &mut Vec<u32>,
+) -> Result<Vec<Page>, Error> {
+    for record in records {
+        let slot = match record.len() > MAX_RECORD_SIZE {
+            true => Slot::Overflow {
+                len: record.len(),
+                first: write_overflow(pager, &record, overflow)?,
+            },
+            false => Slot::Record(&record),
+        };
+
+        let fits = pages.last_mut().is_some_and(|page| page.insert(&slot));
+        if !fits {
+            let mut page = Page::new();
+            page.insert(&slot);
+            pages.push(page);
+        }
+    }
+
+    Ok(pages)
+}
+
+/// Write a record too large to fit in a page to a chain of new overflow pages, each holding as
+/// much of it as fits, adding them to `overflow`. Returns the first.
+fn write_overflow(pager: &mut Pager, record: &[u8], overflow: &mut Vec<u32>) -> Result<u32, Error> {
+    let pages: Vec<_> = record
+        .chunks(MAX_RECORD_SIZE)
+        .map(|part| {
+            let mut page = Page::new();
+            page.insert(&Slot::Record(part));
+            page
+        })
+        .collect();
+    let numbers: Vec<_> = pages.iter().map(|_| pager.allocate()).collect();
+
+    write_chain(pager, pages, &numbers)?;
+    overflow.extend_from_slice(&numbers);
+
+    Ok(numbers[0])
+}
+
+/// Write pages to the page numbers given, chaining each to the next.
+fn write_chain(pager: &mut Pager, mut pages: Vec<Page>, numbers: &[u32]) -> Result<(), Error> {
+    for (i, page) in pages.iter_mut().enumerate() {
+        page.set_next(numbers.get(i + 1).copied());
+        pager.write(numbers[i], page)?;
+    }
+
+    Ok(())
+}
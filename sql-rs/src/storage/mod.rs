@@ -0,0 +1,166 @@
+//! Storing a database in a file, so it outlives the process using it.
+//!
+//! The file is split into fixed-size pages. The first is a header, pointing to the catalog.
+//! Every other page is a slotted page of records, chained to the next page of whatever it belongs
+//! to: the catalog, a table's heap, or a record too large to fit in a page. The pages which don't
+//! belong to anything are free.
+//!
+//! Changes are written to free pages (or past the end of what's already in use), and only take
+//! the place of what they change once the header is written to point to them, so a crash part
+//! way through writing them leaves the file as it was.
+//!
+//! The catalog holds a record for each table, with its definition (columns, constraints, and
+//! indexes) and the first page of its heap, which holds a record for each of its rows. Tables
+//! are read into memory when the file is opened, and written back as queries change them. Only
+//! index definitions are stored, and indexes are rebuilt as tables are read.
+//!
+//! Separately, a write-ahead log records each statement changing a database before it's
+//! executed, so the database can be rebuilt by executing them again. The header of a database
+//! file records the last statement of the log it holds the changes of, so that when both are
+//! used, only the statements after it are executed again.
+
+mod encode;
+
+mod page;
+use page::*;
+
+mod pager;
+use pager::*;
+
+mod heap;
+use heap::*;
+
+mod catalog;
+use catalog::*;
+
+mod wal;
+pub use wal::*;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::data::Table;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("database file is corrupt: {0}")]
+    Corrupt(String),
+}
+
+/// The file a database is stored in, and where each table is stored within it.
+#[derive(Debug)]
+pub struct Storage {
+    pager: Pager,
+    catalog: Heap,
+    heaps: HashMap<String, Heap>,
+}
+
+impl Storage {
+    /// Open the database stored in a file, creating an empty one if the file doesn't exist, and
+    /// read its tables.
+    pub fn open(path: &Path) -> Result<(Self, HashMap<String, Table>), Error> {
+        let mut pager = Pager::open(path)?;
+
+        let (first_page, count) = pager.catalog();
+        let (catalog, records) = Heap::read(&mut pager, first_page, count)?;
+        let mut heaps = HashMap::new();
+        let mut tables = HashMap::new();
+        for record in records {
+            let entry = CatalogEntry::decode(&record)?;
+            let (heap, rows) = Heap::read(&mut pager, entry.first_page, entry.records)?;
+
+            tables.insert(entry.name.clone(), entry.table(rows)?);
+            heaps.insert(entry.name, heap);
+        }
+        pager.set_used(
+            catalog
+                .page_numbers()
+                .chain(heaps.values().flat_map(Heap::page_numbers)),
+        );
+
+        let storage = Storage {
+            pager,
+            catalog,
+            heaps,
+        };
+
+        Ok((storage, tables))
+    }
+
+    /// The sequence number of the last statement of a write-ahead log whose changes are stored.
+    pub fn applied(&self) -> u64 {
+        self.pager.applied()
+    }
+
+    pub fn set_applied(&mut self, applied: u64) {
+        self.pager.set_applied(applied);
+    }
+
+    /// Write the tables to the file, replacing what was stored.
+    ///
+    /// Rows a table gained at its end (as inserting adds them) are appended to its heap, but
+    /// anything else would go unnoticed: the rows of each table in `rewritten` are written out
+    /// again in full. The catalog is always written out again.
+    ///
+    /// If writing fails, the storage goes back to what it knew was stored before, which the file
+    /// still holds.
+    pub fn save(
+        &mut self,
+        tables: &HashMap<String, Arc<Table>>,
+        rewritten: &[String],
+    ) -> Result<(), Error> {
+        let pager = self.pager.state();
+        let catalog = self.catalog.clone();
+        let heaps = self.heaps.clone();
+
+        let result = self.write(tables, rewritten);
+        if result.is_err() {
+            self.pager.restore(pager);
+            self.catalog = catalog;
+            self.heaps = heaps;
+        }
+
+        result
+    }
+
+    fn write(
+        &mut self,
+        tables: &HashMap<String, Arc<Table>>,
+        rewritten: &[String],
+    ) -> Result<(), Error> {
+        let dropped: Vec<_> = self
+            .heaps
+            .keys()
+            .filter(|name| !tables.contains_key(*name))
+            .cloned()
+            .collect();
+        for name in dropped {
+            let heap = self.heaps.remove(&name).unwrap();
+            heap.free(&mut self.pager);
+        }
+
+        let mut entries = Vec::new();
+        for (name, table) in tables {
+            let heap = self.heaps.entry(name.clone()).or_default();
+            let stored = heap.records();
+            let rows = &table.rows;
+
+            if rewritten.contains(name) || rows.len() < stored {
+                heap.write(&mut self.pager, rows.iter().map(encode::row))?;
+            } else if rows.len() > stored {
+                heap.append(&mut self.pager, rows[stored..].iter().map(encode::row))?;
+            }
+
+            entries
+                .push(CatalogEntry::new(name, table, heap.first_page(), heap.records()).encode());
+        }
+
+        self.catalog.write(&mut self.pager, entries.into_iter())?;
+        self.pager
+            .set_catalog(self.catalog.first_page(), self.catalog.records());
+        self.pager.flush()
+    }
+}
@@ -0,0 +1,171 @@
+use super::Error;
+
+/// The size of each page of a database file, in bytes.
+pub const PAGE_SIZE: usize = 4096;
+
+/// The size of the largest record which fits in a page. A larger record is stored in a chain of
+/// overflow pages, each holding as much of it as fits, and the page it belongs in only holds
+/// where to find it.
+pub const MAX_RECORD_SIZE: usize = PAGE_SIZE - HEADER_SIZE - SLOT_SIZE;
+
+// A page starts with a header: the number of the next page in its chain (or 0 if it's the last,
+// since page 0 is the file header and never chained to), the number of records, and where the
+// free space between the slots and the records ends. Then come the slots, the offset and length
+// of each record, growing towards the end of the page. The records are packed in from the end
+// of the page, growing back towards the slots. A record's length has its top bit set if the
+// record is only the length of an overflowing record and the first of its overflow pages.
+
+const HEADER_SIZE: usize = 8;
+const SLOT_SIZE: usize = 4;
+const OVERFLOW: u16 = 1 << 15;
+const OVERFLOW_SIZE: usize = 8;
+
+/// A record of a page.
+#[derive(Debug)]
+pub enum Slot<'a> {
+    /// A record held in the page.
+    Record(&'a [u8]),
+    /// A record of `len` bytes held in the chain of overflow pages starting at `first`.
+    Overflow { len: usize, first: u32 },
+}
+
+/// A page holding records, which are opaque byte strings.
+#[derive(Debug, Clone)]
+pub struct Page {
+    bytes: Vec<u8>,
+}
+
+impl Page {
+    /// An empty page, which is the last of its chain.
+    pub fn new() -> Self {
+        let mut page = Page {
+            bytes: vec![0; PAGE_SIZE],
+        };
+        page.set_u16(6, PAGE_SIZE as u16);
+
+        page
+    }
+
+    /// A page read from a file, checking that its slots point within it.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        let page = Page { bytes };
+        let slots_end = HEADER_SIZE + page.len() * SLOT_SIZE;
+        let free_end = page.u16(6) as usize;
+
+        if slots_end > free_end || free_end > PAGE_SIZE {
+            return Err(Error::Corrupt(String::from("page header out of bounds")));
+        }
+        for i in 0..page.len() {
+            let (offset, len, overflow) = page.slot(i);
+            if offset < free_end || offset + len > PAGE_SIZE || overflow && len != OVERFLOW_SIZE {
+                return Err(Error::Corrupt(String::from("record out of bounds")));
+            }
+        }
+
+        Ok(page)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The next page of the chain this page is in, if it isn't the last.
+    pub fn next(&self) -> Option<u32> {
+        match self.u32(0) {
+            0 => None,
+            next => Some(next),
+        }
+    }
+
+    pub fn set_next(&mut self, next: Option<u32>) {
+        self.set_u32(0, next.unwrap_or(0));
+    }
+
+    /// The number of records in the page.
+    pub fn len(&self) -> usize {
+        self.u16(4) as usize
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = Slot<'_>> {
+        (0..self.len()).map(move |i| match self.slot(i) {
+            (offset, _, true) => Slot::Overflow {
+                len: self.u32(offset) as usize,
+                first: self.u32(offset + 4),
+            },
+            (offset, len, false) => Slot::Record(&self.bytes[offset..offset + len]),
+        })
+    }
+
+    /// Add a record to the end of the page. `false` if there isn't room for it.
+    pub fn insert(&mut self, slot: &Slot<'_>) -> bool {
+        match *slot {
+            Slot::Record(record) => self.insert_bytes(record, 0),
+            Slot::Overflow { len, first } => {
+                let mut record = [0; OVERFLOW_SIZE];
+                record[..4].copy_from_slice(&(len as u32).to_le_bytes());
+                record[4..].copy_from_slice(&first.to_le_bytes());
+
+                self.insert_bytes(&record, OVERFLOW)
+            }
+        }
+    }
+
+    fn insert_bytes(&mut self, record: &[u8], flags: u16) -> bool {
+        let count = self.len();
+        let slots_end = HEADER_SIZE + (count + 1) * SLOT_SIZE;
+        let free_end = self.u16(6) as usize;
+
+        if slots_end + record.len() > free_end {
+            return false;
+        }
+
+        let offset = free_end - record.len();
+        self.bytes[offset..free_end].copy_from_slice(record);
+        self.set_u16(HEADER_SIZE + count * SLOT_SIZE, offset as u16);
+        self.set_u16(
+            HEADER_SIZE + count * SLOT_SIZE + 2,
+            record.len() as u16 | flags,
+        );
+        self.set_u16(4, count as u16 + 1);
+        self.set_u16(6, offset as u16);
+
+        true
+    }
+
+    /// The offset and length of a record, and whether it's where to find an overflowing record.
+    fn slot(&self, i: usize) -> (usize, usize, bool) {
+        let slot = HEADER_SIZE + i * SLOT_SIZE;
+        let len = self.u16(slot + 2);
+
+        (
+            self.u16(slot) as usize,
+            (len & !OVERFLOW) as usize,
+            len & OVERFLOW != 0,
+        )
+    }
+
+    fn u16(&self, at: usize) -> u16 {
+        u16::from_le_bytes([self.bytes[at], self.bytes[at + 1]])
+    }
+
+    fn set_u16(&mut self, at: usize, value: u16) {
+        self.bytes[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&self, at: usize) -> u32 {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&self.bytes[at..at + 4]);
+
+        u32::from_le_bytes(bytes)
+    }
+
+    fn set_u32(&mut self, at: usize, value: u32) {
+        self.bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Page::new()
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::{Error, Page, PAGE_SIZE};
+
+/// Identifies a file as a database file, and the version of its format.
+const MAGIC: &[u8; 8] = b"sql-rs02";
+
+/// Reads and writes the pages of a database file, and keeps track of which are free.
+///
+/// The header (page 0) holds the first page of the catalog and its number of records, and the
+/// last statement of the write-ahead log applied, and is written when the pager is flushed.
+/// Until then, the pages of what the header points to are left as they are, so that a crash
+/// part way through writing leaves the file as it was when the header was last written: pages
+/// are freed for reuse only once the header no longer points to anything they're part of.
+#[derive(Debug)]
+pub struct Pager {
+    file: File,
+    page_count: u32,
+    catalog: u32,
+    catalog_records: u32,
+    applied: u64,
+    /// Pages no longer in use, with the last on top.
+    free: Vec<u32>,
+    /// Pages no longer in use, but still part of what the header points to until it's flushed.
+    released: Vec<u32>,
+}
+
+/// What a pager knows about its file, kept to go back to if writing to the file fails.
+#[derive(Debug)]
+pub struct PagerState {
+    page_count: u32,
+    catalog: u32,
+    catalog_records: u32,
+    applied: u64,
+    free: Vec<u32>,
+    released: Vec<u32>,
+}
+
+impl Pager {
+    /// Open a database file, creating it if it doesn't exist.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let len = file.metadata()?.len();
+
+        let mut pager = Pager {
+            file,
+            page_count: 1,
+            catalog: 0,
+            catalog_records: 0,
+            applied: 0,
+            free: Vec::new(),
+            released: Vec::new(),
+        };
+
+        if len == 0 {
+            pager.flush()?;
+            return Ok(pager);
+        }
+        // a page cut short by a crash as the file grew isn't part of anything the header points
+        // to, so it's written over
+        pager.page_count = (len / PAGE_SIZE as u64) as u32;
+        let header = match pager.page_count {
+            0 => Vec::new(),
+            _ => pager.read_bytes(0)?,
+        };
+        if !header.starts_with(MAGIC) {
+            return Err(Error::Corrupt(String::from("not a database file")));
+        }
+        pager.catalog = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        pager.catalog_records =
+            u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+        let mut applied = [0; 8];
+        applied.copy_from_slice(&header[16..24]);
+        pager.applied = u64::from_le_bytes(applied);
+
+        Ok(pager)
+    }
+
+    /// The first page of the catalog (or 0 if it's empty), and its number of records.
+    pub fn catalog(&self) -> (u32, usize) {
+        (self.catalog, self.catalog_records as usize)
+    }
+
+    pub fn set_catalog(&mut self, catalog: u32, records: usize) {
+        self.catalog = catalog;
+        self.catalog_records = records as u32;
+    }
+
+    /// Mark every page which isn't one of `used` as free. The free pages aren't recorded in the
+    /// file, so they're found once it's known which pages are used when it's opened.
+    pub fn set_used(&mut self, used: impl Iterator<Item = u32>) {
+        let used: HashSet<_> = used.collect();
+        self.free = (1..self.page_count)
+            .rev()
+            .filter(|number| !used.contains(number))
+            .collect();
+    }
+
+    pub fn applied(&self) -> u64 {
+        self.applied
+    }
+
+    pub fn set_applied(&mut self, applied: u64) {
+        self.applied = applied;
+    }
+
+    pub fn state(&self) -> PagerState {
+        PagerState {
+            page_count: self.page_count,
+            catalog: self.catalog,
+            catalog_records: self.catalog_records,
+            applied: self.applied,
+            free: self.free.clone(),
+            released: self.released.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, state: PagerState) {
+        self.page_count = state.page_count;
+        self.catalog = state.catalog;
+        self.catalog_records = state.catalog_records;
+        self.applied = state.applied;
+        self.free = state.free;
+        self.released = state.released;
+    }
+
+    pub fn read(&mut self, number: u32) -> Result<Page, Error> {
+        if number == 0 || number >= self.page_count {
+            return Err(Error::Corrupt(format!("no page {}", number)));
+        }
+
+        Page::from_bytes(self.read_bytes(number)?)
+    }
+
+    pub fn write(&mut self, number: u32, page: &Page) -> Result<(), Error> {
+        self.file
+            .seek(SeekFrom::Start(number as u64 * PAGE_SIZE as u64))?;
+        self.file.write_all(page.bytes())?;
+
+        Ok(())
+    }
+
+    /// A page to use, either a free one or a new one at the end of the file.
+    pub fn allocate(&mut self) -> u32 {
+        self.free.pop().unwrap_or_else(|| {
+            self.page_count += 1;
+            self.page_count - 1
+        })
+    }
+
+    /// Free a page for reuse, once the header no longer points to anything it's part of.
+    pub fn free(&mut self, number: u32) {
+        self.released.push(number);
+    }
+
+    /// Write the header, once everything else written has reached the disk, and wait for it to
+    /// reach the disk too. The pages freed since it was last written can then be reused.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.file.sync_data()?;
+
+        let mut header = vec![0; PAGE_SIZE];
+        header[..8].copy_from_slice(MAGIC);
+        header[8..12].copy_from_slice(&self.catalog.to_le_bytes());
+        header[12..16].copy_from_slice(&self.catalog_records.to_le_bytes());
+        header[16..24].copy_from_slice(&self.applied.to_le_bytes());
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.sync_data()?;
+        self.free.append(&mut self.released);
+
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, number: u32) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![0; PAGE_SIZE];
+        self.file
+            .seek(SeekFrom::Start(number as u64 * PAGE_SIZE as u64))?;
+        self.file.read_exact(&mut bytes)?;
+
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,250 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::Error;
+use crate::data::Timestamp;
+
+/// Identifies a file as a write-ahead log, and the version of its format.
+const MAGIC: &[u8; 8] = b"sql-rs-w";
+
+/// The size of a record's header: its sequence number, the length of its statement, and a
+/// checksum of both and the rest of the record.
+const RECORD_HEADER_SIZE: usize = 16;
+
+/// Set in the length of a record's statement if the statement is preceded by the time it began,
+/// in microseconds since 1970-01-01 00:00:00. Records logged before statements could call the
+/// current time don't have one.
+const TIMED: u32 = 1 << 31;
+
+/// Set in the length of a record's statement if the statement is preceded by the seed of the
+/// uuids it generates (after its time, if it has one).
+const SEEDED: u32 = 1 << 30;
+
+/// A record of the log, read back.
+#[derive(Debug)]
+pub struct Record {
+    pub sequence: u64,
+    /// The time the statements began, if it was logged.
+    pub time: Option<Timestamp>,
+    /// The seed of the uuids the statements generate, if it was logged.
+    pub seed: Option<u64>,
+    pub statements: String,
+}
+
+/// A write-ahead log, recording every statement which changes a database before it's executed.
+///
+/// Executing the logged statements again, in order, rebuilds the database. Each statement is
+/// a record with a sequence number, which is increasing, and a checksum. A record is only
+/// whole once it's all been written, so a record cut short (or otherwise mangled) by a crash
+/// while it was being written is the end of the log: its statement was never executed.
+#[derive(Debug)]
+pub struct Wal {
+    file: File,
+    /// The sequence number of the last record.
+    last: u64,
+    /// Where the last record written since the log was opened (or cleared) starts.
+    last_start: Option<u64>,
+}
+
+impl Wal {
+    /// Open a log, creating it if it doesn't exist, and read the records logged after sequence
+    /// number `applied` (whose statements were already applied elsewhere).
+    ///
+    /// Anything after the last whole record is cut off the log, so new records follow on from it.
+    pub fn open(path: &Path, applied: u64) -> Result<(Self, Vec<Record>), Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        if bytes.len() < MAGIC.len() {
+            // a log cut short before its first record is empty
+            if !MAGIC.starts_with(&bytes) {
+                return Err(Error::Corrupt(String::from("not a write-ahead log")));
+            }
+            bytes.clear();
+            bytes.extend_from_slice(MAGIC);
+            file.set_len(0)?;
+            file.write_all(MAGIC)?;
+        } else if &bytes[..MAGIC.len()] != MAGIC {
+            return Err(Error::Corrupt(String::from("not a write-ahead log")));
+        }
+
+        // sequence numbers only increase, so one which doesn't isn't part of the log
+        let mut records = Vec::new();
+        let mut last = 0;
+        let mut end = MAGIC.len();
+        while let Some((record, len)) = read_record(&bytes[end..]) {
+            if record.sequence <= last {
+                break;
+            }
+            last = record.sequence;
+            if record.sequence > applied {
+                records.push(record);
+            }
+
+            end += len;
+        }
+
+        let mut wal = Wal {
+            file,
+            last: last.max(applied),
+            last_start: None,
+        };
+        if end < bytes.len() {
+            wal.file.set_len(end as u64)?;
+            wal.file.sync_data()?;
+        }
+        wal.file.seek(SeekFrom::Start(end as u64))?;
+
+        Ok((wal, records))
+    }
+
+    /// The sequence number of the last record written.
+    pub fn last(&self) -> u64 {
+        self.last
+    }
+
+    /// Add a statement to the end of the log, waiting for it to reach the disk. Returns its
+    /// sequence number.
+    pub fn append(&mut self, statement: &str) -> Result<u64, Error> {
+        self.write_record(statement, None, None)
+    }
+
+    /// Add a statement to the end of the log along with the time it began, which is the current
+    /// time when it's executed again, and the seed of the uuids it generates, which generates the
+    /// same uuids when it's executed again.
+    pub fn append_at(&mut self, statement: &str, time: Timestamp, seed: u64) -> Result<u64, Error> {
+        self.write_record(statement, Some(time), Some(seed))
+    }
+
+    fn write_record(
+        &mut self,
+        statement: &str,
+        time: Option<Timestamp>,
+        seed: Option<u64>,
+    ) -> Result<u64, Error> {
+        let sequence = self.last + 1;
+        let mut len = statement.len() as u32;
+        if time.is_some() {
+            len |= TIMED;
+        }
+        if seed.is_some() {
+            len |= SEEDED;
+        }
+
+        let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + 16 + statement.len());
+        record.extend_from_slice(&sequence.to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&[0; 4]);
+        if let Some(time) = time {
+            record.extend_from_slice(&time.micros().to_le_bytes());
+        }
+        if let Some(seed) = seed {
+            record.extend_from_slice(&seed.to_le_bytes());
+        }
+        record.extend_from_slice(statement.as_bytes());
+        let checksum = checksum(&record);
+        record[12..16].copy_from_slice(&checksum.to_le_bytes());
+
+        let start = self.file.stream_position()?;
+        self.file.write_all(&record)?;
+        self.file.sync_data()?;
+        self.last = sequence;
+        self.last_start = Some(start);
+
+        Ok(sequence)
+    }
+
+    /// Remove every record, once their statements are stored elsewhere. Sequence numbers carry
+    /// on from the last record.
+    pub fn clear(&mut self) -> Result<(), Error> {
+        self.file.set_len(MAGIC.len() as u64)?;
+        self.file.seek(SeekFrom::Start(MAGIC.len() as u64))?;
+        self.file.sync_data()?;
+        self.last_start = None;
+
+        Ok(())
+    }
+
+    /// Remove the last record written, if it's still in the log, once its statements turn out
+    /// not to have changed anything. Sequence numbers carry on from the record before it.
+    pub fn remove_last(&mut self) -> Result<(), Error> {
+        if let Some(start) = self.last_start.take() {
+            self.file.set_len(start)?;
+            self.file.seek(SeekFrom::Start(start))?;
+            self.file.sync_data()?;
+            self.last -= 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// The record at the start of `bytes`, and its length. `None` if there isn't a whole record
+/// there.
+fn read_record(bytes: &[u8]) -> Option<(Record, usize)> {
+    if bytes.len() < RECORD_HEADER_SIZE {
+        return None;
+    }
+
+    let mut sequence = [0; 8];
+    sequence.copy_from_slice(&bytes[..8]);
+    let mut len = [0; 4];
+    len.copy_from_slice(&bytes[8..12]);
+    let mut expected = [0; 4];
+    expected.copy_from_slice(&bytes[12..16]);
+
+    let len = u32::from_le_bytes(len);
+    let timed = len & TIMED != 0;
+    let seeded = len & SEEDED != 0;
+    let body = RECORD_HEADER_SIZE + 8 * (timed as usize + seeded as usize);
+    let len = body + (len & !(TIMED | SEEDED)) as usize;
+    if bytes.len() < len {
+        return None;
+    }
+
+    let mut record = bytes[..len].to_vec();
+    record[12..16].copy_from_slice(&[0; 4]);
+    if checksum(&record) != u32::from_le_bytes(expected) {
+        return None;
+    }
+
+    let statements = String::from_utf8(record.split_off(body)).ok()?;
+    let mut extra = record[RECORD_HEADER_SIZE..].chunks(8).map(|chunk| {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(chunk);
+        bytes
+    });
+    let time = match timed {
+        true => Some(Timestamp::from_micros(i64::from_le_bytes(extra.next()?))?),
+        false => None,
+    };
+    let seed = match seeded {
+        true => Some(u64::from_le_bytes(extra.next()?)),
+        false => None,
+    };
+
+    Some((
+        Record {
+            sequence: u64::from_le_bytes(sequence),
+            time,
+            seed,
+            statements,
+        },
+        len,
+    ))
+}
+
+/// The 32-bit FNV-1a hash of some bytes.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, &byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::execute;
+use sql_rs::data::Database;
+
+#[test]
+fn having_without_group_by_makes_the_whole_table_one_group() {
+    for vectorized in [false, true] {
+        let mut database = Database::new();
+        database.vectorized = vectorized;
+        execute(
+            &mut database,
+            "create table users (id integer primary key, age integer);
+        create table empty (id integer);
+        insert into users (id, age) values (1, 30);
+        insert into users (id, age) values (2, 30);
+        insert into users (id, age) values (3, 40);",
+        );
+
+        for (sql, expected) in [
+            (
+                "select count(*) from users having count(*) > 1;",
+                "count\n3",
+            ),
+            ("select count(*) from users having count(*) > 5;", "count\n"),
+            (
+                "select max(age) from users having min(age) = 30;",
+                "max\n40",
+            ),
+            (
+                "select count(*) from empty having count(*) = 0;",
+                "count\n0",
+            ),
+            (
+                "select * from users having count(*) > 1;",
+                "column \"users.id\" must appear in GROUP BY or be used in an aggregate function",
+            ),
+        ] {
+            assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+        }
+    }
+}
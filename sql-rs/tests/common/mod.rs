@@ -0,0 +1,18 @@
+use sql_rs::data::Database;
+use sql_rs::parse::parse_queries;
+
+/// Execute each statement of `sql` in turn, returning what each printed (its result, or its
+/// error) on a line of its own.
+pub fn execute(database: &mut Database, sql: &str) -> String {
+    let queries = parse_queries(sql).unwrap();
+    let outputs: Vec<_> = queries
+        .0
+        .into_iter()
+        .map(|query| match database.execute(query) {
+            Ok(success) => success.to_string(),
+            Err(e) => e.to_string(),
+        })
+        .collect();
+
+    outputs.join("\n")
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use std::thread;
+
+use common::execute;
+use sql_rs::data::Database;
+
+/// A database with two accounts, holding 100 between them.
+fn accounts() -> Database {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table accounts (id number primary key, balance number);
+        insert into accounts (id, balance) values (1, 100);
+        insert into accounts (id, balance) values (2, 0);",
+    );
+
+    database
+}
+
+#[test]
+fn readers_see_every_transfer_whole_while_one_thread_writes() {
+    let database = accounts();
+
+    let mut writer = database.clone();
+    let writing = thread::spawn(move || {
+        for balance in 0..=100 {
+            let output = execute(
+                &mut writer,
+                &format!(
+                    "begin;
+                    update accounts set balance = {} where id = 1;
+                    update accounts set balance = {} where id = 2;
+                    commit;",
+                    balance,
+                    100 - balance
+                ),
+            );
+            assert!(output.ends_with("COMMIT"), "{}", output);
+        }
+    });
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let mut reader = database.clone();
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    let total = execute(&mut reader, "select sum(balance) from accounts;");
+                    assert_eq!(total, "sum\n100");
+                }
+            })
+        })
+        .collect();
+
+    writing.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    let mut database = database;
+    assert_eq!(
+        execute(&mut database, "select * from accounts order by id;"),
+        "id,balance\n1,100\n2,0"
+    );
+}
+
+#[test]
+fn a_transaction_reads_the_snapshot_taken_when_it_began() {
+    let mut database = accounts();
+    let mut reader = database.clone();
+
+    execute(&mut reader, "begin;");
+    execute(&mut database, "delete from accounts where id = 2;");
+    assert_eq!(
+        execute(&mut reader, "select count(*) from accounts;"),
+        "count\n2"
+    );
+    execute(&mut reader, "commit;");
+
+    assert_eq!(
+        execute(&mut reader, "select count(*) from accounts;"),
+        "count\n1"
+    );
+}
+
+#[test]
+fn changing_a_table_committed_since_the_snapshot_fails() {
+    let mut database = accounts();
+    let mut other = database.clone();
+
+    execute(&mut database, "begin;");
+    execute(&mut other, "update accounts set balance = 50 where id = 1;");
+    assert_eq!(
+        execute(
+            &mut database,
+            "update accounts set balance = 0 where id = 1;"
+        ),
+        "could not serialize access: table `accounts` was changed since the transaction began"
+    );
+    assert_eq!(execute(&mut database, "commit;"), "ROLLBACK");
+
+    assert_eq!(
+        execute(&mut database, "select balance from accounts where id = 1;"),
+        "balance\n50"
+    );
+}
+
+#[test]
+fn a_handle_dropped_in_a_transaction_rolls_it_back() {
+    let mut database = accounts();
+    let mut other = database.clone();
+
+    execute(&mut other, "begin;");
+    execute(
+        &mut other,
+        "insert into accounts (id, balance) values (3, 10);",
+    );
+    drop(other);
+
+    // the dropped handle no longer holds the right to write
+    assert_eq!(
+        execute(
+            &mut database,
+            "insert into accounts (id, balance) values (3, 20);"
+        ),
+        "INSERT 1"
+    );
+    assert_eq!(
+        execute(&mut database, "select balance from accounts where id = 3;"),
+        "balance\n20"
+    );
+}
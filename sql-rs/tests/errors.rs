@@ -0,0 +1,225 @@
+mod common;
+
+use common::execute;
+use sql_rs::data::Database;
+
+/// Two tables, the second referencing the first.
+fn database(vectorized: bool) -> Database {
+    let mut database = Database::new();
+    database.vectorized = vectorized;
+    execute(
+        &mut database,
+        "create table t (id integer primary key, name text, ok boolean);
+        create table r (tid integer references t (id), v integer);
+        insert into t (id, name, ok) values (1, 'a', true);
+        insert into t (id, name, ok) values (2, 'b', false);
+        insert into r (tid, v) values (1, 5);",
+    );
+
+    database
+}
+
+#[test]
+fn tables_and_columns_which_do_not_exist_fail() {
+    for vectorized in [false, true] {
+        let mut database = database(vectorized);
+        for (sql, expected) in [
+            (
+                "select * from missing;",
+                "Table with that name does not exist",
+            ),
+            (
+                "select * from t where id in (select * from missing);",
+                "Table with that name does not exist",
+            ),
+            (
+                "explain select * from missing;",
+                "Table with that name does not exist",
+            ),
+            (
+                "insert into missing (id) values (1);",
+                "Table with that name does not exist",
+            ),
+            (
+                "update missing set a = 1 where true;",
+                "Table with that name does not exist",
+            ),
+            (
+                "delete from missing;",
+                "Table with that name does not exist",
+            ),
+            ("select nope from t;", "column \"nope\" does not exist"),
+            ("select t2.id from t;", "column \"t2.id\" does not exist"),
+            ("select t2.* from t;", "column \"t2.*\" does not exist"),
+            ("select sum(nope) from t;", "column \"nope\" does not exist"),
+            (
+                "select * from t where t.nope = 1;",
+                "column \"t.nope\" does not exist",
+            ),
+            (
+                "select * from t order by nope;",
+                "column \"nope\" does not exist",
+            ),
+            (
+                "select count(*) from t group by nope;",
+                "column \"nope\" does not exist",
+            ),
+            (
+                "select * from t join r on id = nope;",
+                "column \"nope\" does not exist",
+            ),
+            (
+                "select * from t where id in (select nope from r);",
+                "column \"nope\" does not exist",
+            ),
+            ("select * from t limit id;", "column \"id\" does not exist"),
+            (
+                "update t set nope = 1 where true;",
+                "columns must exist in the table",
+            ),
+            (
+                "update t set name = 'z' where nope = 1;",
+                "column \"nope\" does not exist",
+            ),
+            (
+                "delete from t where nope = 1;",
+                "column \"nope\" does not exist",
+            ),
+        ] {
+            assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+        }
+    }
+}
+
+#[test]
+fn conditions_which_are_not_boolean_fail() {
+    for vectorized in [false, true] {
+        let mut database = database(vectorized);
+        for (sql, expected) in [
+            (
+                "select * from t where id;",
+                "a condition must be of type boolean, not integer",
+            ),
+            (
+                "select * from t where ok and name;",
+                "a condition must be of type boolean, not text",
+            ),
+            (
+                "select * from t join r on v;",
+                "a condition must be of type boolean, not integer",
+            ),
+            (
+                "update t set name = 'z' where name;",
+                "a condition must be of type boolean, not text",
+            ),
+            (
+                "delete from t where id;",
+                "a condition must be of type boolean, not integer",
+            ),
+            ("select id from t where ok;", "id\n1"),
+        ] {
+            assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+        }
+    }
+}
+
+#[test]
+fn subqueries_and_limits_which_can_not_be_used_fail() {
+    let mut database = database(false);
+    for (sql, expected) in [
+        (
+            "select * from t where id in (select tid, v from r);",
+            "subquery has too many columns",
+        ),
+        (
+            "select (select id from t) from t;",
+            "a subquery can only be used as the list of values on the right of IN",
+        ),
+        (
+            "select * from t where id = (select tid from r);",
+            "a subquery can only be used as the list of values on the right of IN",
+        ),
+        (
+            "delete from t where (select tid from r) + 1 > 0;",
+            "a subquery can only be used as the list of values on the right of IN",
+        ),
+        (
+            "select * from t where id in 1;",
+            "operator does not exist: integer in integer",
+        ),
+        (
+            "select * from t limit 'a';",
+            "LIMIT must be an integer, not text",
+        ),
+        (
+            "select * from t limit 1.5;",
+            "LIMIT must be an integer, not numeric",
+        ),
+        (
+            "select * from t limit count(*);",
+            "aggregate functions are not allowed in LIMIT",
+        ),
+        ("select id from t where id in (select tid from r);", "id\n1"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
+
+#[test]
+fn columns_which_are_neither_grouped_nor_aggregated_fail() {
+    for vectorized in [false, true] {
+        let mut database = database(vectorized);
+        for (sql, expected) in [
+            (
+                "select name, count(*) from t group by ok;",
+                "column \"t.name\" must appear in GROUP BY or be used in an aggregate function",
+            ),
+            (
+                "select id from t having count(*) > 1;",
+                "column \"t.id\" must appear in GROUP BY or be used in an aggregate function",
+            ),
+            (
+                "select ok from t group by ok having id > 1;",
+                "column \"t.id\" must appear in GROUP BY or be used in an aggregate function",
+            ),
+            (
+                "select count(*) from t order by name;",
+                "column \"t.name\" must appear in GROUP BY or be used in an aggregate function",
+            ),
+            (
+                "select * from t group by id;",
+                "column \"t.name\" must appear in GROUP BY or be used in an aggregate function",
+            ),
+            (
+                "select t.ok, count(id) from t group by ok order by ok;",
+                "ok,count\nfalse,1\ntrue,1",
+            ),
+            (
+                "select id + 1 from t group by id + 1 order by id + 1;",
+                "id\n2\n3",
+            ),
+            (
+                "select max(id) + 1 from t group by ok order by ok;",
+                "max\n3\n2",
+            ),
+        ] {
+            assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+        }
+    }
+}
+
+#[test]
+fn dropping_the_only_column_of_a_table_fails() {
+    let mut database = Database::new();
+    for (sql, expected) in [
+        ("create table one (a integer, b text);", "CREATED TABLE"),
+        ("alter table one drop column a;", "ALTERED TABLE"),
+        (
+            "alter table one drop column b;",
+            "column `b` is the only column of the table, so can't be dropped",
+        ),
+        ("select * from one;", "b\n"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
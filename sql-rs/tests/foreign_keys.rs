@@ -0,0 +1,76 @@
+mod common;
+
+use common::execute;
+use sql_rs::data::Database;
+
+/// Authors, and their books, posts and reviews, each deleted along with an author differently.
+fn library() -> Database {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table authors (id integer primary key, name text);
+        create table books (id integer primary key, author integer references authors (id) on delete cascade);
+        create table reviews (book integer references books (id) on delete cascade, stars integer);
+        create table posts (id integer, author integer references authors on delete set null);
+        create table awards (author integer references authors (id) on delete restrict, year integer);
+        insert into authors (id, name) values (1, 'ann');
+        insert into authors (id, name) values (2, 'bob');
+        insert into authors (id, name) values (3, 'cat');
+        insert into books (id, author) values (10, 1);
+        insert into books (id, author) values (11, 1);
+        insert into books (id, author) values (12, 2);
+        insert into reviews (book, stars) values (10, 5);
+        insert into reviews (book, stars) values (12, 3);
+        insert into posts (id, author) values (100, 1);
+        insert into posts (id, author) values (101, 2);
+        insert into awards (author, year) values (3, 2020);",
+    );
+
+    database
+}
+
+#[test]
+fn on_delete_cascade_deletes_referencing_rows_in_turn() {
+    let mut database = library();
+    for (sql, expected) in [
+        ("delete from authors where id = 1;", "DELETE 1"),
+        ("select id from books;", "id\n12"),
+        ("select book from reviews;", "book\n12"),
+        ("select id from books where id = 10;", "id\n"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
+
+#[test]
+fn on_delete_set_null_keeps_referencing_rows() {
+    let mut database = library();
+    for (sql, expected) in [
+        ("delete from authors where id = 2;", "DELETE 1"),
+        (
+            "select id, author from posts;",
+            "id,author\n100,1\n101,null",
+        ),
+        ("select id from books;", "id\n10\n11"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
+
+#[test]
+fn on_delete_restrict_leaves_every_table_as_it_was() {
+    let mut database = library();
+    for (sql, expected) in [
+        (
+            "delete from authors;",
+            "key (3) is still referenced from table `awards` by foreign key (author) references authors (id) constraint",
+        ),
+        ("select id from authors;", "id\n1\n2\n3"),
+        ("select id from books;", "id\n10\n11\n12"),
+        ("select id, author from posts;", "id,author\n100,1\n101,2"),
+        ("delete from authors where id < 3;", "DELETE 2"),
+        ("select id from authors;", "id\n3"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::execute;
+use sql_rs::data::Database;
+
+#[test]
+fn indexes_find_rows_after_they_are_updated_and_deleted() {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table t (id integer primary key, name text, n integer);
+        create index t_name on t using hash (name);
+        create index t_n on t (n);
+        create table r (tid integer references t (id) on delete cascade, v integer);
+        create index r_tid on r (tid);
+        insert into t (id, name, n) values (1, 'a', 10);
+        insert into t (id, name, n) values (2, 'b', 20);
+        insert into t (id, name, n) values (3, 'c', 30);
+        insert into t (id, name, n) values (4, 'd', 40);
+        insert into r (tid, v) values (1, 100);
+        insert into r (tid, v) values (2, 200);
+        insert into r (tid, v) values (4, 400);
+        update t set name = 'z', n = 5 where id = 3;
+        delete from t where id = 2;",
+    );
+
+    for (sql, expected) in [
+        ("select id from t where id = 4;", "id\n4"),
+        ("select id from t where id = 2;", "id\n"),
+        ("select id from t where name = 'z';", "id\n3"),
+        ("select id from t where name = 'c';", "id\n"),
+        ("select id from t where name = 'd';", "id\n4"),
+        ("select id from t where n < 15;", "id\n1\n3"),
+        ("select id from t where n > 35;", "id\n4"),
+        ("select v from r where tid = 4;", "v\n400"),
+        ("select v from r where tid = 2;", "v\n"),
+        // the key a row was moved from is free, and the key it was moved to isn't
+        ("update t set id = 2 where id = 3;", "UPDATE 1"),
+        (
+            "update t set id = 4 where id = 1;",
+            "duplicate key (4) violates primary key (id) constraint",
+        ),
+        ("select id, name from t where id = 2;", "id,name\n2,z"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use common::execute;
+use sql_rs::data::Database;
+
+#[test]
+fn using_joins_columns_of_different_datatypes_the_same_as_on() {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table a (id integer, day date, ref uuid);
+        create table c (id real, day text, ref text);
+        insert into a (id, day, ref) values (2, date '2024-01-01', uuid 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11');
+        insert into a (id, day, ref) values (3, date '2024-01-02', uuid 'b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11');
+        insert into c (id, day, ref) values (2.0, '2024-01-01', 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11');
+        insert into c (id, day, ref) values (4.5, '2024-01-03', 'a');",
+    );
+
+    for (using, on) in [
+        (
+            "select a.id from a join c using (id);",
+            "select a.id from a join c on a.id = c.id;",
+        ),
+        (
+            "select a.id from a join c using (day);",
+            "select a.id from a join c on a.day = c.day;",
+        ),
+        (
+            "select a.id, c.id from a full join c using (id);",
+            "select a.id, c.id from a full join c on a.id = c.id;",
+        ),
+        (
+            "select a.id from a natural join c;",
+            "select a.id from a join c on a.id = c.id and a.day = c.day and a.ref = c.ref;",
+        ),
+    ] {
+        assert_eq!(
+            execute(&mut database, using),
+            execute(&mut database, on),
+            "{}",
+            using
+        );
+    }
+    assert_eq!(
+        execute(&mut database, "select id, a.day from a join c using (id);"),
+        "id,day\n2,2024-01-01"
+    );
+
+    // text which isn't a uuid fails to compare with one
+    for sql in [
+        "select a.id from a join c using (ref);",
+        "select a.id from a join c on a.ref = c.ref;",
+    ] {
+        assert_eq!(
+            execute(&mut database, sql),
+            "invalid input syntax for type uuid: \"a\"",
+            "{}",
+            sql
+        );
+    }
+}
+
+/// The rows of a query's output in order, after its header, since each join method may produce
+/// them in a different order.
+fn sorted_rows(output: String) -> Vec<String> {
+    let mut lines: Vec<_> = output.lines().map(str::to_owned).collect();
+    lines[1..].sort();
+
+    lines
+}
+
+#[test]
+fn hash_and_merge_joins_find_the_same_rows_as_a_nested_loop() {
+    let mut database = Database::new();
+    // `s` is stored in order of `k`, so a join scanning it as the inner table merges, and `u`
+    // isn't, so a join scanning it hashes
+    execute(
+        &mut database,
+        "create table s (k integer, x integer);
+        create table u (k integer, y integer);
+        insert into s (k, x) values (1, 1);
+        insert into s (k, x) values (2, 2);
+        insert into s (k, x) values (2, 3);
+        insert into s (k, x) values (4, 4);
+        insert into u (k, y) values (3, 30);
+        insert into u (k, y) values (2, 20);
+        insert into u (k, y) values (1, 10);
+        insert into u (k, y) values (2, 21);
+        insert into u (y) values (40);",
+    );
+
+    for (inner, method) in [("s", "merge join"), ("u", "hash join")] {
+        let explain = execute(
+            &mut database,
+            &format!("explain select * from u join {} as i on u.k = i.k;", inner),
+        );
+        assert!(explain.contains(method), "{}", explain);
+    }
+
+    let tables = [("s", "u"), ("u", "s"), ("s", "s"), ("u", "u")];
+    for kind in ["join", "left join", "right join", "full join"] {
+        for (left, right) in tables {
+            let equi = format!(
+                "select * from {} as l {} {} as r on l.k = r.k;",
+                left, kind, right
+            );
+            let nested = format!(
+                "select * from {} as l {} {} as r on l.k >= r.k and l.k <= r.k;",
+                left, kind, right
+            );
+
+            assert!(execute(&mut database, &format!("explain {}", nested)).contains("nested loop"));
+            assert_eq!(
+                sorted_rows(execute(&mut database, &equi)),
+                sorted_rows(execute(&mut database, &nested)),
+                "{}",
+                equi
+            );
+        }
+    }
+}
+
+#[test]
+fn using_and_natural_joins_merge_the_columns_they_join_on() {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table users (id integer, name text);
+        create table orders (id integer, total integer);
+        create table tags (tag text);
+        insert into users (id, name) values (1, 'ann');
+        insert into users (id, name) values (2, 'bob');
+        insert into orders (id, total) values (2, 20);
+        insert into orders (id, total) values (3, 30);
+        insert into tags (tag) values ('x');",
+    );
+
+    for (sql, expected) in [
+        (
+            "select * from users join orders using (id);",
+            "id,name,total\n2,bob,20",
+        ),
+        // the merged column is whichever of the two isn't null
+        (
+            "select * from users full join orders using (id);",
+            "id,name,total\n1,ann,null\n2,bob,20\n3,null,30",
+        ),
+        (
+            "select id, users.id, orders.id from users full join orders using (id);",
+            "id,id,id\n1,1,null\n2,2,2\n3,null,3",
+        ),
+        (
+            "select * from users natural left join orders;",
+            "id,name,total\n1,ann,null\n2,bob,20",
+        ),
+        (
+            "select * from users natural right join orders;",
+            "id,name,total\n2,bob,20\n3,null,30",
+        ),
+        (
+            "select users.* from users join orders using (id);",
+            "id,name\n2,bob",
+        ),
+        (
+            "select total from users as u join orders as o using (id) where id = 2;",
+            "total\n20",
+        ),
+        // with no columns in common, a natural join pairs every row with every other
+        (
+            "select * from users natural join tags;",
+            "id,name,tag\n1,ann,x\n2,bob,x",
+        ),
+        (
+            "select * from users join orders using (name);",
+            "column `name` in USING join does not exist in both tables",
+        ),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
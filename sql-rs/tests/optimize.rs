@@ -0,0 +1,257 @@
+mod common;
+
+use common::execute;
+use sql_rs::data::Database;
+
+/// Users, some of whom have orders, and an order of a user who doesn't exist.
+fn shop() -> Database {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table users (id integer primary key, name text, age integer);
+        create table orders (id integer primary key, user_id integer, total integer);
+        insert into users (id, name, age) values (1, 'ann', 30);
+        insert into users (id, name, age) values (2, 'bob', 17);
+        insert into users (id, name, age) values (3, 'cat', 45);
+        insert into orders (id, user_id, total) values (10, 1, 100);
+        insert into orders (id, user_id, total) values (11, 1, 5);
+        insert into orders (id, user_id, total) values (12, 4, 70);",
+    );
+
+    database
+}
+
+/// Two tables sharing an `id` column, which only some of their rows match on.
+fn shared_ids() -> Database {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table a (id integer, x integer);
+        create table b (id integer, y integer);
+        insert into a (id, x) values (1, 10);
+        insert into a (id, x) values (2, 20);
+        insert into b (id, y) values (2, 200);
+        insert into b (id, y) values (3, 300);",
+    );
+
+    database
+}
+
+/// The plan of a select query, and its rows.
+fn plan_and_rows(database: &mut Database, query: &str) -> (String, String) {
+    (
+        execute(database, &format!("explain {}", query)),
+        execute(database, query),
+    )
+}
+
+#[test]
+fn filter_on_preserved_side_of_left_join_is_pushed_into_its_scan() {
+    let (plan, rows) = plan_and_rows(
+        &mut shop(),
+        "select users.name, orders.total from users
+        left join orders on users.id = orders.user_id where users.age > 20;",
+    );
+
+    assert_eq!(
+        plan,
+        "plan
+project users.name, orders.total
+-> left join on users.id = orders.user_id (merge join)
+  -> scan users where users.age > 20
+  -> scan orders (user_id, total)"
+    );
+    assert_eq!(rows, "name,total\nann,100\nann,5\ncat,null");
+}
+
+#[test]
+fn filter_on_null_supplying_side_of_left_join_stays_above_it() {
+    let (plan, rows) = plan_and_rows(
+        &mut shop(),
+        "select users.name, orders.total from users
+        left join orders on users.id = orders.user_id where orders.total > 50;",
+    );
+
+    // filtering the orders first would keep bob and cat, with null totals
+    assert_eq!(
+        plan,
+        "plan
+project users.name, orders.total
+-> filter orders.total > 50
+  -> left join on users.id = orders.user_id (merge join)
+    -> scan users (id, name)
+    -> scan orders (user_id, total)"
+    );
+    assert_eq!(rows, "name,total\nann,100");
+}
+
+#[test]
+fn on_condition_of_left_join_only_filters_the_null_supplying_side() {
+    let (plan, rows) = plan_and_rows(
+        &mut shop(),
+        "select users.name, orders.total from users
+        left join orders on users.id = orders.user_id and orders.total > 50;",
+    );
+
+    assert_eq!(
+        plan,
+        "plan
+project users.name, orders.total
+-> left join on users.id = orders.user_id (merge join)
+  -> scan users (id, name)
+  -> scan orders (user_id, total) where orders.total > 50"
+    );
+    assert_eq!(rows, "name,total\nann,100\nbob,null\ncat,null");
+}
+
+#[test]
+fn filter_on_null_supplying_side_of_right_join_stays_above_it() {
+    let (plan, rows) = plan_and_rows(
+        &mut shop(),
+        "select users.name, orders.total from users
+        right join orders on users.id = orders.user_id where users.age > 20;",
+    );
+
+    assert_eq!(
+        plan,
+        "plan
+project users.name, orders.total
+-> filter users.age > 20
+  -> right join on users.id = orders.user_id (merge join)
+    -> scan users
+    -> scan orders (user_id, total)"
+    );
+    assert_eq!(rows, "name,total\nann,100\nann,5");
+}
+
+#[test]
+fn filter_on_either_side_of_full_join_stays_above_it() {
+    let (plan, rows) = plan_and_rows(
+        &mut shop(),
+        "select users.name, orders.total from users
+        full join orders on users.id = orders.user_id where orders.total < 80;",
+    );
+
+    assert_eq!(
+        plan,
+        "plan
+project users.name, orders.total
+-> filter orders.total < 80
+  -> full join on users.id = orders.user_id (merge join)
+    -> scan users (id, name)
+    -> scan orders (user_id, total)"
+    );
+    assert_eq!(rows, "name,total\nann,5\nnull,70");
+}
+
+#[test]
+fn filter_on_merged_column_of_full_join_using_stays_above_it() {
+    let (plan, rows) = plan_and_rows(
+        &mut shared_ids(),
+        "select * from a full join b using (id) where id = 3;",
+    );
+
+    // the merged id is b's where a has no matching row, so it can't be pushed into a's scan
+    assert_eq!(
+        plan,
+        "plan
+project *
+-> filter id = 3
+  -> full join using (id) (merge join)
+    -> scan a
+    -> scan b"
+    );
+    assert_eq!(rows, "id,x,y\n3,null,300");
+}
+
+#[test]
+fn filter_on_merged_column_of_natural_right_join_stays_above_it() {
+    let (plan, rows) = plan_and_rows(
+        &mut shared_ids(),
+        "select * from a natural right join b where id > 1;",
+    );
+
+    assert_eq!(
+        plan,
+        "plan
+project *
+-> filter id > 1
+  -> natural right join (merge join)
+    -> scan a
+    -> scan b"
+    );
+    assert_eq!(rows, "id,x,y\n2,20,200\n3,null,300");
+}
+
+#[test]
+fn filter_on_merged_column_of_left_join_using_keeps_unmatched_rows() {
+    let (_, rows) = plan_and_rows(
+        &mut shared_ids(),
+        "select * from a left join b using (id) where id = 1;",
+    );
+
+    assert_eq!(rows, "id,x,y\n1,10,null");
+}
+
+#[test]
+fn constant_conditions_are_folded() {
+    let mut database = shop();
+
+    let (plan, rows) = plan_and_rows(
+        &mut database,
+        "select name from users where age > 10 + 10 and 1 = 1;",
+    );
+    assert_eq!(
+        plan,
+        "plan\nproject name\n-> scan users (name, age) where age > 20"
+    );
+    assert_eq!(rows, "name\nann\ncat");
+
+    let (plan, rows) = plan_and_rows(&mut database, "select name from users where 1 = 2;");
+    assert_eq!(
+        plan,
+        "plan\nproject name\n-> filter false\n  -> scan users (name)"
+    );
+    assert_eq!(rows, "name\n");
+}
+
+#[test]
+fn explain_shows_how_rows_are_found_and_joined_without_executing() {
+    let mut database = shop();
+
+    assert_eq!(
+        execute(
+            &mut database,
+            "explain select * from orders join users on users.id = orders.user_id
+            where users.id = 1;"
+        ),
+        "plan
+project *
+-> join on users.id = orders.user_id (merge join)
+  -> scan orders
+  -> scan users where users.id = 1 (index lookup using users_pkey)"
+    );
+    // orders aren't stored in order of their totals
+    assert_eq!(
+        execute(
+            &mut database,
+            "explain select * from users join orders on users.age = orders.total;"
+        ),
+        "plan
+project *
+-> join on users.age = orders.total (hash join)
+  -> scan users
+  -> scan orders"
+    );
+    assert_eq!(
+        execute(
+            &mut database,
+            "explain select * from users join orders on users.age > orders.total;"
+        ),
+        "plan
+project *
+-> join on users.age > orders.total (nested loop join)
+  -> scan users
+  -> scan orders"
+    );
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::execute;
+use sql_rs::data::Database;
+
+fn database() -> Database {
+    let mut database = Database::new();
+    execute(&mut database, "create table t (id integer primary key);");
+
+    database
+}
+
+#[test]
+fn rolling_back_to_a_savepoint_undoes_only_the_changes_since() {
+    let mut database = database();
+    for (sql, expected) in [
+        ("begin;", "BEGIN"),
+        ("insert into t (id) values (1);", "INSERT 1"),
+        ("savepoint a;", "SAVEPOINT"),
+        ("insert into t (id) values (2);", "INSERT 1"),
+        ("savepoint b;", "SAVEPOINT"),
+        ("insert into t (id) values (3);", "INSERT 1"),
+        ("rollback to savepoint a;", "ROLLBACK"),
+        ("select id from t;", "id\n1"),
+        // the savepoints made since are gone, but the one rolled back to is kept
+        ("rollback to savepoint b;", "savepoint `b` does not exist"),
+        ("insert into t (id) values (4);", "INSERT 1"),
+        ("rollback to a;", "ROLLBACK"),
+        ("commit;", "COMMIT"),
+        ("select id from t;", "id\n1"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
+
+#[test]
+fn rolling_back_to_a_savepoint_recovers_from_a_failed_query() {
+    let mut database = database();
+    for (sql, expected) in [
+        ("begin;", "BEGIN"),
+        ("insert into t (id) values (1);", "INSERT 1"),
+        ("savepoint a;", "SAVEPOINT"),
+        (
+            "insert into t (id) values (1);",
+            "duplicate key (1) violates primary key (id) constraint",
+        ),
+        (
+            "select id from t;",
+            "a query in the transaction failed, so queries are ignored until it's rolled back (or rolled back to a savepoint)",
+        ),
+        ("rollback to savepoint a;", "ROLLBACK"),
+        ("insert into t (id) values (2);", "INSERT 1"),
+        ("commit;", "COMMIT"),
+        ("select id from t;", "id\n1\n2"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
+
+#[test]
+fn releasing_a_savepoint_keeps_its_changes_and_forgets_it() {
+    let mut database = database();
+    for (sql, expected) in [
+        ("begin;", "BEGIN"),
+        ("savepoint a;", "SAVEPOINT"),
+        ("insert into t (id) values (1);", "INSERT 1"),
+        ("savepoint b;", "SAVEPOINT"),
+        ("insert into t (id) values (2);", "INSERT 1"),
+        // releasing a savepoint releases every one made after it too
+        ("release savepoint a;", "RELEASE"),
+        ("rollback to savepoint a;", "savepoint `a` does not exist"),
+        ("rollback to savepoint b;", "savepoint `b` does not exist"),
+        ("commit;", "COMMIT"),
+        ("select id from t;", "id\n1\n2"),
+        ("savepoint c;", "there is no transaction in progress"),
+        ("release c;", "there is no transaction in progress"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
+
+#[test]
+fn rolling_back_a_transaction_undoes_every_savepoint() {
+    let mut database = database();
+    for (sql, expected) in [
+        ("begin;", "BEGIN"),
+        ("insert into t (id) values (1);", "INSERT 1"),
+        ("savepoint a;", "SAVEPOINT"),
+        ("insert into t (id) values (2);", "INSERT 1"),
+        ("release a;", "RELEASE"),
+        ("rollback;", "ROLLBACK"),
+        ("select id from t;", "id\n"),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
@@ -0,0 +1,237 @@
+mod common;
+
+use common::execute;
+use sql_rs::data::Database;
+
+/// Events on two days, one of them indexed by day.
+fn events(vectorized: bool) -> Database {
+    let mut database = Database::new();
+    database.vectorized = vectorized;
+    execute(
+        &mut database,
+        "create table events (id integer, day date, name text);
+        create index events_day on events (day);
+        insert into events (id, day, name) values (1, date '2024-01-01', 'a');
+        insert into events (id, day, name) values (2, date '2024-02-01', 'b');",
+    );
+
+    database
+}
+
+#[test]
+fn text_compared_with_a_date_is_parsed_as_one() {
+    for vectorized in [false, true] {
+        let mut database = events(vectorized);
+        for (sql, expected) in [
+            ("select id from events where day = '2024-01-01';", "id\n1"),
+            ("select id from events where day > '2024-01-01';", "id\n2"),
+            ("select id from events where '2024-01-15' >= day;", "id\n1"),
+            (
+                "select id from events where day < '2024-13-01';",
+                "invalid input syntax for type date: \"2024-13-01\"",
+            ),
+        ] {
+            assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+        }
+    }
+}
+
+#[test]
+fn operators_on_values_they_are_not_defined_for_fail() {
+    for vectorized in [false, true] {
+        let mut database = events(vectorized);
+        for (sql, expected) in [
+            (
+                "select id from events where day > 1;",
+                "operator does not exist: date > integer",
+            ),
+            (
+                "select day + 'x' from events;",
+                "operator does not exist: date + text",
+            ),
+            (
+                "select sum(name) from events;",
+                "function sum(text) does not exist",
+            ),
+            (
+                "select avg(name) from events where id = 1;",
+                "function avg(text) does not exist",
+            ),
+            // values of different datatypes are never equal
+            ("select id from events where id = 'a';", "id\n"),
+        ] {
+            assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+        }
+    }
+}
+
+#[test]
+fn text_compared_with_a_uuid_is_parsed_as_one() {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table accounts (id uuid primary key, name text);
+        insert into accounts (id, name) values ('a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'alice');
+        insert into accounts (id, name) values ('b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', 'bob');
+        create table refs (ref text);
+        insert into refs (ref) values ('b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11');
+        insert into refs (ref) values ('c0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11');",
+    );
+
+    for (sql, expected) in [
+        (
+            "select name from accounts where id = 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11';",
+            "name\nalice",
+        ),
+        (
+            "select name from accounts where id = 'A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11';",
+            "name\nalice",
+        ),
+        (
+            "select name from accounts where id > 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11';",
+            "name\nbob",
+        ),
+        // the uuids a subquery gives as text
+        (
+            "select name from accounts where id in (select ref from refs);",
+            "name\nbob",
+        ),
+        (
+            "select name from accounts where id > 'a';",
+            "invalid input syntax for type uuid: \"a\"",
+        ),
+        (
+            "select name from accounts where id = 'a';",
+            "invalid input syntax for type uuid: \"a\"",
+        ),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+
+    // the key is still looked up in the primary key's index
+    let plan = execute(
+        &mut database,
+        "explain select name from accounts where id = 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11';",
+    );
+    assert!(
+        plan.contains("index lookup using accounts_pkey"),
+        "{}",
+        plan
+    );
+}
+
+#[test]
+fn reals_are_written_with_an_exponent_once_large_or_small() {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        "create table t (id integer, x real);
+        insert into t (id, x) values (1, 1e300);
+        insert into t (id, x) values (2, -1.5e-5);
+        insert into t (id, x) values (3, 123456789012345e0);
+        insert into t (id, x) values (4, 1234567890123456e0);
+        insert into t (id, x) values (5, 0.0001e0);
+        insert into t (id, x) values (6, 0e0);",
+    );
+
+    assert_eq!(
+        execute(&mut database, "select x from t;"),
+        "x\n1e+300\n-1.5e-05\n123456789012345\n1.234567890123456e+15\n0.0001\n0"
+    );
+}
+
+#[test]
+fn integers_are_averaged_even_when_their_sum_overflows() {
+    for vectorized in [false, true] {
+        let mut database = Database::new();
+        database.vectorized = vectorized;
+        execute(
+            &mut database,
+            "create table t (n integer);
+            insert into t (n) values (9223372036854775807);
+            insert into t (n) values (9223372036854775807);
+            insert into t (n) values (-9223372036854775807);",
+        );
+
+        for (sql, expected) in [
+            ("select avg(n) from t;", "avg\n3.0744573456182584e+18"),
+            ("select sum(n) from t;", "sum\n9223372036854775807"),
+            ("insert into t (n) values (1);", "INSERT 1"),
+            ("select sum(n) from t;", "integer out of range"),
+        ] {
+            assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+        }
+    }
+}
+
+#[test]
+fn json_fields_and_elements_are_looked_up_and_json_is_ordered_by_kind() {
+    let mut database = Database::new();
+    execute(
+        &mut database,
+        r#"create table docs (id integer, doc json);
+        insert into docs (id, doc) values (1, json '{"name": "ann", "age": 30, "tags": ["a", "b"], "address": {"city": "oslo"}}');
+        insert into docs (id, doc) values (2, json '{"name": "bob", "age": 4, "tags": []}');
+        insert into docs (id, doc) values (3, json '[1, 2, 3]');
+        create table kinds (id integer, doc json);
+        insert into kinds (id, doc) values (1, json '{"a": 1}');
+        insert into kinds (id, doc) values (2, json '[1]');
+        insert into kinds (id, doc) values (3, json 'true');
+        insert into kinds (id, doc) values (4, json 'false');
+        insert into kinds (id, doc) values (5, json '10');
+        insert into kinds (id, doc) values (6, json '2');
+        insert into kinds (id, doc) values (7, json '"b"');
+        insert into kinds (id, doc) values (8, json '"a"');
+        insert into kinds (id, doc) values (9, json 'null');"#,
+    );
+
+    for (sql, expected) in [
+        (
+            "select doc -> 'name' from docs;",
+            "doc\n\"ann\"\n\"bob\"\nnull",
+        ),
+        ("select doc ->> 'name' from docs;", "doc\nann\nbob\nnull"),
+        (
+            "select doc -> 'tags' -> 1 from docs;",
+            "doc\n\"b\"\nnull\nnull",
+        ),
+        ("select doc -> -1 from docs;", "doc\nnull\nnull\n3"),
+        (
+            "select doc -> 'address' ->> 'city' from docs;",
+            "doc\noslo\nnull\nnull",
+        ),
+        (
+            "select json_extract(doc, '$.address.city') from docs;",
+            "json_extract\n\"oslo\"\nnull\nnull",
+        ),
+        (
+            "select json_extract(doc, '$.tags[0]') from docs;",
+            "json_extract\n\"a\"\nnull\nnull",
+        ),
+        (
+            "select json_extract(doc, 'name') from docs;",
+            "invalid json path: \"name\"",
+        ),
+        ("select id from docs where doc ->> 'name' = 'bob';", "id\n2"),
+        (
+            "select id from docs where doc -> 'age' > json '5';",
+            "id\n1",
+        ),
+        // numbers are ordered as numbers, not as text
+        (
+            "select id from docs where id < 3 order by doc -> 'age';",
+            "id\n2\n1",
+        ),
+        (
+            "select id from docs where id < 3 order by doc ->> 'age';",
+            "id\n1\n2",
+        ),
+        // null, then strings, numbers, booleans, arrays and objects
+        (
+            "select doc from kinds order by doc;",
+            "doc\nnull\n\"a\"\n\"b\"\n2\n10\nfalse\ntrue\n[1]\n{\"a\": 1}",
+        ),
+    ] {
+        assert_eq!(execute(&mut database, sql), expected, "{}", sql);
+    }
+}
@@ -0,0 +1,378 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use common::execute;
+use sql_rs::data::Database;
+use sql_rs::storage::{Storage, Wal};
+
+const STATEMENTS: &[&str] = &[
+    "create table users (id number primary key, email text unique, age number default 30);",
+    "insert into users (id, email, age) values (1, 'a@example.com', 21);",
+    "insert into users (id, email) values (2, 'b@example.com');",
+    "insert into users (id, email, age) values (3, 'c@example.com', 40);",
+    "update users set age = 50 where id = 2;",
+    "delete from users where age < 30;",
+    "alter table users add column active boolean default true;",
+    "insert into users (id, email, age, active) values (4, 'd@example.com', 60, false);",
+];
+
+/// A path in the temporary directory unique to this test, with nothing at it.
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("sql-rs-{}-{}", process::id(), name));
+    let _ = fs::remove_file(&path);
+
+    path
+}
+
+fn users(database: &mut Database) -> String {
+    execute(database, "select * from users;")
+}
+
+/// The rows of `users` after executing the first `count` statements, in memory alone.
+fn expected_users(count: usize) -> String {
+    let mut database = Database::new();
+    for statement in &STATEMENTS[..count] {
+        execute(&mut database, statement);
+    }
+
+    match count {
+        0 => String::new(),
+        _ => users(&mut database),
+    }
+}
+
+/// Execute every statement with a fresh log at `path`, returning the length of the log after
+/// each.
+fn write_log(path: &Path) -> Vec<u64> {
+    let mut database = Database::new();
+    database.open_wal(path).unwrap();
+
+    let mut lengths = vec![fs::metadata(path).unwrap().len()];
+    for statement in STATEMENTS {
+        execute(&mut database, statement);
+        lengths.push(fs::metadata(path).unwrap().len());
+    }
+
+    lengths
+}
+
+/// Open a new database replaying the log at `path`, returning the number of statements
+/// replayed and the rows of `users`.
+fn replay(path: &Path) -> (usize, String) {
+    let mut database = Database::new();
+    let replayed = database.open_wal(path).unwrap();
+
+    let users = match database.snapshot().contains_key("users") {
+        true => users(&mut database),
+        false => String::new(),
+    };
+
+    (replayed, users)
+}
+
+#[test]
+fn replaying_the_log_rebuilds_the_tables() {
+    let path = temp_path("replay.wal");
+    write_log(&path);
+
+    assert_eq!(
+        replay(&path),
+        (STATEMENTS.len(), expected_users(STATEMENTS.len()))
+    );
+}
+
+#[test]
+fn truncating_between_records_loses_only_later_statements() {
+    let path = temp_path("between.wal");
+    let lengths = write_log(&path);
+    let log = fs::read(&path).unwrap();
+
+    for (count, &len) in lengths.iter().enumerate() {
+        fs::write(&path, &log[..len as usize]).unwrap();
+
+        assert_eq!(replay(&path), (count, expected_users(count)));
+    }
+}
+
+#[test]
+fn a_record_cut_short_is_discarded() {
+    let path = temp_path("torn.wal");
+    let lengths = write_log(&path);
+    let log = fs::read(&path).unwrap();
+
+    // crash at every point while writing each record
+    for count in 0..STATEMENTS.len() {
+        for len in lengths[count] + 1..lengths[count + 1] {
+            fs::write(&path, &log[..len as usize]).unwrap();
+
+            assert_eq!(replay(&path), (count, expected_users(count)));
+            assert_eq!(fs::metadata(&path).unwrap().len(), lengths[count]);
+        }
+    }
+}
+
+#[test]
+fn statements_after_a_torn_record_are_kept() {
+    let path = temp_path("after-torn.wal");
+    let lengths = write_log(&path);
+    let log = fs::read(&path).unwrap();
+
+    // the last insert is torn, then executed again once the database is rebuilt
+    let count = STATEMENTS.len() - 1;
+    fs::write(&path, &log[..lengths[count] as usize + 5]).unwrap();
+    let mut database = Database::new();
+    database.open_wal(&path).unwrap();
+    execute(&mut database, STATEMENTS[count]);
+
+    assert_eq!(
+        replay(&path),
+        (STATEMENTS.len(), expected_users(STATEMENTS.len()))
+    );
+}
+
+#[test]
+fn a_corrupt_record_ends_the_log() {
+    let path = temp_path("corrupt.wal");
+    let lengths = write_log(&path);
+    let mut log = fs::read(&path).unwrap();
+
+    // flip a bit in the last byte of the fourth record's statement
+    log[lengths[4] as usize - 1] ^= 1;
+    fs::write(&path, &log).unwrap();
+
+    assert_eq!(replay(&path), (3, expected_users(3)));
+}
+
+#[test]
+fn a_log_cut_short_before_any_record_is_empty() {
+    let path = temp_path("empty.wal");
+    write_log(&path);
+    let log = fs::read(&path).unwrap();
+
+    for len in 0..8 {
+        fs::write(&path, &log[..len]).unwrap();
+
+        assert_eq!(replay(&path), (0, String::new()));
+    }
+}
+
+#[test]
+fn statements_stored_in_the_database_file_are_not_replayed() {
+    let db_path = temp_path("stored.db");
+    let wal_path = temp_path("stored.wal");
+
+    let mut database = Database::open(&db_path).unwrap();
+    database.open_wal(&wal_path).unwrap();
+    for statement in STATEMENTS {
+        execute(&mut database, statement);
+    }
+    drop(database);
+    let applied = Storage::open(&db_path).unwrap().0.applied();
+
+    // crash after logging a statement, but before its changes are stored
+    let (mut wal, statements) = Wal::open(&wal_path, applied).unwrap();
+    assert!(statements.is_empty());
+    wal.append("insert into users (id, email) values (5, 'e@example.com');")
+        .unwrap();
+    drop(wal);
+
+    let mut expected = Database::new();
+    for statement in STATEMENTS {
+        execute(&mut expected, statement);
+    }
+    execute(
+        &mut expected,
+        "insert into users (id, email) values (5, 'e@example.com');",
+    );
+
+    for replayed in [1, 0] {
+        let mut database = Database::open(&db_path).unwrap();
+        assert_eq!(database.open_wal(&wal_path).unwrap(), replayed);
+        assert_eq!(users(&mut database), users(&mut expected));
+    }
+}
+
+#[test]
+fn rows_too_large_for_a_page_are_stored() {
+    let path = temp_path("large.db");
+    let statements = [
+        "create table notes (id integer primary key, body text);".to_owned(),
+        format!(
+            "insert into notes (id, body) values (1, '{}');",
+            "a".repeat(10_000)
+        ),
+        "insert into notes (id, body) values (2, 'short');".to_owned(),
+        format!(
+            "insert into notes (id, body) values (3, '{}');",
+            "b".repeat(5_000)
+        ),
+        format!(
+            "update notes set body = '{}' where id = 1;",
+            "c".repeat(20_000)
+        ),
+        "delete from notes where id = 3;".to_owned(),
+    ];
+
+    let mut database = Database::open(&path).unwrap();
+    let mut expected = Database::new();
+    for statement in &statements {
+        assert_eq!(
+            execute(&mut database, statement),
+            execute(&mut expected, statement)
+        );
+    }
+    drop(database);
+
+    let select = "select id, length(body), substring(body, 1, 3) from notes;";
+    let mut database = Database::open(&path).unwrap();
+    assert_eq!(
+        execute(&mut database, select),
+        execute(&mut expected, select)
+    );
+
+    // the overflow pages of rows written again are reused
+    let len = fs::metadata(&path).unwrap().len();
+    for _ in 0..5 {
+        execute(&mut database, &statements[4]);
+    }
+    assert_eq!(fs::metadata(&path).unwrap().len(), len);
+}
+
+#[test]
+fn a_save_cut_short_before_its_header_is_written_leaves_what_was_stored() {
+    let path = temp_path("cut-short.db");
+    let crashed_path = temp_path("crashed.db");
+    let mut database = Database::open(&path).unwrap();
+    execute(&mut database, STATEMENTS[0]);
+
+    for statement in &STATEMENTS[1..] {
+        let stored = users(&mut database);
+        let before = fs::read(&path).unwrap();
+        execute(&mut database, statement);
+
+        // crash once every page but the header is written
+        let mut crashed = fs::read(&path).unwrap();
+        crashed[..4096].copy_from_slice(&before[..4096]);
+        fs::write(&crashed_path, crashed).unwrap();
+
+        let mut reopened = Database::open(&crashed_path).unwrap();
+        assert_eq!(users(&mut reopened), stored, "{}", statement);
+        // executing the statement again stores its changes over what was left of them
+        execute(&mut reopened, statement);
+        drop(reopened);
+        let mut reopened = Database::open(&crashed_path).unwrap();
+        assert_eq!(users(&mut reopened), users(&mut database), "{}", statement);
+
+        let mut reopened = Database::open(&path).unwrap();
+        assert_eq!(users(&mut reopened), users(&mut database), "{}", statement);
+    }
+}
+
+#[test]
+fn statements_which_failed_fail_again_when_replayed() {
+    let path = temp_path("failed.wal");
+    let mut database = Database::new();
+    database.open_wal(&path).unwrap();
+    execute(&mut database, STATEMENTS[0]);
+    for statement in [
+        "insert into missing (id) values (1);",
+        "update users set age = 1 where nope = 1;",
+        "delete from users where email;",
+    ] {
+        assert_ne!(execute(&mut database, statement), "");
+    }
+    execute(&mut database, STATEMENTS[1]);
+    drop(database);
+
+    assert_eq!(replay(&path), (5, expected_users(2)));
+}
+
+#[test]
+fn only_committed_transactions_are_replayed() {
+    let path = temp_path("transaction.wal");
+    let mut database = Database::new();
+    database.open_wal(&path).unwrap();
+    execute(&mut database, STATEMENTS[0]);
+    let committed = fs::metadata(&path).unwrap().len();
+
+    execute(&mut database, "begin;");
+    for statement in &STATEMENTS[1..4] {
+        execute(&mut database, statement);
+    }
+    // nothing is logged until the transaction commits
+    assert_eq!(fs::metadata(&path).unwrap().len(), committed);
+    execute(&mut database, "commit;");
+    let log = fs::read(&path).unwrap();
+
+    // a transaction which is rolled back, or never ends, isn't logged at all
+    execute(&mut database, "begin;");
+    execute(&mut database, STATEMENTS[4]);
+    execute(&mut database, "rollback;");
+    execute(&mut database, "begin;");
+    execute(&mut database, STATEMENTS[4]);
+    drop(database);
+    assert_eq!(fs::read(&path).unwrap(), log);
+
+    assert_eq!(replay(&path), (2, expected_users(4)));
+
+    // a commit cut short loses the whole transaction
+    fs::write(&path, &log[..log.len() - 1]).unwrap();
+    assert_eq!(replay(&path), (1, expected_users(1)));
+}
+
+#[test]
+fn the_current_time_is_replayed_as_it_was_logged() {
+    let path = temp_path("now.wal");
+    let mut database = Database::new();
+    database.open_wal(&path).unwrap();
+    execute(
+        &mut database,
+        "create table events (id number, at timestamp default now());",
+    );
+    execute(&mut database, "insert into events (id) values (1);");
+    execute(&mut database, "begin;");
+    execute(&mut database, "insert into events (id) values (2);");
+    execute(&mut database, "insert into events (id) values (3);");
+    execute(&mut database, "commit;");
+    let events = execute(&mut database, "select * from events;");
+    drop(database);
+
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    let mut replayed = Database::new();
+    assert_eq!(replayed.open_wal(&path).unwrap(), 3);
+    assert_eq!(execute(&mut replayed, "select * from events;"), events);
+
+    // every statement of a transaction sees the time it began
+    let times: Vec<_> = events.lines().skip(1).map(|row| &row[2..]).collect();
+    assert_ne!(times[0], times[1]);
+    assert_eq!(times[1], times[2]);
+}
+
+#[test]
+fn generated_uuids_are_replayed_as_they_were_logged() {
+    let path = temp_path("uuid.wal");
+    let mut database = Database::new();
+    database.open_wal(&path).unwrap();
+    execute(
+        &mut database,
+        "create table accounts (id uuid primary key default gen_random_uuid(), name text);",
+    );
+    execute(&mut database, "insert into accounts (name) values ('a');");
+    execute(&mut database, "begin;");
+    execute(&mut database, "insert into accounts (name) values ('b');");
+    execute(&mut database, "select gen_random_uuid() from accounts;");
+    execute(&mut database, "savepoint s;");
+    execute(&mut database, "insert into accounts (name) values ('c');");
+    execute(&mut database, "rollback to savepoint s;");
+    execute(&mut database, "insert into accounts (name) values ('d');");
+    execute(&mut database, "commit;");
+    let accounts = execute(&mut database, "select * from accounts;");
+    drop(database);
+
+    let mut replayed = Database::new();
+    assert_eq!(replayed.open_wal(&path).unwrap(), 3);
+    assert_eq!(execute(&mut replayed, "select * from accounts;"), accounts);
+}